@@ -0,0 +1,267 @@
+use crate::db::{BillingLineItem, BillingSummary, Client, RoundingScope};
+use rusqlite::Connection;
+use std::collections::BTreeMap;
+
+pub const CLIENT_COLUMNS: &str = "id, name, email, address, currency, hourly_rate_cents,
+                                  rounding_minutes, rounding_scope, created_at";
+
+pub fn map_client(row: &rusqlite::Row) -> rusqlite::Result<Client> {
+    Ok(Client {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        email: row.get(2)?,
+        address: row.get(3)?,
+        currency: row.get(4)?,
+        hourly_rate_cents: row.get(5)?,
+        rounding_minutes: row.get(6)?,
+        rounding_scope: RoundingScope::parse(&row.get::<_, String>(7)?),
+        created_at: row.get(8)?,
+    })
+}
+
+pub fn get_client(conn: &Connection, id: i64) -> Result<Client, rusqlite::Error> {
+    conn.query_row(
+        &format!("SELECT {} FROM clients WHERE id = ?", CLIENT_COLUMNS),
+        [id],
+        map_client,
+    )
+}
+
+/// Round `seconds` up to a whole multiple of `minutes`. Zero disables rounding.
+pub fn round_up(seconds: i64, minutes: i64) -> i64 {
+    if minutes <= 0 || seconds <= 0 {
+        return seconds.max(0);
+    }
+    let increment = minutes * 60;
+    (seconds + increment - 1) / increment * increment
+}
+
+/// Amount for `seconds` at an hourly rate, rounded to the nearest cent.
+pub fn amount_cents(seconds: i64, hourly_rate_cents: i64) -> i64 {
+    (seconds * hourly_rate_cents + 1800) / 3600
+}
+
+/// Billable time and amounts per project of a client between two local
+/// dates (inclusive).
+///
+/// Project rate and rounding override the client's. With day-scoped rounding
+/// each project's daily total is rounded instead of every record.
+pub fn summarize(
+    conn: &Connection,
+    client_id: i64,
+    start_date: &str,
+    end_date: &str,
+) -> Result<BillingSummary, rusqlite::Error> {
+    let client = get_client(conn, client_id)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT p.id, p.name, COALESCE(p.hourly_rate_cents, c.hourly_rate_cents, 0),
+                COALESCE(p.rounding_minutes, c.rounding_minutes),
+                date(ar.start_time, 'unixepoch', 'localtime'), ar.duration_seconds,
+                COALESCE(ar.is_billable, p.is_billable)
+         FROM activity_records ar
+         JOIN projects p ON ar.project_id = p.id
+         JOIN clients c ON p.client_id = c.id
+         WHERE c.id = ? AND ar.is_idle = 0
+           AND date(ar.start_time, 'unixepoch', 'localtime') >= ?
+           AND date(ar.start_time, 'unixepoch', 'localtime') <= ?
+         ORDER BY p.name, p.id, ar.start_time",
+    )?;
+
+    let rows = stmt
+        .query_map(rusqlite::params![client_id, start_date, end_date], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, i64>(5)?,
+                row.get::<_, i32>(6)? == 1,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut line_items: Vec<BillingLineItem> = Vec::new();
+    // Per line item: (rounding minutes, seconds per day) for day-scoped rounding
+    let mut daily: Vec<(i64, BTreeMap<String, i64>)> = Vec::new();
+    let mut non_billable_seconds = 0;
+
+    for (project_id, project_name, rate, rounding, day, seconds, billable) in rows {
+        if !billable {
+            non_billable_seconds += seconds;
+            continue;
+        }
+
+        if line_items.last().map(|l| l.project_id) != Some(project_id) {
+            line_items.push(BillingLineItem {
+                project_id,
+                project_name,
+                entry_count: 0,
+                tracked_seconds: 0,
+                billed_seconds: 0,
+                hourly_rate_cents: rate,
+                amount_cents: 0,
+            });
+            daily.push((rounding, BTreeMap::new()));
+        }
+
+        let item = line_items.last_mut().expect("line item was just pushed");
+        item.entry_count += 1;
+        item.tracked_seconds += seconds;
+        match client.rounding_scope {
+            RoundingScope::Entry => item.billed_seconds += round_up(seconds, rounding),
+            RoundingScope::Day => {
+                let (_, days) = daily.last_mut().expect("days were just pushed");
+                *days.entry(day).or_insert(0) += seconds;
+            }
+        }
+    }
+
+    for (item, (rounding, days)) in line_items.iter_mut().zip(daily) {
+        if client.rounding_scope == RoundingScope::Day {
+            item.billed_seconds = days.values().map(|&s| round_up(s, rounding)).sum();
+        }
+        item.amount_cents = amount_cents(item.billed_seconds, item.hourly_rate_cents);
+    }
+
+    Ok(BillingSummary {
+        start_date: start_date.to_string(),
+        end_date: end_date.to_string(),
+        currency: client.currency.clone(),
+        tracked_seconds: line_items.iter().map(|l| l.tracked_seconds).sum(),
+        billed_seconds: line_items.iter().map(|l| l.billed_seconds).sum(),
+        non_billable_seconds,
+        total_cents: line_items.iter().map(|l| l.amount_cents).sum(),
+        line_items,
+        client,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    /// Local midnight of 2024-03-04 plus `offset` seconds
+    fn at(offset: i64) -> i64 {
+        use chrono::{Local, NaiveDate, TimeZone};
+        let midnight = NaiveDate::from_ymd_opt(2024, 3, 4)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        Local.from_local_datetime(&midnight).unwrap().timestamp() + offset
+    }
+
+    fn seed(conn: &Connection, scope: &str) {
+        conn.execute(
+            "INSERT INTO clients (id, name, currency, hourly_rate_cents, rounding_minutes, rounding_scope)
+             VALUES (1, 'Acme', 'EUR', 10000, 15, ?)",
+            [scope],
+        )
+        .expect("Failed to insert client");
+        conn.execute_batch(
+            "INSERT INTO projects (id, name, client_id) VALUES (1, 'Website', 1);
+             INSERT INTO projects (id, name, client_id, hourly_rate_cents, rounding_minutes)
+                 VALUES (2, 'Support', 1, 6000, 6);",
+        )
+        .expect("Failed to insert projects");
+
+        // Website: two 10 minute entries, one non-billable 30 minute entry.
+        // Support: one 20 minute entry.
+        for (start, minutes, project, billable) in [
+            (9 * 3600, 10, 1, None),
+            (10 * 3600, 10, 1, None),
+            (11 * 3600, 30, 1, Some(0)),
+            (12 * 3600, 20, 2, None),
+        ] {
+            conn.execute(
+                "INSERT INTO activity_records (window_title, start_time, end_time, project_id, is_billable)
+                 VALUES ('work', ?, ?, ?, ?)",
+                rusqlite::params![at(start), at(start + minutes * 60), project, billable],
+            )
+            .expect("Failed to insert record");
+        }
+    }
+
+    #[test]
+    fn test_round_up() {
+        assert_eq!(round_up(0, 15), 0);
+        assert_eq!(round_up(1, 6), 360);
+        assert_eq!(round_up(900, 15), 900);
+        assert_eq!(round_up(901, 15), 1800);
+        assert_eq!(round_up(123, 0), 123);
+    }
+
+    #[test]
+    fn test_summary_rounds_per_entry() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+        seed(&conn, "entry");
+
+        let summary = summarize(&conn, 1, "2024-03-04", "2024-03-04").expect("Failed to summarize");
+        assert_eq!(summary.currency, "EUR");
+        assert_eq!(summary.line_items.len(), 2);
+        assert_eq!(summary.non_billable_seconds, 1800);
+
+        let support = &summary.line_items[0];
+        assert_eq!(support.project_name, "Support");
+        assert_eq!(support.billed_seconds, 1440);
+        assert_eq!(support.amount_cents, 2400);
+
+        let website = &summary.line_items[1];
+        assert_eq!(website.tracked_seconds, 1200);
+        assert_eq!(website.billed_seconds, 1800);
+        assert_eq!(website.amount_cents, 5000);
+        assert_eq!(summary.total_cents, 7400);
+    }
+
+    #[test]
+    fn test_summary_rounds_per_day() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+        seed(&conn, "day");
+
+        let summary = summarize(&conn, 1, "2024-03-04", "2024-03-04").expect("Failed to summarize");
+        let website = &summary.line_items[1];
+        assert_eq!(website.billed_seconds, 1800);
+        assert_eq!(summary.billed_seconds, 3240);
+
+        conn.execute("UPDATE clients SET rounding_minutes = 60", [])
+            .expect("Failed to update client");
+        let summary = summarize(&conn, 1, "2024-03-04", "2024-03-04").expect("Failed to summarize");
+        assert_eq!(summary.line_items[1].billed_seconds, 3600);
+        // Project override still applies
+        assert_eq!(summary.line_items[0].billed_seconds, 1440);
+    }
+
+    #[test]
+    fn test_projects_sharing_a_name_stay_separate() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+        seed(&conn, "entry");
+        conn.execute(
+            "INSERT INTO projects (id, name, client_id, hourly_rate_cents)
+             VALUES (3, 'Website', 1, 20000)",
+            [],
+        )
+        .expect("Failed to insert project");
+        // Starts between the other Website project's entries
+        conn.execute(
+            "INSERT INTO activity_records (window_title, start_time, end_time, project_id)
+             VALUES ('work', ?, ?, 3)",
+            [at(9 * 3600 + 1800), at(9 * 3600 + 2400)],
+        )
+        .expect("Failed to insert record");
+
+        let summary = summarize(&conn, 1, "2024-03-04", "2024-03-04").expect("Failed to summarize");
+        assert_eq!(summary.line_items.len(), 3);
+
+        let website = &summary.line_items[1];
+        assert_eq!((website.project_id, website.entry_count), (1, 2));
+        assert_eq!(website.amount_cents, 5000);
+        let other = &summary.line_items[2];
+        assert_eq!((other.project_id, other.entry_count), (3, 1));
+        assert_eq!(other.amount_cents, 5000);
+    }
+}
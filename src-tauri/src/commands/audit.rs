@@ -0,0 +1,30 @@
+use crate::db::audit;
+use crate::db::AuditEntry;
+use crate::state::AppState;
+use tauri::State;
+
+#[tauri::command]
+pub fn get_audit_log(
+    state: State<'_, AppState>,
+    entity_type: Option<String>,
+    limit: Option<i64>,
+) -> Result<Vec<AuditEntry>, String> {
+    let conn = state.db.conn();
+    audit::get_log(&conn, entity_type.as_deref(), limit.unwrap_or(100)).map_err(|e| e.to_string())
+}
+
+/// Revert the most recent logged change and return its log entry.
+#[tauri::command]
+pub fn undo_last_change(state: State<'_, AppState>) -> Result<AuditEntry, String> {
+    let entry = {
+        let conn = state.db.conn();
+        audit::undo_last(&conn).map_err(|e| e.to_string())?
+    };
+    match entry.entity_type.as_str() {
+        "settings" => super::settings::apply_stored_settings(&state),
+        "tag" => state.tag_rules_changed(),
+        _ => {}
+    }
+    log::info!("Undid {} on {}", entry.action, entry.entity_type);
+    Ok(entry)
+}
@@ -1,30 +1,121 @@
+use crate::db::audit::{self, Snapshot};
 use crate::db::Category;
 use crate::state::AppState;
+use rusqlite::{Connection, Row};
 use tauri::State;
 
+const CATEGORY_COLUMNS: &str = "id, name, color, icon, is_productive, created_at, updated_at,
+                                is_protected, parent_id, inherit_productive";
+
+/// Matches a category and all of its descendants; takes the category id as parameter.
+const SUBTREE_FILTER: &str = "id IN (
+    WITH RECURSIVE subtree(id) AS (
+        SELECT ?
+        UNION ALL
+        SELECT c.id FROM categories c JOIN subtree s ON c.parent_id = s.id
+    )
+    SELECT id FROM subtree
+)";
+
+fn map_category(row: &Row) -> Result<Category, rusqlite::Error> {
+    Ok(Category {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        color: row.get(2)?,
+        icon: row.get(3)?,
+        is_productive: row.get::<_, i32>(4)? == 1,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+        is_protected: row.get::<_, i32>(7)? == 1,
+        parent_id: row.get(8)?,
+        inherit_productive: row.get::<_, i32>(9)? == 1,
+    })
+}
+
+fn get_category(conn: &Connection, id: i64) -> Result<Category, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM categories WHERE id = ?", CATEGORY_COLUMNS),
+        [id],
+        map_category,
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => format!("Category {} not found", id),
+        e => e.to_string(),
+    })
+}
+
+fn capture_subtree(conn: &Connection, id: i64) -> Result<Snapshot, String> {
+    Snapshot::new()
+        .capture(conn, "categories", &[], SUBTREE_FILTER, [id])
+        .map_err(|e| e.to_string())
+}
+
+/// Ensure `parent_id` exists and is not `id` itself or one of its descendants.
+fn validate_parent(conn: &Connection, id: Option<i64>, parent_id: i64) -> Result<(), String> {
+    get_category(conn, parent_id)?;
+
+    if let Some(id) = id {
+        let is_descendant: bool = conn
+            .query_row(
+                &format!(
+                    "SELECT EXISTS(SELECT 1 FROM categories WHERE {} AND id = ?)",
+                    SUBTREE_FILTER
+                ),
+                [id, parent_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if is_descendant {
+            return Err("A category cannot be moved under itself or its subcategories".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+/// Copy `is_productive` down to every descendant that inherits it.
+fn propagate_productive(conn: &Connection, id: i64) -> Result<(), String> {
+    let is_productive: i32 = conn
+        .query_row(
+            "SELECT is_productive FROM categories WHERE id = ?",
+            [id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let children: Vec<i64> = conn
+        .prepare("SELECT id FROM categories WHERE parent_id = ? AND inherit_productive = 1")
+        .and_then(|mut stmt| {
+            stmt.query_map([id], |row| row.get(0))?
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| e.to_string())?;
+
+    for child in children {
+        conn.execute(
+            "UPDATE categories SET is_productive = ?, updated_at = strftime('%s', 'now') WHERE id = ?",
+            [is_productive as i64, child],
+        )
+        .map_err(|e| e.to_string())?;
+        propagate_productive(conn, child)?;
+    }
+
+    Ok(())
+}
+
 #[tauri::command]
 pub fn get_categories(state: State<'_, AppState>) -> Result<Vec<Category>, String> {
     let conn = state.db.conn();
 
     let mut stmt = conn
-        .prepare(
-            "SELECT id, name, color, icon, is_productive, created_at, updated_at
-             FROM categories ORDER BY name",
-        )
+        .prepare(&format!(
+            "SELECT {} FROM categories ORDER BY name",
+            CATEGORY_COLUMNS
+        ))
         .map_err(|e| e.to_string())?;
 
     let categories = stmt
-        .query_map([], |row| {
-            Ok(Category {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                color: row.get(2)?,
-                icon: row.get(3)?,
-                is_productive: row.get::<_, i32>(4)? == 1,
-                created_at: row.get(5)?,
-                updated_at: row.get(6)?,
-            })
-        })
+        .query_map([], map_category)
         .map_err(|e| e.to_string())?
         .collect::<Result<Vec<_>, _>>()
         .map_err(|e| e.to_string())?;
@@ -32,42 +123,52 @@ pub fn get_categories(state: State<'_, AppState>) -> Result<Vec<Category>, Strin
     Ok(categories)
 }
 
+/// Create a category, optionally under `parent_id`. A subcategory created
+/// without `is_productive` inherits it from its parent.
 #[tauri::command]
 pub fn create_category(
     state: State<'_, AppState>,
     name: String,
     color: String,
-    is_productive: bool,
+    is_productive: Option<bool>,
+    parent_id: Option<i64>,
 ) -> Result<Category, String> {
     let conn = state.db.conn();
 
-    conn.execute(
-        "INSERT INTO categories (name, color, is_productive) VALUES (?, ?, ?)",
-        rusqlite::params![name, color, is_productive as i32],
+    let parent = match parent_id {
+        Some(parent_id) => {
+            validate_parent(&conn, None, parent_id)?;
+            Some(get_category(&conn, parent_id)?)
+        }
+        None => None,
+    };
+    let inherit = is_productive.is_none() && parent.is_some();
+    let is_productive = is_productive
+        .or(parent.map(|p| p.is_productive))
+        .unwrap_or(true);
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "INSERT INTO categories (name, color, is_productive, parent_id, inherit_productive)
+         VALUES (?, ?, ?, ?, ?)",
+        rusqlite::params![name, color, is_productive as i32, parent_id, inherit as i32],
     )
     .map_err(|e| e.to_string())?;
 
-    let id = conn.last_insert_rowid();
+    let id = tx.last_insert_rowid();
 
-    conn.query_row(
-        "SELECT id, name, color, icon, is_productive, created_at, updated_at
-         FROM categories WHERE id = ?",
-        [id],
-        |row| {
-            Ok(Category {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                color: row.get(2)?,
-                icon: row.get(3)?,
-                is_productive: row.get::<_, i32>(4)? == 1,
-                created_at: row.get(5)?,
-                updated_at: row.get(6)?,
-            })
-        },
-    )
-    .map_err(|e| e.to_string())
+    let after = capture_subtree(&tx, id)?;
+    audit::record(&tx, "create_category", "category", Some(id), &Snapshot::new(), &after)
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    get_category(&conn, id)
 }
 
+/// Update a category. Setting `is_productive` overrides inheritance;
+/// `inherit_productive: true` restores it. `clear_parent` makes it top-level.
+#[allow(clippy::too_many_arguments)]
 #[tauri::command]
 pub fn update_category(
     state: State<'_, AppState>,
@@ -75,11 +176,22 @@ pub fn update_category(
     name: Option<String>,
     color: Option<String>,
     is_productive: Option<bool>,
+    parent_id: Option<i64>,
+    clear_parent: Option<bool>,
+    inherit_productive: Option<bool>,
 ) -> Result<Category, String> {
     let conn = state.db.conn();
+    get_category(&conn, id)?;
+
+    if let Some(parent_id) = parent_id {
+        validate_parent(&conn, Some(id), parent_id)?;
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let before = capture_subtree(&tx, id)?;
 
     if let Some(name) = &name {
-        conn.execute(
+        tx.execute(
             "UPDATE categories SET name = ?, updated_at = strftime('%s', 'now') WHERE id = ?",
             rusqlite::params![name, id],
         )
@@ -87,48 +199,149 @@ pub fn update_category(
     }
 
     if let Some(color) = &color {
-        conn.execute(
+        tx.execute(
             "UPDATE categories SET color = ?, updated_at = strftime('%s', 'now') WHERE id = ?",
             rusqlite::params![color, id],
         )
         .map_err(|e| e.to_string())?;
     }
 
+    if parent_id.is_some() || clear_parent == Some(true) {
+        tx.execute(
+            "UPDATE categories SET parent_id = ?, updated_at = strftime('%s', 'now') WHERE id = ?",
+            rusqlite::params![parent_id, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
     if let Some(is_productive) = is_productive {
-        conn.execute(
-            "UPDATE categories SET is_productive = ?, updated_at = strftime('%s', 'now') WHERE id = ?",
+        tx.execute(
+            "UPDATE categories SET is_productive = ?, inherit_productive = 0,
+                    updated_at = strftime('%s', 'now')
+             WHERE id = ?",
             rusqlite::params![is_productive as i32, id],
         )
         .map_err(|e| e.to_string())?;
+    } else if let Some(inherit) = inherit_productive {
+        tx.execute(
+            "UPDATE categories SET inherit_productive = ?, updated_at = strftime('%s', 'now')
+             WHERE id = ?",
+            rusqlite::params![inherit as i32, id],
+        )
+        .map_err(|e| e.to_string())?;
     }
 
-    conn.query_row(
-        "SELECT id, name, color, icon, is_productive, created_at, updated_at
-         FROM categories WHERE id = ?",
+    // Pick up the parent's value when inheriting, then pass ours down
+    tx.execute(
+        "UPDATE categories
+         SET is_productive = (SELECT p.is_productive FROM categories p
+                              WHERE p.id = categories.parent_id)
+         WHERE id = ? AND inherit_productive = 1 AND parent_id IS NOT NULL",
         [id],
-        |row| {
-            Ok(Category {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                color: row.get(2)?,
-                icon: row.get(3)?,
-                is_productive: row.get::<_, i32>(4)? == 1,
-                created_at: row.get(5)?,
-                updated_at: row.get(6)?,
-            })
-        },
     )
-    .map_err(|e| e.to_string())
+    .map_err(|e| e.to_string())?;
+    propagate_productive(&tx, id)?;
+
+    let after = capture_subtree(&tx, id)?;
+    audit::record(&tx, "update_category", "category", Some(id), &before, &after)
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    get_category(&conn, id)
 }
 
+/// Delete a category, moving its applications, rules, projects and activity
+/// records to `reassign_to` (the protected fallback category by default).
+/// Returns the number of reassigned rows.
 #[tauri::command]
-pub fn delete_category(state: State<'_, AppState>, id: i64) -> Result<(), String> {
+pub fn delete_category(
+    state: State<'_, AppState>,
+    id: i64,
+    reassign_to: Option<i64>,
+) -> Result<usize, String> {
     let conn = state.db.conn();
+    let (target, affected) = delete_and_reassign(&conn, id, reassign_to)?;
+
+    log::info!("Deleted category {}, reassigned {} rows to {}", id, affected, target);
+
+    Ok(affected)
+}
+
+/// Returns the category the rows were moved to and how many were moved.
+fn delete_and_reassign(
+    conn: &Connection,
+    id: i64,
+    reassign_to: Option<i64>,
+) -> Result<(i64, usize), String> {
+    let category = get_category(conn, id)?;
+    if category.is_protected {
+        return Err("This category is protected and cannot be deleted".to_string());
+    }
 
-    conn.execute("DELETE FROM categories WHERE id = ?", [id])
+    let target: i64 = match reassign_to {
+        Some(target) => target,
+        None => conn
+            .query_row(
+                "SELECT id FROM categories WHERE is_protected = 1 ORDER BY id LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|_| "No fallback category to reassign to".to_string())?,
+    };
+    if target == id {
+        return Err("Cannot reassign a category to itself".to_string());
+    }
+    get_category(conn, target)?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    let capture = |conn: &Connection| -> Result<Snapshot, rusqlite::Error> {
+        Snapshot::new()
+            .capture(conn, "categories", &[], SUBTREE_FILTER, [id])?
+            .capture(conn, "applications", &["category_id"], "category_id = ?", [id])?
+            .capture(conn, "categorization_rules", &["category_id"], "category_id = ?", [id])?
+            .capture(conn, "projects", &["category_id"], "category_id = ?", [id])?
+            .capture(conn, "activity_records", &["category_id"], "category_id = ?", [id])
+    };
+    let before = capture(&tx).map_err(|e| e.to_string())?;
+
+    let mut affected = 0;
+    for table in ["applications", "categorization_rules", "projects", "activity_records"] {
+        affected += tx
+            .execute(
+                &format!("UPDATE {} SET category_id = ? WHERE category_id = ?", table),
+                [target, id],
+            )
+            .map_err(|e| e.to_string())?;
+    }
+
+    // Subcategories move up to the deleted category's parent
+    let children: Vec<i64> = tx
+        .prepare("SELECT id FROM categories WHERE parent_id = ?")
+        .and_then(|mut stmt| {
+            stmt.query_map([id], |row| row.get(0))?
+                .collect::<Result<Vec<_>, _>>()
+        })
         .map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE categories SET parent_id = ? WHERE parent_id = ?",
+        rusqlite::params![category.parent_id, id],
+    )
+    .map_err(|e| e.to_string())?;
+    affected += children.len();
 
-    Ok(())
+    tx.execute("DELETE FROM categories WHERE id = ?", [id])
+        .map_err(|e| e.to_string())?;
+
+    if let Some(parent_id) = category.parent_id {
+        propagate_productive(&tx, parent_id)?;
+    }
+
+    audit::record(&tx, "delete_category", "category", Some(id), &before, &Snapshot::new())
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok((target, affected))
 }
 
 #[tauri::command]
@@ -138,12 +351,122 @@ pub fn assign_app_to_category(
     category_id: Option<i64>,
 ) -> Result<(), String> {
     let conn = state.db.conn();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let capture = |conn: &Connection| {
+        Snapshot::new()
+            .capture(conn, "applications", &[], "id = ?", [app_id])
+            .map_err(|e| e.to_string())
+    };
+
+    let before = capture(&tx)?;
 
-    conn.execute(
+    tx.execute(
         "UPDATE applications SET category_id = ?, updated_at = strftime('%s', 'now') WHERE id = ?",
         rusqlite::params![category_id, app_id],
     )
     .map_err(|e| e.to_string())?;
 
+    let after = capture(&tx)?;
+    audit::record(&tx, "assign_app_to_category", "application", Some(app_id), &before, &after)
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    fn fallback_id(conn: &Connection) -> i64 {
+        conn.query_row("SELECT id FROM categories WHERE is_protected = 1", [], |row| row.get(0))
+            .expect("Failed to find fallback category")
+    }
+
+    #[test]
+    fn test_delete_reassigns_references_to_fallback() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+        conn.execute_batch(
+            "INSERT INTO categories (id, name) VALUES (100, 'Reading');
+             INSERT INTO applications (id, name, category_id) VALUES (100, 'Reader', 100);
+             INSERT INTO categorization_rules (name, rule_type, pattern, category_id)
+                 VALUES ('books', 'window_title', 'epub', 100);
+             INSERT INTO projects (id, name, category_id) VALUES (100, 'Library', 100);
+             INSERT INTO activity_records (window_title, start_time, end_time, category_id)
+                 VALUES ('book.epub', 1000, 2000, 100);",
+        )
+        .expect("Failed to insert fixtures");
+
+        let fallback = fallback_id(&conn);
+        let (target, affected) = delete_and_reassign(&conn, 100, None).expect("Failed to delete");
+        assert_eq!(target, fallback);
+        assert_eq!(affected, 4);
+
+        for table in ["applications", "categorization_rules", "projects", "activity_records"] {
+            let category_id: i64 = conn
+                .query_row(&format!("SELECT category_id FROM {}", table), [], |row| row.get(0))
+                .expect("Failed to read category");
+            assert_eq!(category_id, fallback, "{} was not reassigned", table);
+        }
+        assert!(get_category(&conn, 100).is_err());
+    }
+
+    #[test]
+    fn test_parent_cannot_create_cycle() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+        conn.execute_batch(
+            "INSERT INTO categories (id, name) VALUES (100, 'Engineering');
+             INSERT INTO categories (id, name, parent_id) VALUES (101, 'Code Review', 100);
+             INSERT INTO categories (id, name, parent_id) VALUES (102, 'Pairing', 101);",
+        )
+        .expect("Failed to insert fixtures");
+
+        assert!(validate_parent(&conn, Some(100), 100).is_err());
+        assert!(validate_parent(&conn, Some(100), 102).is_err());
+        assert!(validate_parent(&conn, Some(101), 102).is_err());
+        assert!(validate_parent(&conn, Some(102), 100).is_ok());
+        assert!(validate_parent(&conn, None, 102).is_ok());
+        assert!(validate_parent(&conn, Some(100), 999).is_err());
+    }
+
+    #[test]
+    fn test_productive_flows_to_inheriting_subcategories() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+        conn.execute_batch(
+            "INSERT INTO categories (id, name, is_productive) VALUES (100, 'Messaging', 0);
+             INSERT INTO categories (id, name, is_productive, parent_id, inherit_productive)
+                 VALUES (101, 'Inbox', 1, 100, 1),
+                        (102, 'Standup', 1, 100, 0),
+                        (103, 'Newsletters', 1, 101, 1);",
+        )
+        .expect("Failed to insert fixtures");
+
+        propagate_productive(&conn, 100).expect("Failed to propagate");
+
+        let productive =
+            |id| get_category(&conn, id).expect("Failed to get category").is_productive;
+        assert!(!productive(101));
+        assert!(!productive(103));
+        // An explicit value overrides the parent's
+        assert!(productive(102));
+    }
+
+    #[test]
+    fn test_fallback_category_is_protected() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+        conn.execute("INSERT INTO categories (id, name) VALUES (100, 'Reading')", [])
+            .expect("Failed to insert category");
+
+        let fallback = fallback_id(&conn);
+        assert!(delete_and_reassign(&conn, fallback, None).is_err());
+        assert!(delete_and_reassign(&conn, fallback, Some(100)).is_err());
+        assert!(delete_and_reassign(&conn, 100, Some(100)).is_err());
+        assert!(get_category(&conn, fallback).is_ok());
+        assert!(get_category(&conn, 100).is_ok());
+    }
+}
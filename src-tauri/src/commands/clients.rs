@@ -0,0 +1,184 @@
+use crate::billing::{self, CLIENT_COLUMNS};
+use crate::db::audit::{self, Snapshot};
+use crate::db::{BillingSummary, Client, ClientInput};
+use crate::state::AppState;
+use rusqlite::Connection;
+use tauri::State;
+
+fn validate_client(input: &ClientInput) -> Result<(), String> {
+    if input.name.trim().is_empty() {
+        return Err("Client name cannot be empty".to_string());
+    }
+    if input.currency.len() != 3 || !input.currency.chars().all(|c| c.is_ascii_uppercase()) {
+        return Err(format!(
+            "Currency must be a three-letter ISO code, got '{}'",
+            input.currency
+        ));
+    }
+    if input.hourly_rate_cents.is_some_and(|rate| rate < 0) {
+        return Err("Hourly rate cannot be negative".to_string());
+    }
+    if input.rounding_minutes < 0 {
+        return Err("Rounding minutes cannot be negative".to_string());
+    }
+    Ok(())
+}
+
+fn get_client(conn: &Connection, id: i64) -> Result<Client, String> {
+    billing::get_client(conn, id).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => format!("Client {} not found", id),
+        e => e.to_string(),
+    })
+}
+
+#[tauri::command]
+pub fn get_clients(state: State<'_, AppState>) -> Result<Vec<Client>, String> {
+    let conn = state.db.conn();
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM clients ORDER BY name",
+            CLIENT_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let clients = stmt
+        .query_map([], billing::map_client)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(clients)
+}
+
+#[tauri::command]
+pub fn create_client(state: State<'_, AppState>, client: ClientInput) -> Result<Client, String> {
+    validate_client(&client)?;
+
+    let conn = state.db.conn();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "INSERT INTO clients
+            (name, email, address, currency, hourly_rate_cents, rounding_minutes, rounding_scope)
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+        rusqlite::params![
+            client.name.trim(),
+            client.email,
+            client.address,
+            client.currency,
+            client.hourly_rate_cents,
+            client.rounding_minutes,
+            client.rounding_scope.as_str(),
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = tx.last_insert_rowid();
+
+    let after = Snapshot::new()
+        .capture(&tx, "clients", &[], "id = ?", [id])
+        .map_err(|e| e.to_string())?;
+    audit::record(
+        &tx,
+        "create_client",
+        "client",
+        Some(id),
+        &Snapshot::new(),
+        &after,
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    get_client(&conn, id)
+}
+
+#[tauri::command]
+pub fn update_client(
+    state: State<'_, AppState>,
+    id: i64,
+    client: ClientInput,
+) -> Result<Client, String> {
+    validate_client(&client)?;
+
+    let conn = state.db.conn();
+    get_client(&conn, id)?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let capture = |conn: &Connection| {
+        Snapshot::new()
+            .capture(conn, "clients", &[], "id = ?", [id])
+            .map_err(|e| e.to_string())
+    };
+
+    let before = capture(&tx)?;
+    tx.execute(
+        "UPDATE clients
+         SET name = ?, email = ?, address = ?, currency = ?, hourly_rate_cents = ?,
+             rounding_minutes = ?, rounding_scope = ?
+         WHERE id = ?",
+        rusqlite::params![
+            client.name.trim(),
+            client.email,
+            client.address,
+            client.currency,
+            client.hourly_rate_cents,
+            client.rounding_minutes,
+            client.rounding_scope.as_str(),
+            id,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let after = capture(&tx)?;
+
+    audit::record(&tx, "update_client", "client", Some(id), &before, &after)
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    get_client(&conn, id)
+}
+
+/// Delete a client. Its projects are kept but no longer belong to a client.
+#[tauri::command]
+pub fn delete_client(state: State<'_, AppState>, id: i64) -> Result<(), String> {
+    let conn = state.db.conn();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    let before = Snapshot::new()
+        .capture(&tx, "clients", &[], "id = ?", [id])
+        .and_then(|s| s.capture(&tx, "projects", &["client_id"], "client_id = ?", [id]))
+        .map_err(|e| e.to_string())?;
+
+    let deleted = tx
+        .execute("DELETE FROM clients WHERE id = ?", [id])
+        .map_err(|e| e.to_string())?;
+    if deleted == 0 {
+        return Err(format!("Client {} not found", id));
+    }
+
+    audit::record(
+        &tx,
+        "delete_client",
+        "client",
+        Some(id),
+        &before,
+        &Snapshot::new(),
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Billable time and amounts per project for a client between two dates.
+#[tauri::command]
+pub fn get_billing_summary(
+    state: State<'_, AppState>,
+    client_id: i64,
+    start_date: String,
+    end_date: String,
+) -> Result<BillingSummary, String> {
+    let conn = state.db.conn();
+    get_client(&conn, client_id)?;
+
+    billing::summarize(&conn, client_id, &start_date, &end_date).map_err(|e| e.to_string())
+}
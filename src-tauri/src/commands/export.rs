@@ -0,0 +1,27 @@
+use crate::export::{self, GroupBy};
+use crate::state::AppState;
+use tauri::State;
+
+/// Export merged time blocks between two dates as an iCalendar file.
+/// Returns the number of events written.
+#[tauri::command]
+pub fn export_ics(
+    state: State<'_, AppState>,
+    start_date: String,
+    end_date: String,
+    group_by: GroupBy,
+    path: String,
+) -> Result<usize, String> {
+    let blocks = {
+        let conn = state.db.conn();
+        export::build_blocks(&conn, &start_date, &end_date, group_by).map_err(|e| e.to_string())?
+    };
+
+    let now = chrono::Utc::now().timestamp();
+    let calendar = export::ics::render_calendar(&blocks, group_by, now);
+
+    std::fs::write(&path, calendar).map_err(|e| e.to_string())?;
+    log::info!("Exported {} calendar events to {}", blocks.len(), path);
+
+    Ok(blocks.len())
+}
@@ -0,0 +1,90 @@
+use crate::db::{FocusDistraction, FocusSessionReport};
+use crate::focus;
+use crate::state::AppState;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Start a focus block. From now on, switching to an app in a
+/// non-productive category emits `focus:distraction`.
+#[tauri::command]
+pub fn start_focus_session(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<FocusSessionReport, String> {
+    if state.get_focus_session().is_some() {
+        return Err("A focus session is already running".to_string());
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let conn = state.db.conn();
+    let session_id = focus::start_session(&conn, now).map_err(|e| e.to_string())?;
+    state.set_focus_session(Some(session_id));
+
+    // The window that is already active counts too
+    if let (Some(window), Some(activity)) = (state.get_current_window(), state.live_activity()) {
+        let distraction = focus::on_window_change(
+            &conn,
+            session_id,
+            activity.application_id,
+            &window.app_name,
+            &window.window_title,
+            now,
+        )
+        .map_err(|e| e.to_string())?;
+        if let Some(d) = distraction {
+            emit_distraction(&app, &d);
+        }
+    }
+
+    focus::session_report(&conn, session_id, now).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn stop_focus_session(state: State<'_, AppState>) -> Result<FocusSessionReport, String> {
+    let session_id = state
+        .get_focus_session()
+        .ok_or_else(|| "No focus session is running".to_string())?;
+
+    let now = chrono::Utc::now().timestamp();
+    let conn = state.db.conn();
+    focus::end_session(&conn, session_id, now).map_err(|e| e.to_string())?;
+    state.set_focus_session(None);
+
+    focus::session_report(&conn, session_id, now).map_err(|e| e.to_string())
+}
+
+/// The running focus session, if any.
+#[tauri::command]
+pub fn get_focus_session(state: State<'_, AppState>) -> Result<Option<FocusSessionReport>, String> {
+    let Some(session_id) = state.get_focus_session() else {
+        return Ok(None);
+    };
+    let conn = state.db.conn();
+    focus::session_report(&conn, session_id, chrono::Utc::now().timestamp())
+        .map(Some)
+        .map_err(|e| e.to_string())
+}
+
+/// Focus sessions started between two dates, with distraction counts and
+/// time lost per block.
+#[tauri::command]
+pub fn get_focus_report(
+    state: State<'_, AppState>,
+    start_date: String,
+    end_date: String,
+) -> Result<Vec<FocusSessionReport>, String> {
+    let conn = state.db.conn();
+    focus::report(
+        &conn,
+        &start_date,
+        &end_date,
+        chrono::Utc::now().timestamp(),
+    )
+    .map_err(|e| e.to_string())
+}
+
+pub(crate) fn emit_distraction(app: &AppHandle, distraction: &FocusDistraction) {
+    log::info!("Focus distraction: {}", distraction.app_name);
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.emit("focus:distraction", distraction);
+    }
+}
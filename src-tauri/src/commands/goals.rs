@@ -0,0 +1,173 @@
+use crate::db::audit::{self, Snapshot};
+use crate::db::{Goal, GoalInput, GoalProgress, GoalTarget};
+use crate::goals;
+use crate::state::AppState;
+use rusqlite::Connection;
+use tauri::State;
+
+fn validate_goal(conn: &Connection, input: &GoalInput) -> Result<(), String> {
+    if input.name.trim().is_empty() {
+        return Err("Goal name cannot be empty".to_string());
+    }
+    if input.target_seconds <= 0 {
+        return Err("Goal target must be a positive number of seconds".to_string());
+    }
+    if !(1..=127).contains(&input.active_days) {
+        return Err("A goal must be active on at least one weekday".to_string());
+    }
+
+    let table = match input.target_type {
+        GoalTarget::Category => "categories",
+        GoalTarget::Application => "applications",
+    };
+    let exists: bool = conn
+        .query_row(
+            &format!("SELECT EXISTS (SELECT 1 FROM {} WHERE id = ?)", table),
+            [input.target_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if !exists {
+        return Err(format!(
+            "{} {} not found",
+            match input.target_type {
+                GoalTarget::Category => "Category",
+                GoalTarget::Application => "Application",
+            },
+            input.target_id
+        ));
+    }
+    Ok(())
+}
+
+fn get_goal(conn: &Connection, id: i64) -> Result<Goal, String> {
+    goals::get_goal(conn, id).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => format!("Goal {} not found", id),
+        e => e.to_string(),
+    })
+}
+
+#[tauri::command]
+pub fn get_goals(state: State<'_, AppState>) -> Result<Vec<Goal>, String> {
+    let conn = state.db.conn();
+    goals::list(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn create_goal(state: State<'_, AppState>, goal: GoalInput) -> Result<Goal, String> {
+    let conn = state.db.conn();
+    validate_goal(&conn, &goal)?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO goals
+            (name, target_type, target_id, kind, period, target_seconds, active_days, is_active)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        rusqlite::params![
+            goal.name.trim(),
+            goal.target_type.as_str(),
+            goal.target_id,
+            goal.kind.as_str(),
+            goal.period.as_str(),
+            goal.target_seconds,
+            goal.active_days,
+            goal.is_active,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = tx.last_insert_rowid();
+
+    let after = Snapshot::new()
+        .capture(&tx, "goals", &[], "id = ?", [id])
+        .map_err(|e| e.to_string())?;
+    audit::record(
+        &tx,
+        "create_goal",
+        "goal",
+        Some(id),
+        &Snapshot::new(),
+        &after,
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    get_goal(&conn, id)
+}
+
+#[tauri::command]
+pub fn update_goal(state: State<'_, AppState>, id: i64, goal: GoalInput) -> Result<Goal, String> {
+    let conn = state.db.conn();
+    get_goal(&conn, id)?;
+    validate_goal(&conn, &goal)?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let capture = |conn: &Connection| {
+        Snapshot::new()
+            .capture(conn, "goals", &[], "id = ?", [id])
+            .map_err(|e| e.to_string())
+    };
+
+    let before = capture(&tx)?;
+    tx.execute(
+        "UPDATE goals
+         SET name = ?, target_type = ?, target_id = ?, kind = ?, period = ?,
+             target_seconds = ?, active_days = ?, is_active = ?
+         WHERE id = ?",
+        rusqlite::params![
+            goal.name.trim(),
+            goal.target_type.as_str(),
+            goal.target_id,
+            goal.kind.as_str(),
+            goal.period.as_str(),
+            goal.target_seconds,
+            goal.active_days,
+            goal.is_active,
+            id,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let after = capture(&tx)?;
+
+    audit::record(&tx, "update_goal", "goal", Some(id), &before, &after)
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    get_goal(&conn, id)
+}
+
+#[tauri::command]
+pub fn delete_goal(state: State<'_, AppState>, id: i64) -> Result<(), String> {
+    let conn = state.db.conn();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    let before = Snapshot::new()
+        .capture(&tx, "goals", &[], "id = ?", [id])
+        .map_err(|e| e.to_string())?;
+    let deleted = tx
+        .execute("DELETE FROM goals WHERE id = ?", [id])
+        .map_err(|e| e.to_string())?;
+    if deleted == 0 {
+        return Err(format!("Goal {} not found", id));
+    }
+
+    audit::record(
+        &tx,
+        "delete_goal",
+        "goal",
+        Some(id),
+        &before,
+        &Snapshot::new(),
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Current progress of the goals that apply today, including the activity
+/// that is still being timed.
+#[tauri::command]
+pub fn get_goal_progress(state: State<'_, AppState>) -> Result<Vec<GoalProgress>, String> {
+    let conn = state.db.conn();
+    goals::evaluate(&conn, chrono::Local::now(), state.live_activity()).map_err(|e| e.to_string())
+}
@@ -0,0 +1,108 @@
+use crate::db::audit::{self, Snapshot};
+use crate::db::{CsvColumnMapping, CsvImportPreview, CsvImportReport, ImportReport};
+use crate::import::{activitywatch, time_entries};
+use crate::state::AppState;
+use rusqlite::Connection;
+use tauri::State;
+
+/// Tables an import can add rows to.
+const IMPORTED_TABLES: &[&str] = &[
+    "clients",
+    "projects",
+    "applications",
+    "activity_records",
+    "idle_periods",
+    "tags",
+    "activity_tags",
+];
+
+fn max_ids(conn: &Connection) -> Result<Vec<i64>, String> {
+    IMPORTED_TABLES
+        .iter()
+        .map(|table| {
+            conn.query_row(&format!("SELECT COALESCE(MAX(id), 0) FROM {}", table), [], |row| {
+                row.get(0)
+            })
+            .map_err(|e| e.to_string())
+        })
+        .collect()
+}
+
+/// Log rows added since `max_ids` were taken so the import can be undone.
+/// Runs in the import's transaction so the rows and the log entry are saved
+/// together.
+fn log_import(tx: &Connection, action: &str, max_ids: &[i64]) -> Result<(), String> {
+    let after = IMPORTED_TABLES
+        .iter()
+        .zip(max_ids)
+        .try_fold(Snapshot::new(), |snapshot, (table, max_id)| {
+            // Only the ids are needed to remove the rows again
+            snapshot.capture(tx, table, &["id"], "id > ?", [max_id])
+        })
+        .map_err(|e| e.to_string())?;
+
+    audit::record(tx, action, "import", None, &Snapshot::new(), &after)
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn import_activitywatch(
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<ImportReport, String> {
+    let json = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let data = activitywatch::parse(&json).map_err(|e| e.to_string())?;
+
+    let conn = state.db.conn();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let max_ids = max_ids(&tx)?;
+    let report = activitywatch::import(&tx, &data).map_err(|e| e.to_string())?;
+    log_import(&tx, "import_activitywatch", &max_ids)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    log::info!(
+        "Imported {} ActivityWatch records ({} skipped) from {}",
+        report.records_imported,
+        report.records_skipped,
+        path
+    );
+
+    Ok(report)
+}
+
+#[tauri::command]
+pub fn preview_csv_import(path: String) -> Result<CsvImportPreview, String> {
+    let text = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    time_entries::preview(&text).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn import_csv_time_entries(
+    state: State<'_, AppState>,
+    path: String,
+    mapping: Option<CsvColumnMapping>,
+    dry_run: bool,
+) -> Result<CsvImportReport, String> {
+    let text = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+
+    let conn = state.db.conn();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let max_ids = max_ids(&tx)?;
+    let report = time_entries::import(&tx, &text, mapping, dry_run).map_err(|e| e.to_string())?;
+    if !dry_run {
+        log_import(&tx, "import_csv_time_entries", &max_ids)?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+
+    log::info!(
+        "{} {} {} time entries ({} conflicts) from {}",
+        if dry_run { "Checked" } else { "Imported" },
+        report.entries_imported,
+        report.format,
+        report.conflicts.len(),
+        path
+    );
+
+    Ok(report)
+}
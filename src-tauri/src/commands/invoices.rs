@@ -0,0 +1,244 @@
+use crate::db::audit::{self, Snapshot};
+use crate::db::{Invoice, InvoiceFormat, InvoiceTemplate, InvoiceTemplateInput};
+use crate::invoice::{self, TEMPLATE_COLUMNS};
+use crate::state::AppState;
+use rusqlite::Connection;
+use tauri::State;
+
+fn validate_template(input: &InvoiceTemplateInput) -> Result<(), String> {
+    if input.name.trim().is_empty() {
+        return Err("Template name cannot be empty".to_string());
+    }
+    if input.payment_terms_days < 0 {
+        return Err("Payment terms cannot be negative".to_string());
+    }
+    Ok(())
+}
+
+fn get_template(conn: &Connection, id: i64) -> Result<InvoiceTemplate, String> {
+    invoice::get_template(conn, Some(id)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_invoice_templates(state: State<'_, AppState>) -> Result<Vec<InvoiceTemplate>, String> {
+    let conn = state.db.conn();
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM invoice_templates ORDER BY id",
+            TEMPLATE_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let templates = stmt
+        .query_map([], invoice::map_template)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(templates)
+}
+
+#[tauri::command]
+pub fn create_invoice_template(
+    state: State<'_, AppState>,
+    template: InvoiceTemplateInput,
+) -> Result<InvoiceTemplate, String> {
+    validate_template(&template)?;
+
+    let conn = state.db.conn();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "INSERT INTO invoice_templates
+            (name, company_name, header_text, footer_text, accent_color, payment_terms_days)
+         VALUES (?, ?, ?, ?, ?, ?)",
+        rusqlite::params![
+            template.name.trim(),
+            template.company_name,
+            template.header_text,
+            template.footer_text,
+            template.accent_color,
+            template.payment_terms_days,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = tx.last_insert_rowid();
+
+    let after = Snapshot::new()
+        .capture(&tx, "invoice_templates", &[], "id = ?", [id])
+        .map_err(|e| e.to_string())?;
+    audit::record(
+        &tx,
+        "create_invoice_template",
+        "invoice_template",
+        Some(id),
+        &Snapshot::new(),
+        &after,
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    get_template(&conn, id)
+}
+
+#[tauri::command]
+pub fn update_invoice_template(
+    state: State<'_, AppState>,
+    id: i64,
+    template: InvoiceTemplateInput,
+) -> Result<InvoiceTemplate, String> {
+    validate_template(&template)?;
+
+    let conn = state.db.conn();
+    get_template(&conn, id)?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let capture = |conn: &Connection| {
+        Snapshot::new()
+            .capture(conn, "invoice_templates", &[], "id = ?", [id])
+            .map_err(|e| e.to_string())
+    };
+
+    let before = capture(&tx)?;
+    tx.execute(
+        "UPDATE invoice_templates
+         SET name = ?, company_name = ?, header_text = ?, footer_text = ?, accent_color = ?,
+             payment_terms_days = ?
+         WHERE id = ?",
+        rusqlite::params![
+            template.name.trim(),
+            template.company_name,
+            template.header_text,
+            template.footer_text,
+            template.accent_color,
+            template.payment_terms_days,
+            id,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let after = capture(&tx)?;
+
+    audit::record(
+        &tx,
+        "update_invoice_template",
+        "invoice_template",
+        Some(id),
+        &before,
+        &after,
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    get_template(&conn, id)
+}
+
+#[tauri::command]
+pub fn delete_invoice_template(state: State<'_, AppState>, id: i64) -> Result<(), String> {
+    let conn = state.db.conn();
+
+    let count: i64 = conn
+        .query_row("SELECT COUNT(*) FROM invoice_templates", [], |row| {
+            row.get(0)
+        })
+        .map_err(|e| e.to_string())?;
+    if count <= 1 {
+        return Err("Cannot delete the last invoice template".to_string());
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let before = Snapshot::new()
+        .capture(&tx, "invoice_templates", &[], "id = ?", [id])
+        .map_err(|e| e.to_string())?;
+
+    let deleted = tx
+        .execute("DELETE FROM invoice_templates WHERE id = ?", [id])
+        .map_err(|e| e.to_string())?;
+    if deleted == 0 {
+        return Err(format!("Invoice template {} not found", id));
+    }
+
+    audit::record(
+        &tx,
+        "delete_invoice_template",
+        "invoice_template",
+        Some(id),
+        &before,
+        &Snapshot::new(),
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Issue an invoice for a client's billable time. `project_ids` limits it to
+/// those projects; empty means all of the client's projects.
+///
+/// Invoices are not audited: undoing one would let its number be reused.
+#[tauri::command]
+pub fn create_invoice(
+    state: State<'_, AppState>,
+    client_id: i64,
+    project_ids: Vec<i64>,
+    start_date: String,
+    end_date: String,
+    template_id: Option<i64>,
+) -> Result<Invoice, String> {
+    let conn = state.db.conn();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    let issued = invoice::create(
+        &tx,
+        client_id,
+        &project_ids,
+        &start_date,
+        &end_date,
+        template_id,
+        chrono::Local::now().date_naive(),
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    log::info!(
+        "Issued invoice {} for {}",
+        issued.number,
+        invoice::format_money(issued.total_cents, &issued.currency)
+    );
+
+    Ok(issued)
+}
+
+#[tauri::command]
+pub fn get_invoices(state: State<'_, AppState>) -> Result<Vec<Invoice>, String> {
+    let conn = state.db.conn();
+    invoice::list(&conn, None).map_err(|e| e.to_string())
+}
+
+/// Write an issued invoice to `path` as HTML or PDF.
+#[tauri::command]
+pub fn export_invoice(
+    state: State<'_, AppState>,
+    id: i64,
+    format: InvoiceFormat,
+    path: String,
+) -> Result<(), String> {
+    let (issued, template) = {
+        let conn = state.db.conn();
+        let issued = invoice::load(&conn, id).map_err(|e| e.to_string())?;
+        let template = invoice::get_template(&conn, issued.template_id)
+            .or_else(|_| invoice::get_template(&conn, None))
+            .map_err(|e| e.to_string())?;
+        (issued, template)
+    };
+
+    let document = match format {
+        InvoiceFormat::Html => invoice::html::render_html(&issued, &template).into_bytes(),
+        InvoiceFormat::Pdf => invoice::pdf::render_pdf(&issued, &template),
+    };
+
+    std::fs::write(&path, document).map_err(|e| e.to_string())?;
+    log::info!("Exported invoice {} to {}", issued.number, path);
+
+    Ok(())
+}
@@ -1,9 +1,41 @@
+pub mod audit;
 pub mod categories;
+pub mod clients;
+pub mod export;
+pub mod focus;
+pub mod goals;
+pub mod import;
+pub mod invoices;
+pub mod presets;
+pub mod projects;
+pub mod records;
 pub mod reports;
+pub mod schedule;
+pub mod search;
 pub mod settings;
+pub mod tags;
+pub mod timer;
+pub mod timesheets;
 pub mod tracking;
+pub mod workhours;
 
+pub use audit::*;
 pub use categories::*;
+pub use clients::*;
+pub use export::*;
+pub use focus::*;
+pub use goals::*;
+pub use import::*;
+pub use invoices::*;
+pub use presets::*;
+pub use projects::*;
+pub use records::*;
 pub use reports::*;
+pub use schedule::*;
+pub use search::*;
 pub use settings::*;
+pub use tags::*;
+pub use timer::*;
+pub use timesheets::*;
 pub use tracking::*;
+pub use workhours::*;
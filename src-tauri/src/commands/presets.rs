@@ -0,0 +1,84 @@
+use crate::db::audit::{self, Snapshot};
+use crate::db::{PresetImportReport, PresetPackInfo};
+use crate::presets::{self, ConflictStrategy, PresetPack};
+use crate::state::AppState;
+use rusqlite::Connection;
+use tauri::State;
+
+fn capture_presets(conn: &Connection) -> Result<Snapshot, rusqlite::Error> {
+    Snapshot::new()
+        .capture(conn, "categories", &[], "1", [])?
+        .capture(conn, "categorization_rules", &[], "1", [])?
+        .capture(conn, "applications", &["category_id"], "1", [])
+}
+
+fn apply_pack(
+    state: &AppState,
+    pack: &PresetPack,
+    strategy: ConflictStrategy,
+) -> Result<PresetImportReport, String> {
+    let conn = state.db.conn();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    let before = capture_presets(&tx).map_err(|e| e.to_string())?;
+    let report = presets::import(&tx, pack, strategy).map_err(|e| e.to_string())?;
+    let after = capture_presets(&tx).map_err(|e| e.to_string())?;
+
+    audit::record(&tx, "import_preset_pack", "preset", None, &before, &after)
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    log::info!(
+        "Imported preset pack '{}': {} categories, {} rules",
+        pack.name,
+        report.categories_created + report.categories_updated,
+        report.rules_created + report.rules_updated
+    );
+
+    Ok(report)
+}
+
+#[tauri::command]
+pub fn list_builtin_packs() -> Result<Vec<PresetPackInfo>, String> {
+    presets::builtin_packs().map_err(|e| e.to_string())
+}
+
+/// Write categories, rules and app assignments to a JSON pack file.
+#[tauri::command]
+pub fn export_preset_pack(
+    state: State<'_, AppState>,
+    name: String,
+    description: Option<String>,
+    path: String,
+) -> Result<(), String> {
+    let pack = {
+        let conn = state.db.conn();
+        presets::export(&conn, name, description).map_err(|e| e.to_string())?
+    };
+
+    let json = serde_json::to_string_pretty(&pack).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn import_preset_pack(
+    state: State<'_, AppState>,
+    path: String,
+    strategy: ConflictStrategy,
+) -> Result<PresetImportReport, String> {
+    let json = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let pack = presets::parse(&json).map_err(|e| e.to_string())?;
+    apply_pack(&state, &pack, strategy)
+}
+
+#[tauri::command]
+pub fn import_builtin_pack(
+    state: State<'_, AppState>,
+    id: String,
+    strategy: ConflictStrategy,
+) -> Result<PresetImportReport, String> {
+    let pack = presets::builtin_pack(&id).map_err(|e| e.to_string())?;
+    apply_pack(&state, &pack, strategy)
+}
@@ -0,0 +1,168 @@
+use crate::db::audit::{self, Snapshot};
+use crate::db::Project;
+use crate::state::AppState;
+use rusqlite::Connection;
+use tauri::State;
+
+const PROJECT_COLUMNS: &str = "id, name, description, category_id, color, is_active, client_id,
+                               hourly_rate_cents, rounding_minutes, is_billable,
+                               created_at, updated_at";
+
+fn map_project(row: &rusqlite::Row) -> rusqlite::Result<Project> {
+    Ok(Project {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        category_id: row.get(3)?,
+        color: row.get(4)?,
+        is_active: row.get::<_, i32>(5)? == 1,
+        client_id: row.get(6)?,
+        hourly_rate_cents: row.get(7)?,
+        rounding_minutes: row.get(8)?,
+        is_billable: row.get::<_, i32>(9)? == 1,
+        created_at: row.get(10)?,
+        updated_at: row.get(11)?,
+    })
+}
+
+fn get_project(conn: &Connection, id: i64) -> Result<Project, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM projects WHERE id = ?", PROJECT_COLUMNS),
+        [id],
+        map_project,
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => format!("Project {} not found", id),
+        e => e.to_string(),
+    })
+}
+
+#[tauri::command]
+pub fn get_projects(state: State<'_, AppState>) -> Result<Vec<Project>, String> {
+    let conn = state.db.conn();
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM projects ORDER BY is_active DESC, name",
+            PROJECT_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let projects = stmt
+        .query_map([], map_project)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(projects)
+}
+
+#[tauri::command]
+pub fn create_project(
+    state: State<'_, AppState>,
+    name: String,
+    color: Option<String>,
+    client_id: Option<i64>,
+    category_id: Option<i64>,
+) -> Result<Project, String> {
+    if name.trim().is_empty() {
+        return Err("Project name cannot be empty".to_string());
+    }
+
+    let conn = state.db.conn();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "INSERT INTO projects (name, color, client_id, category_id)
+         VALUES (?, COALESCE(?, '#3B82F6'), ?, ?)",
+        rusqlite::params![name.trim(), color, client_id, category_id],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = tx.last_insert_rowid();
+
+    let after = Snapshot::new()
+        .capture(&tx, "projects", &[], "id = ?", [id])
+        .map_err(|e| e.to_string())?;
+    audit::record(
+        &tx,
+        "create_project",
+        "project",
+        Some(id),
+        &Snapshot::new(),
+        &after,
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    get_project(&conn, id)
+}
+
+/// Set a project's client and billing overrides. `None` rate or rounding
+/// falls back to the client's.
+#[tauri::command]
+pub fn set_project_billing(
+    state: State<'_, AppState>,
+    project_id: i64,
+    client_id: Option<i64>,
+    hourly_rate_cents: Option<i64>,
+    rounding_minutes: Option<i64>,
+    is_billable: bool,
+) -> Result<Project, String> {
+    if hourly_rate_cents.is_some_and(|rate| rate < 0) {
+        return Err("Hourly rate cannot be negative".to_string());
+    }
+    if rounding_minutes.is_some_and(|minutes| minutes < 0) {
+        return Err("Rounding minutes cannot be negative".to_string());
+    }
+
+    let conn = state.db.conn();
+    get_project(&conn, project_id)?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let capture = |conn: &Connection| {
+        Snapshot::new()
+            .capture(
+                conn,
+                "projects",
+                &[
+                    "client_id",
+                    "hourly_rate_cents",
+                    "rounding_minutes",
+                    "is_billable",
+                ],
+                "id = ?",
+                [project_id],
+            )
+            .map_err(|e| e.to_string())
+    };
+
+    let before = capture(&tx)?;
+    tx.execute(
+        "UPDATE projects
+         SET client_id = ?, hourly_rate_cents = ?, rounding_minutes = ?, is_billable = ?,
+             updated_at = strftime('%s', 'now')
+         WHERE id = ?",
+        rusqlite::params![
+            client_id,
+            hourly_rate_cents,
+            rounding_minutes,
+            is_billable as i32,
+            project_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let after = capture(&tx)?;
+
+    audit::record(
+        &tx,
+        "set_project_billing",
+        "project",
+        Some(project_id),
+        &before,
+        &after,
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    get_project(&conn, project_id)
+}
@@ -0,0 +1,764 @@
+use crate::db::audit::{self, Snapshot};
+use crate::db::{ActivityRecord, IdlePeriod};
+use crate::search;
+use crate::state::AppState;
+use crate::timesheet;
+use rusqlite::Connection;
+use tauri::State;
+
+const RECORD_COLUMNS: &str = "id, application_id, window_title, url, start_time, end_time,
+                              duration_seconds, category_id, project_id, is_idle, source, notes,
+                              is_billable";
+
+fn map_record(row: &rusqlite::Row) -> rusqlite::Result<ActivityRecord> {
+    Ok(ActivityRecord {
+        id: row.get(0)?,
+        application_id: row.get(1)?,
+        window_title: row.get(2)?,
+        url: row.get(3)?,
+        start_time: row.get(4)?,
+        end_time: row.get(5)?,
+        duration_seconds: row.get(6)?,
+        category_id: row.get(7)?,
+        project_id: row.get(8)?,
+        is_idle: row.get::<_, i32>(9)? == 1,
+        source: row.get(10)?,
+        notes: row.get(11)?,
+        is_billable: row.get::<_, Option<i32>>(12)?.map(|b| b == 1),
+    })
+}
+
+fn get_record(conn: &Connection, id: i64) -> Result<ActivityRecord, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM activity_records WHERE id = ?", RECORD_COLUMNS),
+        [id],
+        map_record,
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => format!("Activity record {} not found", id),
+        e => e.to_string(),
+    })
+}
+
+/// Records with their tags, which go with them when they are deleted.
+fn capture_records(conn: &Connection, ids: &[i64]) -> Result<Snapshot, String> {
+    ids.iter()
+        .try_fold(Snapshot::new(), |snapshot, id| {
+            snapshot
+                .capture(conn, "activity_records", &[], "id = ?", [id])?
+                .capture(conn, "activity_tags", &[], "activity_id = ?", [id])
+        })
+        .map_err(|e| e.to_string())
+}
+
+fn log_change(
+    conn: &Connection,
+    action: &str,
+    id: i64,
+    before: &Snapshot,
+    after: &Snapshot,
+) -> Result<(), String> {
+    audit::record(conn, action, "activity_record", Some(id), before, after)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Treat blank notes as no note.
+fn normalize_note(notes: Option<String>) -> Option<String> {
+    notes
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+}
+
+/// Combine two notes, keeping both on separate lines.
+fn join_notes(first: Option<String>, second: Option<String>) -> Option<String> {
+    match (first, second) {
+        (Some(a), Some(b)) if a != b => Some(format!("{}\n{}", a, b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Reject `[start, end)` if it is empty or overlaps a non-idle record other than `exclude`.
+fn validate_range(conn: &Connection, start: i64, end: i64, exclude: &[i64]) -> Result<(), String> {
+    if end <= start {
+        return Err("End time must be after start time".to_string());
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, start_time, end_time FROM activity_records
+             WHERE is_idle = 0 AND start_time < ? AND end_time > ?
+             ORDER BY start_time",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let overlapping = stmt
+        .query_map([end, start], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    match overlapping.iter().find(|(id, _, _)| !exclude.contains(id)) {
+        Some((id, s, e)) => Err(format!(
+            "Time range overlaps activity record {} ({} - {})",
+            id, s, e
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Add time that was not auto-tracked, e.g. a meeting away from the computer.
+#[tauri::command]
+pub fn create_manual_entry(
+    state: State<'_, AppState>,
+    start_time: i64,
+    end_time: i64,
+    title: String,
+    category_id: Option<i64>,
+    project_id: Option<i64>,
+    notes: Option<String>,
+) -> Result<ActivityRecord, String> {
+    let conn = state.db.conn();
+
+    validate_range(&conn, start_time, end_time, &[])?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "INSERT INTO activity_records
+            (window_title, start_time, end_time, category_id, project_id, is_idle, source, notes)
+         VALUES (?, ?, ?, ?, ?, 0, 'manual', ?)",
+        rusqlite::params![
+            title,
+            start_time,
+            end_time,
+            category_id,
+            project_id,
+            normalize_note(notes)
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = tx.last_insert_rowid();
+
+    let after = capture_records(&tx, &[id])?;
+    log_change(&tx, "create_manual_entry", id, &Snapshot::new(), &after)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    get_record(&conn, id)
+}
+
+/// Changes to a record; `None` leaves a field as it is.
+#[derive(Default)]
+struct RecordUpdate {
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+    title: Option<String>,
+    category_id: Option<Option<i64>>,
+    project_id: Option<Option<i64>>,
+    notes: Option<String>,
+}
+
+fn update_record(
+    conn: &Connection,
+    id: i64,
+    update: RecordUpdate,
+) -> Result<ActivityRecord, String> {
+    let record = get_record(conn, id)?;
+
+    let start = update.start_time.unwrap_or(record.start_time);
+    let end = update.end_time.unwrap_or(record.end_time);
+    if start != record.start_time || end != record.end_time {
+        validate_range(conn, start, end, &[id])?;
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let before = capture_records(&tx, &[id])?;
+
+    tx.execute(
+        "UPDATE activity_records
+         SET start_time = ?, end_time = ?, window_title = ?, category_id = ?, project_id = ?,
+             notes = ?
+         WHERE id = ?",
+        rusqlite::params![
+            start,
+            end,
+            update.title.unwrap_or(record.window_title),
+            update.category_id.unwrap_or(record.category_id),
+            update.project_id.unwrap_or(record.project_id),
+            match update.notes {
+                Some(notes) => normalize_note(Some(notes)),
+                None => record.notes,
+            },
+            id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let after = capture_records(&tx, &[id])?;
+    log_change(&tx, "update_activity_record", id, &before, &after)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    get_record(conn, id)
+}
+
+/// Update a record. An empty `notes` string clears the note, and
+/// `clear_category` or `clear_project` remove the category or project.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn update_activity_record(
+    state: State<'_, AppState>,
+    id: i64,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+    title: Option<String>,
+    category_id: Option<i64>,
+    project_id: Option<i64>,
+    notes: Option<String>,
+    clear_category: Option<bool>,
+    clear_project: Option<bool>,
+) -> Result<ActivityRecord, String> {
+    // A null argument arrives the same as a missing one, hence the flags
+    let clear_or = |clear: Option<bool>, id: Option<i64>| {
+        if clear == Some(true) {
+            Some(None)
+        } else {
+            id.map(Some)
+        }
+    };
+
+    let conn = state.db.conn();
+    update_record(
+        &conn,
+        id,
+        RecordUpdate {
+            start_time,
+            end_time,
+            title,
+            category_id: clear_or(clear_category, category_id),
+            project_id: clear_or(clear_project, project_id),
+            notes,
+        },
+    )
+}
+
+/// Split a record in two at `at`; both halves keep the original's attributes.
+#[tauri::command]
+pub fn split_activity_record(
+    state: State<'_, AppState>,
+    id: i64,
+    at: i64,
+) -> Result<Vec<ActivityRecord>, String> {
+    let conn = state.db.conn();
+    let record = get_record(&conn, id)?;
+
+    if at <= record.start_time || at >= record.end_time {
+        return Err("Split time must be inside the record".to_string());
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let before = capture_records(&tx, &[id])?;
+
+    tx.execute(
+        "INSERT INTO activity_records
+            (application_id, window_title, url, start_time, end_time,
+             category_id, project_id, is_idle, source, notes, is_billable)
+         SELECT application_id, window_title, url, ?, end_time,
+                category_id, project_id, is_idle, source, notes, is_billable
+         FROM activity_records WHERE id = ?",
+        [at, id],
+    )
+    .map_err(|e| e.to_string())?;
+    let new_id = tx.last_insert_rowid();
+
+    tx.execute(
+        "UPDATE activity_records SET end_time = ? WHERE id = ?",
+        [at, id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let after = capture_records(&tx, &[id, new_id])?;
+    log_change(&tx, "split_activity_record", id, &before, &after)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(vec![get_record(&conn, id)?, get_record(&conn, new_id)?])
+}
+
+/// Combine two window titles, keeping both when they differ.
+fn join_titles(first: String, second: String) -> String {
+    if first == second || second.is_empty() {
+        first
+    } else if first.is_empty() {
+        second
+    } else {
+        format!("{} | {}", first, second)
+    }
+}
+
+fn merge_records(
+    conn: &Connection,
+    first_id: i64,
+    second_id: i64,
+) -> Result<ActivityRecord, String> {
+    let a = get_record(conn, first_id)?;
+    let b = get_record(conn, second_id)?;
+    let (first, second) = if a.start_time <= b.start_time { (a, b) } else { (b, a) };
+
+    if first.id == second.id {
+        return Err("Cannot merge a record with itself".to_string());
+    }
+
+    let end = first.end_time.max(second.end_time);
+    validate_range(conn, first.start_time, end, &[first.id, second.id])
+        .map_err(|_| "Only adjacent records can be merged".to_string())?;
+
+    timesheet::check_unlocked(conn, second.start_time, second.end_time)
+        .map_err(|e| e.to_string())?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let before = capture_records(&tx, &[first.id, second.id])?;
+
+    tx.execute(
+        "UPDATE activity_records SET end_time = ?, window_title = ?, notes = ? WHERE id = ?",
+        rusqlite::params![
+            end,
+            join_titles(first.window_title, second.window_title),
+            join_notes(first.notes, second.notes),
+            first.id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM activity_records WHERE id = ?", [second.id])
+        .map_err(|e| e.to_string())?;
+
+    let after = capture_records(&tx, &[first.id, second.id])?;
+    log_change(&tx, "merge_activity_records", first.id, &before, &after)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    get_record(conn, first.id)
+}
+
+/// Merge two adjacent records into the earlier one, covering any gap between
+/// them. Differing window titles are both kept.
+#[tauri::command]
+pub fn merge_activity_records(
+    state: State<'_, AppState>,
+    first_id: i64,
+    second_id: i64,
+) -> Result<ActivityRecord, String> {
+    let conn = state.db.conn();
+    merge_records(&conn, first_id, second_id)
+}
+
+#[tauri::command]
+pub fn delete_activity_record(state: State<'_, AppState>, id: i64) -> Result<(), String> {
+    let conn = state.db.conn();
+    let record = get_record(&conn, id)?;
+    timesheet::check_unlocked(&conn, record.start_time, record.end_time)
+        .map_err(|e| e.to_string())?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let before = capture_records(&tx, &[id])?;
+
+    tx.execute("DELETE FROM activity_records WHERE id = ?", [id])
+        .map_err(|e| e.to_string())?;
+
+    log_change(&tx, "delete_activity_record", id, &before, &Snapshot::new())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Set or clear (with `None` or a blank string) the note on a record.
+#[tauri::command]
+pub fn set_activity_note(
+    state: State<'_, AppState>,
+    id: i64,
+    notes: Option<String>,
+) -> Result<ActivityRecord, String> {
+    let conn = state.db.conn();
+    get_record(&conn, id)?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let capture = |conn: &Connection| {
+        Snapshot::new()
+            .capture(conn, "activity_records", &["notes"], "id = ?", [id])
+            .map_err(|e| e.to_string())
+    };
+
+    let before = capture(&tx)?;
+    tx.execute(
+        "UPDATE activity_records SET notes = ? WHERE id = ?",
+        rusqlite::params![normalize_note(notes), id],
+    )
+    .map_err(|e| e.to_string())?;
+    let after = capture(&tx)?;
+
+    log_change(&tx, "set_activity_note", id, &before, &after)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    get_record(&conn, id)
+}
+
+/// Attach `note` to every non-idle record overlapping `[start_time, end_time)`,
+/// appending it to any existing note. Returns the number of records updated.
+#[tauri::command]
+pub fn add_note_to_range(
+    state: State<'_, AppState>,
+    start_time: i64,
+    end_time: i64,
+    note: String,
+) -> Result<usize, String> {
+    if end_time <= start_time {
+        return Err("End time must be after start time".to_string());
+    }
+    let note = normalize_note(Some(note)).ok_or("Note cannot be empty")?;
+
+    let conn = state.db.conn();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let capture = |conn: &Connection| {
+        Snapshot::new()
+            .capture(
+                conn,
+                "activity_records",
+                &["notes"],
+                "is_idle = 0 AND start_time < ? AND end_time > ?",
+                [end_time, start_time],
+            )
+            .map_err(|e| e.to_string())
+    };
+
+    let before = capture(&tx)?;
+    let updated = append_note(&tx, start_time, end_time, &note).map_err(|e| e.to_string())?;
+    let after = capture(&tx)?;
+
+    audit::record(
+        &tx,
+        "add_note_to_range",
+        "activity_record",
+        None,
+        &before,
+        &after,
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(updated)
+}
+
+/// Add `note` as a new line to the notes of records overlapping the range,
+/// unless one of their lines already is that note.
+fn append_note(
+    conn: &Connection,
+    start_time: i64,
+    end_time: i64,
+    note: &str,
+) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE activity_records
+         SET notes = CASE
+             WHEN notes IS NULL OR notes = '' THEN ?1
+             WHEN instr(char(10) || notes || char(10), char(10) || ?1 || char(10)) > 0 THEN notes
+             ELSE notes || char(10) || ?1
+         END
+         WHERE is_idle = 0 AND start_time < ?3 AND end_time > ?2",
+        rusqlite::params![note, start_time, end_time],
+    )
+}
+
+/// Records whose notes match every word of `query`, newest first,
+/// optionally limited to a date range.
+#[tauri::command]
+pub fn search_notes(
+    state: State<'_, AppState>,
+    query: String,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    limit: Option<i64>,
+) -> Result<Vec<ActivityRecord>, String> {
+    let Some(query) = search::fts_query(&query, Some("notes")) else {
+        return Ok(Vec::new());
+    };
+
+    let conn = state.db.conn();
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM activity_records
+             WHERE id IN (SELECT rowid FROM activity_search WHERE activity_search MATCH ?1)
+               AND (?2 IS NULL OR date(start_time, 'unixepoch', 'localtime') >= ?2)
+               AND (?3 IS NULL OR date(start_time, 'unixepoch', 'localtime') <= ?3)
+             ORDER BY start_time DESC
+             LIMIT ?4",
+            RECORD_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+
+    let records = stmt
+        .query_map(
+            rusqlite::params![query, start_date, end_date, limit.unwrap_or(100)],
+            map_record,
+        )
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(records)
+}
+
+/// Mark records billable or not; `None` reverts to the project's flag.
+/// Returns the number of records updated.
+#[tauri::command]
+pub fn set_records_billable(
+    state: State<'_, AppState>,
+    record_ids: Vec<i64>,
+    is_billable: Option<bool>,
+) -> Result<usize, String> {
+    let conn = state.db.conn();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let capture = |conn: &Connection| {
+        record_ids
+            .iter()
+            .try_fold(Snapshot::new(), |snapshot, id| {
+                snapshot.capture(conn, "activity_records", &["is_billable"], "id = ?", [id])
+            })
+            .map_err(|e| e.to_string())
+    };
+
+    let before = capture(&tx)?;
+    let mut updated = 0;
+    for id in &record_ids {
+        updated += tx
+            .execute(
+                "UPDATE activity_records SET is_billable = ? WHERE id = ?",
+                rusqlite::params![is_billable.map(i32::from), id],
+            )
+            .map_err(|e| e.to_string())?;
+    }
+    let after = capture(&tx)?;
+
+    audit::record(
+        &tx,
+        "set_records_billable",
+        "activity_record",
+        None,
+        &before,
+        &after,
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(updated)
+}
+
+/// Settle an idle stretch from the "keep or discard?" prompt. Kept time
+/// becomes an activity record; discarded time stays out of all totals.
+#[tauri::command]
+pub fn resolve_idle_period(
+    state: State<'_, AppState>,
+    id: i64,
+    keep: bool,
+) -> Result<IdlePeriod, String> {
+    let conn = state.db.conn();
+    let (start_time, end_time, disposition): (i64, Option<i64>, Option<String>) = conn
+        .query_row(
+            "SELECT start_time, end_time, disposition FROM idle_periods WHERE id = ?",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => format!("Idle period {} not found", id),
+            e => e.to_string(),
+        })?;
+    if disposition.is_some() {
+        return Err(format!("Idle period {} is already resolved", id));
+    }
+    let end_time = end_time.ok_or_else(|| format!("Idle period {} has not ended", id))?;
+    if keep {
+        validate_range(&conn, start_time, end_time, &[])?;
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let capture = |conn: &Connection| {
+        Snapshot::new()
+            .capture(conn, "idle_periods", &[], "id = ?", [id])
+            .map_err(|e| e.to_string())
+    };
+
+    let before = capture(&tx)?;
+    tx.execute(
+        "UPDATE idle_periods SET disposition = ? WHERE id = ?",
+        rusqlite::params![if keep { "other" } else { "discarded" }, id],
+    )
+    .map_err(|e| e.to_string())?;
+    let mut after = capture(&tx)?;
+    if keep {
+        tx.execute(
+            "INSERT INTO activity_records (window_title, start_time, end_time, is_idle, source)
+             VALUES ('Away from computer', ?, ?, 0, 'idle')",
+            [start_time, end_time],
+        )
+        .map_err(|e| e.to_string())?;
+        let record_id = tx.last_insert_rowid();
+        after = after
+            .capture(&tx, "activity_records", &[], "id = ?", [record_id])
+            .map_err(|e| e.to_string())?;
+    }
+
+    audit::record(
+        &tx,
+        "resolve_idle_period",
+        "idle_period",
+        Some(id),
+        &before,
+        &after,
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    conn.query_row(
+        "SELECT id, start_time, end_time, duration_seconds, disposition, notes
+         FROM idle_periods WHERE id = ?",
+        [id],
+        |row| {
+            Ok(IdlePeriod {
+                id: row.get(0)?,
+                start_time: row.get(1)?,
+                end_time: row.get(2)?,
+                duration_seconds: row.get(3)?,
+                disposition: row.get(4)?,
+                notes: row.get(5)?,
+            })
+        },
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    #[test]
+    fn test_update_can_clear_category_and_project() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+        conn.execute_batch(
+            "INSERT INTO categories (id, name) VALUES (100, 'Clearing');
+             INSERT INTO projects (id, name) VALUES (100, 'Clearing');
+             INSERT INTO activity_records (id, window_title, start_time, end_time, category_id, project_id)
+                 VALUES (1, 'doc', 1000, 2000, 100, 100);",
+        )
+        .unwrap();
+
+        // Leaving fields out keeps them
+        let kept = update_record(
+            &conn,
+            1,
+            RecordUpdate {
+                title: Some("renamed".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(kept.category_id, Some(100));
+        assert_eq!(kept.project_id, Some(100));
+
+        let cleared = update_record(
+            &conn,
+            1,
+            RecordUpdate {
+                category_id: Some(None),
+                project_id: Some(None),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(cleared.window_title, "renamed");
+        assert_eq!(cleared.category_id, None);
+        assert_eq!(cleared.project_id, None);
+    }
+
+    #[test]
+    fn test_merge_keeps_titles_and_notes() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+        conn.execute_batch(
+            "INSERT INTO activity_records (id, window_title, start_time, end_time, notes)
+                 VALUES (1, 'Design doc', 1000, 2000, 'draft'),
+                        (2, 'Review thread', 2100, 3000, 'feedback'),
+                        (3, 'Elsewhere', 5000, 6000, NULL);",
+        )
+        .unwrap();
+
+        let merged = merge_records(&conn, 2, 1).unwrap();
+        assert_eq!(merged.id, 1);
+        assert_eq!(merged.start_time, 1000);
+        assert_eq!(merged.end_time, 3000);
+        assert_eq!(merged.window_title, "Design doc | Review thread");
+        assert_eq!(merged.notes.as_deref(), Some("draft\nfeedback"));
+        assert!(get_record(&conn, 2).is_err());
+
+        // Merging across another record is refused
+        conn.execute(
+            "INSERT INTO activity_records (id, window_title, start_time, end_time)
+             VALUES (4, 'Between', 3500, 4000)",
+            [],
+        )
+        .unwrap();
+        assert!(merge_records(&conn, 1, 3).is_err());
+    }
+
+    #[test]
+    fn test_undoing_a_delete_restores_tags() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+        conn.execute_batch(
+            "INSERT INTO tags (id, name) VALUES (1, 'deep-work');
+             INSERT INTO activity_records (id, window_title, start_time, end_time)
+                 VALUES (1, 'doc', 1000, 2000);
+             INSERT INTO activity_tags (activity_id, tag_id) VALUES (1, 1);",
+        )
+        .unwrap();
+
+        let before = capture_records(&conn, &[1]).unwrap();
+        conn.execute("DELETE FROM activity_records WHERE id = 1", [])
+            .unwrap();
+        let after = capture_records(&conn, &[1]).unwrap();
+        log_change(&conn, "delete_activity_record", 1, &before, &after).unwrap();
+
+        audit::undo_last(&conn).unwrap();
+        let tags: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM activity_tags WHERE activity_id = 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(tags, 1);
+    }
+
+    #[test]
+    fn test_append_note_skips_only_whole_duplicate_lines() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+        conn.execute_batch(
+            "INSERT INTO activity_records (id, window_title, start_time, end_time, notes)
+                 VALUES (1, 'a', 1000, 2000, 'Sprint planning\nRetro'),
+                        (2, 'b', 2000, 3000, NULL);",
+        )
+        .unwrap();
+
+        let notes = |id| get_record(&conn, id).unwrap().notes;
+
+        // "Sprint" is part of an existing line but not a line of its own
+        append_note(&conn, 1000, 3000, "Sprint").unwrap();
+        assert_eq!(notes(1).as_deref(), Some("Sprint planning\nRetro\nSprint"));
+        assert_eq!(notes(2).as_deref(), Some("Sprint"));
+
+        append_note(&conn, 1000, 3000, "Retro").unwrap();
+        append_note(&conn, 1000, 3000, "Sprint").unwrap();
+        assert_eq!(notes(1).as_deref(), Some("Sprint planning\nRetro\nSprint"));
+        assert_eq!(notes(2).as_deref(), Some("Sprint\nRetro"));
+    }
+}
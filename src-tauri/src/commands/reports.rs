@@ -1,5 +1,7 @@
 use crate::db::{ActivityRecord, AppUsageStats, CategoryStats, DailySummary};
 use crate::state::AppState;
+use rusqlite::types::Value;
+use std::collections::{HashMap, HashSet};
 use tauri::State;
 
 #[tauri::command]
@@ -85,7 +87,8 @@ pub fn get_activity_range(
     let mut stmt = conn
         .prepare(
             "SELECT id, application_id, window_title, url, start_time, end_time,
-                    duration_seconds, category_id, project_id, is_idle
+                    duration_seconds, category_id, project_id, is_idle, source, notes,
+                    is_billable
              FROM activity_records
              WHERE date(start_time, 'unixepoch', 'localtime') >= ?
                AND date(start_time, 'unixepoch', 'localtime') <= ?
@@ -106,6 +109,9 @@ pub fn get_activity_range(
                 category_id: row.get(7)?,
                 project_id: row.get(8)?,
                 is_idle: row.get::<_, i32>(9)? == 1,
+                source: row.get(10)?,
+                notes: row.get(11)?,
+                is_billable: row.get::<_, Option<i32>>(12)?.map(|b| b == 1),
             })
         })
         .map_err(|e| e.to_string())?
@@ -120,26 +126,35 @@ pub fn get_app_usage(
     state: State<'_, AppState>,
     start_date: String,
     end_date: String,
+    include_tags: Option<Vec<i64>>,
+    exclude_tags: Option<Vec<i64>>,
 ) -> Result<Vec<AppUsageStats>, String> {
     let conn = state.db.conn();
+    let (tag_clause, params) = tag_filter(
+        start_date,
+        end_date,
+        include_tags.as_deref(),
+        exclude_tags.as_deref(),
+    );
 
     let mut stmt = conn
-        .prepare(
+        .prepare(&format!(
             "SELECT a.name, a.id, COALESCE(SUM(ar.duration_seconds), 0) as total,
                     c.name as cat_name, c.color as cat_color
              FROM applications a
              LEFT JOIN activity_records ar ON a.id = ar.application_id
                 AND date(ar.start_time, 'unixepoch', 'localtime') >= ?
-                AND date(ar.start_time, 'unixepoch', 'localtime') <= ?
+                AND date(ar.start_time, 'unixepoch', 'localtime') <= ?{}
              LEFT JOIN categories c ON a.category_id = c.id
              GROUP BY a.id
              HAVING total > 0
              ORDER BY total DESC",
-        )
+            tag_clause
+        ))
         .map_err(|e| e.to_string())?;
 
     let stats = stmt
-        .query_map([&start_date, &end_date], |row| {
+        .query_map(rusqlite::params_from_iter(params), |row| {
             Ok(AppUsageStats {
                 app_name: row.get(0)?,
                 app_id: row.get(1)?,
@@ -155,43 +170,128 @@ pub fn get_app_usage(
     Ok(stats)
 }
 
+/// Time per category between two dates. With `as_tree`, returns top-level
+/// categories with subcategories nested and their time rolled up.
 #[tauri::command]
 pub fn get_category_breakdown(
     state: State<'_, AppState>,
     start_date: String,
     end_date: String,
+    as_tree: Option<bool>,
+    include_tags: Option<Vec<i64>>,
+    exclude_tags: Option<Vec<i64>>,
 ) -> Result<Vec<CategoryStats>, String> {
     let conn = state.db.conn();
+    let (tag_clause, params) = tag_filter(
+        start_date,
+        end_date,
+        include_tags.as_deref(),
+        exclude_tags.as_deref(),
+    );
 
     let mut stmt = conn
-        .prepare(
+        .prepare(&format!(
             "SELECT c.id, c.name, c.color, COALESCE(SUM(ar.duration_seconds), 0) as total,
-                    c.is_productive
+                    c.is_productive, c.parent_id
              FROM categories c
              LEFT JOIN activity_records ar ON c.id = ar.category_id
                 AND date(ar.start_time, 'unixepoch', 'localtime') >= ?
-                AND date(ar.start_time, 'unixepoch', 'localtime') <= ?
+                AND date(ar.start_time, 'unixepoch', 'localtime') <= ?{}
              GROUP BY c.id
-             HAVING total > 0
              ORDER BY total DESC",
-        )
+            tag_clause
+        ))
         .map_err(|e| e.to_string())?;
 
     let stats = stmt
-        .query_map([&start_date, &end_date], |row| {
+        .query_map(rusqlite::params_from_iter(params), |row| {
             Ok(CategoryStats {
                 category_id: row.get(0)?,
                 category_name: row.get(1)?,
                 color: row.get(2)?,
                 total_seconds: row.get(3)?,
                 is_productive: row.get::<_, i32>(4)? == 1,
+                parent_id: row.get(5)?,
+                own_seconds: row.get(3)?,
+                children: Vec::new(),
             })
         })
         .map_err(|e| e.to_string())?
         .collect::<Result<Vec<_>, _>>()
         .map_err(|e| e.to_string())?;
 
-    Ok(stats)
+    if as_tree.unwrap_or(false) {
+        return Ok(build_category_tree(stats));
+    }
+
+    Ok(stats.into_iter().filter(|s| s.total_seconds > 0).collect())
+}
+
+/// Extra join conditions restricting `ar` to records carrying any of
+/// `include` and none of `exclude`, either directly or through their
+/// project. Returns the clause with the date range and tag ids as params.
+fn tag_filter(
+    start_date: String,
+    end_date: String,
+    include: Option<&[i64]>,
+    exclude: Option<&[i64]>,
+) -> (String, Vec<Value>) {
+    let mut clause = String::new();
+    let mut params = vec![Value::Text(start_date), Value::Text(end_date)];
+
+    let mut add = |ids: &[i64], negate: bool| {
+        let placeholders = vec!["?"; ids.len()].join(", ");
+        clause.push_str(&format!(
+            "
+                AND {}(EXISTS (SELECT 1 FROM activity_tags at
+                              WHERE at.activity_id = ar.id AND at.tag_id IN ({p}))
+                     OR EXISTS (SELECT 1 FROM project_tags pt
+                              WHERE pt.project_id = ar.project_id AND pt.tag_id IN ({p})))",
+            if negate { "NOT " } else { "" },
+            p = placeholders
+        ));
+        for _ in 0..2 {
+            params.extend(ids.iter().map(|&id| Value::Integer(id)));
+        }
+    };
+
+    if let Some(ids) = include.filter(|ids| !ids.is_empty()) {
+        add(ids, false);
+    }
+    if let Some(ids) = exclude.filter(|ids| !ids.is_empty()) {
+        add(ids, true);
+    }
+
+    (clause, params)
+}
+
+/// Nest categories under their parents, rolling child totals up and dropping
+/// branches without any time.
+fn build_category_tree(stats: Vec<CategoryStats>) -> Vec<CategoryStats> {
+    let ids: HashSet<i64> = stats.iter().map(|s| s.category_id).collect();
+    let mut by_parent: HashMap<Option<i64>, Vec<CategoryStats>> = HashMap::new();
+
+    for s in stats {
+        let parent = s.parent_id.filter(|p| ids.contains(p));
+        by_parent.entry(parent).or_default().push(s);
+    }
+
+    fn attach(
+        parent: Option<i64>,
+        by_parent: &mut HashMap<Option<i64>, Vec<CategoryStats>>,
+    ) -> Vec<CategoryStats> {
+        let mut nodes = by_parent.remove(&parent).unwrap_or_default();
+        for node in &mut nodes {
+            node.children = attach(Some(node.category_id), by_parent);
+            node.total_seconds =
+                node.own_seconds + node.children.iter().map(|c| c.total_seconds).sum::<i64>();
+        }
+        nodes.retain(|n| n.total_seconds > 0);
+        nodes.sort_by_key(|n| std::cmp::Reverse(n.total_seconds));
+        nodes
+    }
+
+    attach(None, &mut by_parent)
 }
 
 #[tauri::command]
@@ -199,3 +299,44 @@ pub fn get_today_summary(state: State<'_, AppState>) -> Result<DailySummary, Str
     let today = chrono::Local::now().format("%Y-%m-%d").to_string();
     get_daily_summary(state, today)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(id: i64, parent_id: Option<i64>, seconds: i64) -> CategoryStats {
+        CategoryStats {
+            category_id: id,
+            category_name: format!("Category {}", id),
+            color: "#000000".to_string(),
+            total_seconds: seconds,
+            is_productive: true,
+            parent_id,
+            own_seconds: seconds,
+            children: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_category_tree_rolls_up_child_totals() {
+        let tree = build_category_tree(vec![
+            stats(1, None, 600),
+            stats(2, Some(1), 300),
+            stats(3, Some(2), 120),
+            stats(4, Some(1), 0),
+            stats(5, None, 60),
+            stats(6, None, 0),
+        ]);
+
+        assert_eq!(tree.len(), 2);
+        let root = &tree[0];
+        assert_eq!(root.category_id, 1);
+        assert_eq!(root.own_seconds, 600);
+        assert_eq!(root.total_seconds, 1020);
+        // Empty branches are dropped
+        assert_eq!(root.children.len(), 1);
+        assert_eq!(root.children[0].total_seconds, 420);
+        assert_eq!(root.children[0].children[0].total_seconds, 120);
+        assert_eq!(tree[1].total_seconds, 60);
+    }
+}
@@ -0,0 +1,79 @@
+use crate::commands::settings::save_registry_values;
+use crate::db::SessionReason;
+use crate::schedule::{self, ScheduleStatus, TrackingSchedule};
+use crate::settings;
+use crate::state::AppState;
+use crate::tray;
+use tauri::{AppHandle, State};
+
+fn schedule_status(state: &AppState) -> ScheduleStatus {
+    let now = chrono::Local::now();
+    state
+        .schedule
+        .lock()
+        .status(now.naive_local(), now.timestamp())
+}
+
+#[tauri::command]
+pub fn get_schedule(state: State<'_, AppState>) -> Result<TrackingSchedule, String> {
+    Ok(state.schedule.lock().schedule.clone())
+}
+
+/// Replace the work hours schedule. The tracking loop applies it within a
+/// second, pausing or resuming tracking if that changes.
+#[tauri::command]
+pub fn set_schedule(
+    state: State<'_, AppState>,
+    schedule: TrackingSchedule,
+) -> Result<ScheduleStatus, String> {
+    schedule.validate()?;
+    let def = settings::lookup("tracking_schedule").ok_or("Unknown setting")?;
+    let value = serde_json::to_value(&schedule).map_err(|e| e.to_string())?;
+    let stored = def.to_stored(&value)?;
+    save_registry_values(&state, "set_schedule", &[(def.key, stored)])?;
+    Ok(schedule_status(&state))
+}
+
+#[tauri::command]
+pub fn get_schedule_status(state: State<'_, AppState>) -> Result<ScheduleStatus, String> {
+    Ok(schedule_status(&state))
+}
+
+/// Keep tracking outside the schedule until `until` (Unix seconds), starting
+/// it now if it is paused.
+#[tauri::command]
+pub fn track_anyway_until(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    until: i64,
+) -> Result<ScheduleStatus, String> {
+    if until <= chrono::Utc::now().timestamp() {
+        return Err("Override must end in the future".to_string());
+    }
+
+    {
+        let mut schedule = state.schedule.lock();
+        schedule.override_until = Some(until);
+        schedule.paused_tracking = false;
+    }
+    if !state.is_tracking() {
+        state.start_session(SessionReason::User);
+    }
+    tray::refresh(&app);
+
+    Ok(schedule_status(&state))
+}
+
+/// Drop a "track anyway" override, letting the schedule apply again right
+/// away: tracking pauses now if it is outside the scheduled hours.
+#[tauri::command]
+pub fn clear_schedule_override(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ScheduleStatus, String> {
+    state.schedule.lock().override_until = None;
+    let _ = schedule::apply(&app, &state);
+    tray::refresh(&app);
+
+    Ok(schedule_status(&state))
+}
@@ -0,0 +1,26 @@
+use crate::db::ActivitySearchMatch;
+use crate::search;
+use crate::state::AppState;
+use tauri::State;
+
+/// Full-text search over window titles, URLs and notes. Dates are optional
+/// local `YYYY-MM-DD` bounds; results are grouped by window title.
+#[tauri::command]
+pub fn search_activity(
+    state: State<'_, AppState>,
+    query: String,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<ActivitySearchMatch>, String> {
+    let conn = state.db.conn();
+
+    search::search(
+        &conn,
+        &query,
+        start_date.as_deref(),
+        end_date.as_deref(),
+        limit.unwrap_or(50),
+    )
+    .map_err(|e| e.to_string())
+}
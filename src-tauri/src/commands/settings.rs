@@ -1,122 +1,136 @@
-use crate::db::AppSettings;
+use crate::db::audit::{self, Snapshot};
+use crate::settings::{self, AppSettings, NotificationSettings};
 use crate::state::AppState;
+use rusqlite::Connection;
+use serde_json::Value;
 use tauri::State;
 
+fn capture_settings(conn: &Connection, keys: &[&str]) -> Result<Snapshot, String> {
+    keys.iter()
+        .try_fold(Snapshot::new(), |snapshot, key| {
+            snapshot.capture(conn, "settings", &[], "key = ?", [key])
+        })
+        .map_err(|e| e.to_string())
+}
+
+/// Store already validated registry values in one audited transaction, then
+/// run the change hook of each setting whose value changed.
+pub(crate) fn save_registry_values(
+    state: &AppState,
+    action: &str,
+    values: &[(&'static str, String)],
+) -> Result<(), String> {
+    let keys: Vec<&str> = values.iter().map(|(key, _)| *key).collect();
+    let mut changed = Vec::new();
+
+    {
+        let conn = state.db.conn();
+        let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+        let before = capture_settings(&tx, &keys)?;
+
+        for (key, value) in values {
+            let def = settings::lookup(key).ok_or_else(|| format!("Unknown setting '{}'", key))?;
+            let old = def.load(&tx);
+            tx.execute(
+                "INSERT OR REPLACE INTO settings (key, value, updated_at)
+                 VALUES (?, ?, strftime('%s', 'now'))",
+                [*key, value.as_str()],
+            )
+            .map_err(|e| e.to_string())?;
+            let new = def.load(&tx);
+            if new != old {
+                changed.push((def, new));
+            }
+        }
+
+        let after = capture_settings(&tx, &keys)?;
+        audit::record(&tx, action, "settings", None, &before, &after).map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+
+    for (def, value) in changed {
+        if let Some(on_change) = def.on_change {
+            on_change(state, &value);
+        }
+    }
+    Ok(())
+}
+
+/// Run every setting's change hook with its stored value, for when the
+/// settings table changed without going through `save_registry_values`.
+pub(crate) fn apply_stored_settings(state: &AppState) {
+    let values: Vec<_> = {
+        let conn = state.db.conn();
+        settings::REGISTRY
+            .iter()
+            .map(|def| (def, def.load(&conn)))
+            .collect()
+    };
+
+    for (def, value) in values {
+        if let Some(on_change) = def.on_change {
+            on_change(state, &value);
+        }
+    }
+}
+
 #[tauri::command]
 pub fn get_settings(state: State<'_, AppState>) -> Result<AppSettings, String> {
     let conn = state.db.conn();
-
-    let idle_threshold: i64 = conn
-        .query_row(
-            "SELECT value FROM settings WHERE key = 'idle_threshold_seconds'",
-            [],
-            |row| row.get::<_, String>(0),
-        )
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(300);
-
-    let tracking_enabled: bool = conn
-        .query_row(
-            "SELECT value FROM settings WHERE key = 'tracking_enabled'",
-            [],
-            |row| row.get::<_, String>(0),
-        )
-        .ok()
-        .map(|v| v == "true")
-        .unwrap_or(true);
-
-    let polling_interval: i64 = conn
-        .query_row(
-            "SELECT value FROM settings WHERE key = 'polling_interval_ms'",
-            [],
-            |row| row.get::<_, String>(0),
-        )
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(1000);
-
-    let theme: String = conn
-        .query_row(
-            "SELECT value FROM settings WHERE key = 'theme'",
-            [],
-            |row| row.get(0),
-        )
-        .unwrap_or_else(|_| "system".to_string());
-
-    Ok(AppSettings {
-        idle_threshold_seconds: idle_threshold,
-        tracking_enabled,
-        polling_interval_ms: polling_interval,
-        theme,
-    })
+    Ok(AppSettings::load(&conn))
 }
 
 #[tauri::command]
 pub fn update_settings(state: State<'_, AppState>, settings: AppSettings) -> Result<(), String> {
-    let conn = state.db.conn();
+    let values = settings.values()?;
+    save_registry_values(&state, "update_settings", &values)
+}
 
-    conn.execute(
-        "INSERT OR REPLACE INTO settings (key, value, updated_at)
-         VALUES ('idle_threshold_seconds', ?, strftime('%s', 'now'))",
-        [settings.idle_threshold_seconds.to_string()],
-    )
-    .map_err(|e| e.to_string())?;
-
-    conn.execute(
-        "INSERT OR REPLACE INTO settings (key, value, updated_at)
-         VALUES ('tracking_enabled', ?, strftime('%s', 'now'))",
-        [settings.tracking_enabled.to_string()],
-    )
-    .map_err(|e| e.to_string())?;
-
-    conn.execute(
-        "INSERT OR REPLACE INTO settings (key, value, updated_at)
-         VALUES ('polling_interval_ms', ?, strftime('%s', 'now'))",
-        [settings.polling_interval_ms.to_string()],
-    )
-    .map_err(|e| e.to_string())?;
-
-    conn.execute(
-        "INSERT OR REPLACE INTO settings (key, value, updated_at)
-         VALUES ('theme', ?, strftime('%s', 'now'))",
-        [&settings.theme],
-    )
-    .map_err(|e| e.to_string())?;
-
-    // Update app state
-    state.set_tracking(settings.tracking_enabled);
+/// Read any registered setting by key.
+#[tauri::command]
+pub fn get_setting(state: State<'_, AppState>, key: String) -> Result<Value, String> {
+    let def = settings::lookup(&key).ok_or_else(|| format!("Unknown setting '{}'", key))?;
+    let conn = state.db.conn();
+    Ok(def.load(&conn))
+}
 
-    Ok(())
+/// Change any registered setting by key, rejecting values of the wrong type
+/// or out of range.
+#[tauri::command]
+pub fn set_setting(state: State<'_, AppState>, key: String, value: Value) -> Result<Value, String> {
+    let def = settings::lookup(&key).ok_or_else(|| format!("Unknown setting '{}'", key))?;
+    let stored = def.to_stored(&value)?;
+    save_registry_values(&state, "set_setting", &[(def.key, stored)])?;
+    let conn = state.db.conn();
+    Ok(def.load(&conn))
 }
 
 #[tauri::command]
 pub fn get_idle_threshold(state: State<'_, AppState>) -> Result<u64, String> {
     let conn = state.db.conn();
-
-    let threshold: u64 = conn
-        .query_row(
-            "SELECT value FROM settings WHERE key = 'idle_threshold_seconds'",
-            [],
-            |row| row.get::<_, String>(0),
-        )
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(300);
-
-    Ok(threshold)
+    Ok(AppSettings::load(&conn).idle_threshold_seconds.max(0) as u64)
 }
 
 #[tauri::command]
 pub fn set_idle_threshold(state: State<'_, AppState>, seconds: u64) -> Result<(), String> {
-    let conn = state.db.conn();
+    let def = settings::lookup("idle_threshold_seconds").ok_or("Unknown setting")?;
+    let stored = def.to_stored(&Value::from(seconds))?;
+    save_registry_values(&state, "set_idle_threshold", &[(def.key, stored)])
+}
 
-    conn.execute(
-        "INSERT OR REPLACE INTO settings (key, value, updated_at)
-         VALUES ('idle_threshold_seconds', ?, strftime('%s', 'now'))",
-        [seconds.to_string()],
-    )
-    .map_err(|e| e.to_string())?;
+#[tauri::command]
+pub fn get_notification_settings(
+    state: State<'_, AppState>,
+) -> Result<NotificationSettings, String> {
+    let conn = state.db.conn();
+    Ok(NotificationSettings::load(&conn))
+}
 
-    Ok(())
+#[tauri::command]
+pub fn update_notification_settings(
+    state: State<'_, AppState>,
+    settings: NotificationSettings,
+) -> Result<(), String> {
+    settings.validate()?;
+    save_registry_values(&state, "update_notification_settings", &settings.values()?)
 }
@@ -0,0 +1,530 @@
+use crate::db::audit::{self, Snapshot};
+use crate::db::{RecordFilter, Tag, TagRule};
+use crate::state::AppState;
+use crate::tracking::{tagger, TagRules};
+use rusqlite::Connection;
+use tauri::State;
+
+fn get_tag(conn: &Connection, id: i64) -> Result<Tag, String> {
+    conn.query_row(
+        "SELECT id, name, color FROM tags WHERE id = ?",
+        [id],
+        |row| {
+            Ok(Tag {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                color: row.get(2)?,
+            })
+        },
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => format!("Tag {} not found", id),
+        e => e.to_string(),
+    })
+}
+
+fn max_activity_tag_id(conn: &Connection) -> Result<i64, String> {
+    conn.query_row(
+        "SELECT COALESCE(MAX(id), 0) FROM activity_tags",
+        [],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Log activity tags added since `max_id` was read.
+fn log_added_tags(conn: &Connection, action: &str, tag_id: i64, max_id: i64) -> Result<(), String> {
+    let after = Snapshot::new()
+        .capture(conn, "activity_tags", &[], "id > ?", [max_id])
+        .map_err(|e| e.to_string())?;
+    audit::record(conn, action, "tag", Some(tag_id), &Snapshot::new(), &after)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_tags(state: State<'_, AppState>) -> Result<Vec<Tag>, String> {
+    let conn = state.db.conn();
+
+    let mut stmt = conn
+        .prepare("SELECT id, name, color FROM tags ORDER BY name")
+        .map_err(|e| e.to_string())?;
+
+    let tags = stmt
+        .query_map([], |row| {
+            Ok(Tag {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                color: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(tags)
+}
+
+#[tauri::command]
+pub fn create_tag(state: State<'_, AppState>, name: String, color: String) -> Result<Tag, String> {
+    let conn = state.db.conn();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "INSERT INTO tags (name, color) VALUES (?, ?)",
+        [&name, &color],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = tx.last_insert_rowid();
+
+    let after = Snapshot::new()
+        .capture(&tx, "tags", &[], "id = ?", [id])
+        .map_err(|e| e.to_string())?;
+    audit::record(&tx, "create_tag", "tag", Some(id), &Snapshot::new(), &after)
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    get_tag(&conn, id)
+}
+
+#[tauri::command]
+pub fn update_tag(
+    state: State<'_, AppState>,
+    id: i64,
+    name: Option<String>,
+    color: Option<String>,
+) -> Result<Tag, String> {
+    let conn = state.db.conn();
+    let tag = get_tag(&conn, id)?;
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let capture = |conn: &Connection| {
+        Snapshot::new()
+            .capture(conn, "tags", &[], "id = ?", [id])
+            .map_err(|e| e.to_string())
+    };
+
+    let before = capture(&tx)?;
+    tx.execute(
+        "UPDATE tags SET name = ?, color = ? WHERE id = ?",
+        rusqlite::params![name.unwrap_or(tag.name), color.unwrap_or(tag.color), id],
+    )
+    .map_err(|e| e.to_string())?;
+    let after = capture(&tx)?;
+
+    audit::record(&tx, "update_tag", "tag", Some(id), &before, &after)
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    get_tag(&conn, id)
+}
+
+#[tauri::command]
+pub fn delete_tag(state: State<'_, AppState>, id: i64) -> Result<(), String> {
+    let conn = state.db.conn();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    // Assignments and rules are cascade-deleted with the tag
+    let before = Snapshot::new()
+        .capture(&tx, "tags", &[], "id = ?", [id])
+        .and_then(|s| s.capture(&tx, "tag_rules", &[], "tag_id = ?", [id]))
+        .and_then(|s| s.capture(&tx, "activity_tags", &[], "tag_id = ?", [id]))
+        .and_then(|s| s.capture(&tx, "project_tags", &[], "tag_id = ?", [id]))
+        .map_err(|e| e.to_string())?;
+
+    tx.execute("DELETE FROM tags WHERE id = ?", [id])
+        .map_err(|e| e.to_string())?;
+
+    audit::record(
+        &tx,
+        "delete_tag",
+        "tag",
+        Some(id),
+        &before,
+        &Snapshot::new(),
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    state.tag_rules_changed();
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_activity_tags(state: State<'_, AppState>, activity_id: i64) -> Result<Vec<Tag>, String> {
+    let conn = state.db.conn();
+
+    // Direct tags plus those inherited from the record's project
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.id, t.name, t.color FROM tags t
+             WHERE t.id IN (
+                 SELECT tag_id FROM activity_tags WHERE activity_id = ?1
+                 UNION
+                 SELECT pt.tag_id FROM project_tags pt
+                 JOIN activity_records ar ON ar.project_id = pt.project_id
+                 WHERE ar.id = ?1
+             )
+             ORDER BY t.name",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let tags = stmt
+        .query_map([activity_id], |row| {
+            Ok(Tag {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                color: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(tags)
+}
+
+/// Tag individual records. Returns the number of newly tagged records.
+#[tauri::command]
+pub fn tag_activity_records(
+    state: State<'_, AppState>,
+    tag_id: i64,
+    record_ids: Vec<i64>,
+) -> Result<usize, String> {
+    let conn = state.db.conn();
+    get_tag(&conn, tag_id)?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let max_id = max_activity_tag_id(&tx)?;
+
+    let mut added = 0;
+    for record_id in &record_ids {
+        added += tx
+            .execute(
+                "INSERT OR IGNORE INTO activity_tags (activity_id, tag_id) VALUES (?, ?)",
+                [*record_id, tag_id],
+            )
+            .map_err(|e| e.to_string())?;
+    }
+
+    log_added_tags(&tx, "tag_activity_records", tag_id, max_id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(added)
+}
+
+#[tauri::command]
+pub fn untag_activity_records(
+    state: State<'_, AppState>,
+    tag_id: i64,
+    record_ids: Vec<i64>,
+) -> Result<usize, String> {
+    let conn = state.db.conn();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    let before = record_ids
+        .iter()
+        .try_fold(Snapshot::new(), |snapshot, record_id| {
+            snapshot.capture(
+                &tx,
+                "activity_tags",
+                &[],
+                "activity_id = ? AND tag_id = ?",
+                [*record_id, tag_id],
+            )
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut removed = 0;
+    for record_id in &record_ids {
+        removed += tx
+            .execute(
+                "DELETE FROM activity_tags WHERE activity_id = ? AND tag_id = ?",
+                [*record_id, tag_id],
+            )
+            .map_err(|e| e.to_string())?;
+    }
+
+    audit::record(
+        &tx,
+        "untag_activity_records",
+        "tag",
+        Some(tag_id),
+        &before,
+        &Snapshot::new(),
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(removed)
+}
+
+/// Tag every non-idle record overlapping `[start_time, end_time)` that
+/// matches `filter`. Returns the number of newly tagged records.
+#[tauri::command]
+pub fn tag_activity_range(
+    state: State<'_, AppState>,
+    tag_id: i64,
+    start_time: i64,
+    end_time: i64,
+    filter: Option<RecordFilter>,
+) -> Result<usize, String> {
+    let conn = state.db.conn();
+    get_tag(&conn, tag_id)?;
+    let filter = filter.unwrap_or_default();
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let max_id = max_activity_tag_id(&tx)?;
+
+    let added = tx
+        .execute(
+            "INSERT OR IGNORE INTO activity_tags (activity_id, tag_id)
+             SELECT id, ?1 FROM activity_records
+             WHERE is_idle = 0 AND start_time < ?3 AND end_time > ?2
+               AND (?4 IS NULL OR application_id = ?4)
+               AND (?5 IS NULL OR category_id = ?5)
+               AND (?6 IS NULL OR project_id = ?6)
+               AND (?7 IS NULL OR window_title LIKE '%' || ?7 || '%')",
+            rusqlite::params![
+                tag_id,
+                start_time,
+                end_time,
+                filter.application_id,
+                filter.category_id,
+                filter.project_id,
+                filter.title_contains,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+
+    log_added_tags(&tx, "tag_activity_range", tag_id, max_id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(added)
+}
+
+#[tauri::command]
+pub fn tag_project(state: State<'_, AppState>, project_id: i64, tag_id: i64) -> Result<(), String> {
+    let conn = state.db.conn();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let capture = |conn: &Connection| {
+        Snapshot::new()
+            .capture(
+                conn,
+                "project_tags",
+                &[],
+                "project_id = ? AND tag_id = ?",
+                [project_id, tag_id],
+            )
+            .map_err(|e| e.to_string())
+    };
+
+    let before = capture(&tx)?;
+    tx.execute(
+        "INSERT OR IGNORE INTO project_tags (project_id, tag_id) VALUES (?, ?)",
+        [project_id, tag_id],
+    )
+    .map_err(|e| e.to_string())?;
+    let after = capture(&tx)?;
+
+    audit::record(&tx, "tag_project", "tag", Some(tag_id), &before, &after)
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn untag_project(
+    state: State<'_, AppState>,
+    project_id: i64,
+    tag_id: i64,
+) -> Result<(), String> {
+    let conn = state.db.conn();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    let before = Snapshot::new()
+        .capture(
+            &tx,
+            "project_tags",
+            &[],
+            "project_id = ? AND tag_id = ?",
+            [project_id, tag_id],
+        )
+        .map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM project_tags WHERE project_id = ? AND tag_id = ?",
+        [project_id, tag_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    audit::record(
+        &tx,
+        "untag_project",
+        "tag",
+        Some(tag_id),
+        &before,
+        &Snapshot::new(),
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_tag_rules(state: State<'_, AppState>) -> Result<Vec<TagRule>, String> {
+    let conn = state.db.conn();
+
+    let mut stmt = conn
+        .prepare("SELECT id, rule_type, pattern, tag_id, is_active FROM tag_rules ORDER BY id")
+        .map_err(|e| e.to_string())?;
+
+    let rules = stmt
+        .query_map([], |row| {
+            Ok(TagRule {
+                id: row.get(0)?,
+                rule_type: row.get(1)?,
+                pattern: row.get(2)?,
+                tag_id: row.get(3)?,
+                is_active: row.get::<_, i32>(4)? == 1,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok(rules)
+}
+
+/// Add a rule tagging new records whose app name, window title or URL
+/// matches `pattern` (a case-insensitive regular expression).
+#[tauri::command]
+pub fn create_tag_rule(
+    state: State<'_, AppState>,
+    rule_type: String,
+    pattern: String,
+    tag_id: i64,
+) -> Result<TagRule, String> {
+    tagger::compile(&pattern).map_err(|e| format!("Invalid pattern: {}", e))?;
+
+    let conn = state.db.conn();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "INSERT INTO tag_rules (rule_type, pattern, tag_id) VALUES (?, ?, ?)",
+        rusqlite::params![rule_type, pattern, tag_id],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = tx.last_insert_rowid();
+
+    let after = Snapshot::new()
+        .capture(&tx, "tag_rules", &[], "id = ?", [id])
+        .map_err(|e| e.to_string())?;
+    audit::record(
+        &tx,
+        "create_tag_rule",
+        "tag",
+        Some(tag_id),
+        &Snapshot::new(),
+        &after,
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    state.tag_rules_changed();
+
+    Ok(TagRule {
+        id,
+        rule_type,
+        pattern,
+        tag_id,
+        is_active: true,
+    })
+}
+
+#[tauri::command]
+pub fn delete_tag_rule(state: State<'_, AppState>, id: i64) -> Result<(), String> {
+    let conn = state.db.conn();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    let before = Snapshot::new()
+        .capture(&tx, "tag_rules", &[], "id = ?", [id])
+        .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM tag_rules WHERE id = ?", [id])
+        .map_err(|e| e.to_string())?;
+
+    audit::record(
+        &tx,
+        "delete_tag_rule",
+        "tag",
+        None,
+        &before,
+        &Snapshot::new(),
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    state.tag_rules_changed();
+
+    Ok(())
+}
+
+/// Run the active tag rules over existing records between two dates.
+/// Returns the number of tags added.
+#[tauri::command]
+pub fn apply_tag_rules(
+    state: State<'_, AppState>,
+    start_date: String,
+    end_date: String,
+) -> Result<usize, String> {
+    let conn = state.db.conn();
+    let rules = TagRules::load(&conn).map_err(|e| e.to_string())?;
+    if rules.is_empty() {
+        return Ok(0);
+    }
+
+    let records = conn
+        .prepare(
+            "SELECT ar.id, a.name, ar.window_title, ar.url
+             FROM activity_records ar
+             LEFT JOIN applications a ON ar.application_id = a.id
+             WHERE date(ar.start_time, 'unixepoch', 'localtime') >= ?
+               AND date(ar.start_time, 'unixepoch', 'localtime') <= ?",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map([&start_date, &end_date], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| e.to_string())?;
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let max_id = max_activity_tag_id(&tx)?;
+
+    let mut added = 0;
+    for (id, app_name, title, url) in &records {
+        added += rules
+            .apply(&tx, *id, app_name.as_deref(), title, url.as_deref())
+            .map_err(|e| e.to_string())?;
+    }
+
+    let after = Snapshot::new()
+        .capture(&tx, "activity_tags", &[], "id > ?", [max_id])
+        .map_err(|e| e.to_string())?;
+    audit::record(
+        &tx,
+        "apply_tag_rules",
+        "tag",
+        None,
+        &Snapshot::new(),
+        &after,
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(added)
+}
@@ -0,0 +1,106 @@
+use crate::commands::settings::save_registry_values;
+use crate::db::{TimerSession, TimerStatus};
+use crate::state::AppState;
+use crate::timer::{self, TimerSettings};
+use crate::tray;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+fn emit_phase(app: &AppHandle, status: &TimerStatus) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.emit("timer:phase", status);
+    }
+}
+
+#[tauri::command]
+pub fn get_timer_status(state: State<'_, AppState>) -> Result<TimerStatus, String> {
+    Ok(state.timer.lock().status(now()))
+}
+
+#[tauri::command]
+pub fn start_timer(app: AppHandle, state: State<'_, AppState>) -> Result<TimerStatus, String> {
+    let now = now();
+    let status = {
+        let mut timer = state.timer.lock();
+        timer.start(now)?;
+        timer.status(now)
+    };
+    emit_phase(&app, &status);
+    Ok(status)
+}
+
+#[tauri::command]
+pub fn pause_timer(state: State<'_, AppState>) -> Result<TimerStatus, String> {
+    let now = now();
+    let mut timer = state.timer.lock();
+    timer.pause(now)?;
+    Ok(timer.status(now))
+}
+
+#[tauri::command]
+pub fn resume_timer(state: State<'_, AppState>) -> Result<TimerStatus, String> {
+    let now = now();
+    let mut timer = state.timer.lock();
+    timer.resume(now)?;
+    Ok(timer.status(now))
+}
+
+/// End the current phase early and start the next one.
+#[tauri::command]
+pub fn skip_timer_phase(app: AppHandle, state: State<'_, AppState>) -> Result<TimerStatus, String> {
+    let now = now();
+    let (status, tracking_changed) = {
+        let mut timer = state.timer.lock();
+        let transition = timer.skip(now)?;
+        let tracking_changed = timer::apply_transition(&app, &state, &mut timer, &transition, now);
+        (timer.status(now), tracking_changed)
+    };
+    if tracking_changed {
+        tray::refresh(&app);
+    }
+    Ok(status)
+}
+
+/// Stop the cycle, resuming tracking if a break had paused it.
+#[tauri::command]
+pub fn stop_timer(app: AppHandle, state: State<'_, AppState>) -> Result<TimerStatus, String> {
+    let now = now();
+    let (status, tracking_changed) = {
+        let mut timer = state.timer.lock();
+        let transition = timer.stop(now)?;
+        let tracking_changed = timer::apply_transition(&app, &state, &mut timer, &transition, now);
+        (timer.status(now), tracking_changed)
+    };
+    if tracking_changed {
+        tray::refresh(&app);
+    }
+    Ok(status)
+}
+
+/// Finished phases that started between two dates.
+#[tauri::command]
+pub fn get_timer_sessions(
+    state: State<'_, AppState>,
+    start_date: String,
+    end_date: String,
+) -> Result<Vec<TimerSession>, String> {
+    let conn = state.db.conn();
+    timer::list_sessions(&conn, &start_date, &end_date).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_timer_settings(state: State<'_, AppState>) -> Result<TimerSettings, String> {
+    Ok(state.timer.lock().settings().clone())
+}
+
+#[tauri::command]
+pub fn update_timer_settings(
+    state: State<'_, AppState>,
+    settings: TimerSettings,
+) -> Result<(), String> {
+    settings.validate()?;
+    save_registry_values(&state, "update_timer_settings", &settings.values()?)
+}
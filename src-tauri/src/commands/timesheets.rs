@@ -0,0 +1,80 @@
+use crate::db::audit::{self, Snapshot};
+use crate::db::Timesheet;
+use crate::state::AppState;
+use crate::timesheet;
+use rusqlite::Connection;
+use tauri::State;
+
+fn capture_week(conn: &Connection, week_start: &str) -> Result<Snapshot, String> {
+    Snapshot::new()
+        .capture(conn, "timesheets", &[], "week_start = ?", [week_start])
+        .map_err(|e| e.to_string())
+}
+
+/// Project × day grid for the seven days starting at `week_start`.
+#[tauri::command]
+pub fn get_timesheet(state: State<'_, AppState>, week_start: String) -> Result<Timesheet, String> {
+    let week = timesheet::parse_week(&week_start).map_err(|e| e.to_string())?;
+    let conn = state.db.conn();
+
+    timesheet::load(&conn, week).map_err(|e| e.to_string())
+}
+
+/// Snapshot the week and lock it: recorder writes and edits for its days are
+/// rejected until it is unlocked.
+#[tauri::command]
+pub fn submit_timesheet(
+    state: State<'_, AppState>,
+    week_start: String,
+) -> Result<Timesheet, String> {
+    let week = timesheet::parse_week(&week_start).map_err(|e| e.to_string())?;
+    let conn = state.db.conn();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    let before = capture_week(&tx, &week.to_string())?;
+    let submitted = timesheet::submit(&tx, week).map_err(|e| e.to_string())?;
+    let after = capture_week(&tx, &week.to_string())?;
+
+    audit::record(&tx, "submit_timesheet", "timesheet", None, &before, &after)
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(submitted)
+}
+
+/// Reopen a submitted week. The reason is kept on the timesheet and in the audit log.
+#[tauri::command]
+pub fn unlock_timesheet(
+    state: State<'_, AppState>,
+    week_start: String,
+    reason: String,
+) -> Result<Timesheet, String> {
+    let reason = reason.trim();
+    if reason.is_empty() {
+        return Err("A reason is required to unlock a timesheet".to_string());
+    }
+
+    let week = timesheet::parse_week(&week_start).map_err(|e| e.to_string())?;
+    let conn = state.db.conn();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+
+    let before = capture_week(&tx, &week.to_string())?;
+    let unlocked = timesheet::unlock(&tx, week, reason).map_err(|e| e.to_string())?;
+    let after = capture_week(&tx, &week.to_string())?;
+
+    audit::record_with_reason(
+        &tx,
+        "unlock_timesheet",
+        "timesheet",
+        None,
+        &before,
+        &after,
+        Some(reason),
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    log::info!("Unlocked timesheet for week of {}: {}", week, reason);
+
+    Ok(unlocked)
+}
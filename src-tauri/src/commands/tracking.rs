@@ -1,22 +1,51 @@
-use crate::db::{TrackingStatus, WindowEvent};
+use crate::db::{SessionReason, TrackingSession, TrackingStatus, WindowEvent};
+use crate::sessions;
 use crate::state::AppState;
 use crate::tracking::WindowDetector;
-use tauri::State;
+use crate::tray;
+use tauri::{AppHandle, State};
 
 #[tauri::command]
-pub fn start_tracking(state: State<'_, AppState>) -> Result<(), String> {
-    state.set_tracking(true);
-    let now = chrono::Utc::now().timestamp();
-    state.set_session_start(Some(now));
-    log::info!("Tracking started");
+pub fn start_tracking(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    state.start_session(SessionReason::User);
+    state.save_tracking_state()?;
+    tray::refresh(&app);
     Ok(())
 }
 
 #[tauri::command]
-pub fn stop_tracking(state: State<'_, AppState>) -> Result<(), String> {
-    state.set_tracking(false);
-    state.set_session_start(None);
-    log::info!("Tracking stopped");
+pub fn stop_tracking(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    state.stop_session(SessionReason::User);
+    state.save_tracking_state()?;
+    tray::refresh(&app);
+    Ok(())
+}
+
+/// Attribute newly tracked activity to a project, or to none.
+#[tauri::command]
+pub fn set_current_project(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    project_id: Option<i64>,
+) -> Result<(), String> {
+    if let Some(id) = project_id {
+        let exists: bool = state
+            .db
+            .conn()
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM projects WHERE id = ?)",
+                [id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if !exists {
+            return Err(format!("Project {} not found", id));
+        }
+    }
+
+    state.set_current_project(project_id);
+    state.save_tracking_state()?;
+    tray::refresh(&app);
     Ok(())
 }
 
@@ -25,6 +54,24 @@ pub fn get_tracking_status(state: State<'_, AppState>) -> Result<TrackingStatus,
     Ok(state.get_tracking_status())
 }
 
+/// Tracking sessions started between two dates, with why each started and
+/// ended.
+#[tauri::command]
+pub fn get_sessions(
+    state: State<'_, AppState>,
+    start_date: String,
+    end_date: String,
+) -> Result<Vec<TrackingSession>, String> {
+    let conn = state.db.conn();
+    sessions::list(
+        &conn,
+        &start_date,
+        &end_date,
+        chrono::Utc::now().timestamp(),
+    )
+    .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn get_current_window() -> Result<Option<WindowEvent>, String> {
     let detector = WindowDetector::new();
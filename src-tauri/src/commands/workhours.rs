@@ -0,0 +1,134 @@
+use crate::commands::settings::save_registry_values;
+use crate::db::audit::{self, Snapshot};
+use crate::db::{DayOff, DayOffKind, WorkBalance};
+use crate::settings::{self, WorkSchedule};
+use crate::state::AppState;
+use crate::workhours::{self, WorkHoursSettings};
+use rusqlite::Connection;
+use tauri::State;
+
+/// Expected against actual active time per day, with overtime or undertime
+/// and the running balance.
+#[tauri::command]
+pub fn get_work_balance(
+    state: State<'_, AppState>,
+    start_date: String,
+    end_date: String,
+) -> Result<WorkBalance, String> {
+    let (start, end) = workhours::parse_range(&start_date, &end_date).map_err(|e| e.to_string())?;
+    let schedule = state.schedule.lock().schedule.clone();
+    let conn = state.db.conn();
+    let settings = WorkSchedule::load(&conn).work_hours;
+    workhours::work_balance(&conn, &settings, &schedule, start, end).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_work_hours_settings(state: State<'_, AppState>) -> Result<WorkHoursSettings, String> {
+    let conn = state.db.conn();
+    Ok(WorkSchedule::load(&conn).work_hours)
+}
+
+#[tauri::command]
+pub fn update_work_hours_settings(
+    state: State<'_, AppState>,
+    settings: WorkHoursSettings,
+) -> Result<WorkHoursSettings, String> {
+    settings.validate()?;
+    let def = settings::lookup("work_hours").ok_or("Unknown setting")?;
+    let value = serde_json::to_value(&settings).map_err(|e| e.to_string())?;
+    let stored = def.to_stored(&value)?;
+    save_registry_values(&state, "update_work_hours_settings", &[(def.key, stored)])?;
+    Ok(settings)
+}
+
+#[tauri::command]
+pub fn get_days_off(
+    state: State<'_, AppState>,
+    start_date: String,
+    end_date: String,
+) -> Result<Vec<DayOff>, String> {
+    let (start, end) = workhours::parse_range(&start_date, &end_date).map_err(|e| e.to_string())?;
+    let conn = state.db.conn();
+    workhours::get_days_off(&conn, start, end).map_err(|e| e.to_string())
+}
+
+/// Book vacation or sick leave over a range. Only days that would have
+/// expected work time are booked, and an existing entry for a day is
+/// replaced.
+#[tauri::command]
+pub fn add_days_off(
+    state: State<'_, AppState>,
+    start_date: String,
+    end_date: String,
+    kind: DayOffKind,
+    note: Option<String>,
+) -> Result<Vec<DayOff>, String> {
+    let (start, end) = workhours::parse_range(&start_date, &end_date).map_err(|e| e.to_string())?;
+    let schedule = state.schedule.lock().schedule.clone();
+
+    let conn = state.db.conn();
+    let settings = WorkSchedule::load(&conn).work_hours;
+    let days = workhours::working_days(&settings, &schedule, start, end);
+    if days.is_empty() {
+        return Err("No working days in that range".to_string());
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let capture = |conn: &Connection| {
+        Snapshot::new()
+            .capture(
+                conn,
+                "days_off",
+                &[],
+                "date BETWEEN ? AND ?",
+                [start.to_string(), end.to_string()],
+            )
+            .map_err(|e| e.to_string())
+    };
+
+    let before = capture(&tx)?;
+    for day in &days {
+        tx.execute(
+            "INSERT OR REPLACE INTO days_off (date, kind, note) VALUES (?, ?, ?)",
+            rusqlite::params![day.to_string(), kind.as_str(), note],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    let after = capture(&tx)?;
+
+    audit::record(&tx, "add_days_off", "days_off", None, &before, &after)
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    workhours::get_days_off(&conn, start, end).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn remove_day_off(state: State<'_, AppState>, date: String) -> Result<(), String> {
+    let date = workhours::parse_date(&date)
+        .map_err(|e| e.to_string())?
+        .to_string();
+
+    let conn = state.db.conn();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let capture = |conn: &Connection| {
+        Snapshot::new()
+            .capture(conn, "days_off", &[], "date = ?", [&date])
+            .map_err(|e| e.to_string())
+    };
+
+    let before = capture(&tx)?;
+    let removed = tx
+        .execute("DELETE FROM days_off WHERE date = ?", [&date])
+        .map_err(|e| e.to_string())?;
+    if removed == 0 {
+        return Err(format!("No day off on {}", date));
+    }
+    let after = capture(&tx)?;
+
+    audit::record(&tx, "remove_day_off", "days_off", None, &before, &after)
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(())
+}
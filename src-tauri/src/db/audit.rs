@@ -0,0 +1,435 @@
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{Connection, Params};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+use super::AuditEntry;
+
+/// Tables that can appear in a snapshot with their primary key, in the order
+/// rows are restored (parents before children). Deletions run in reverse.
+const TABLES: &[(&str, &str)] = &[
+    ("settings", "key"),
+    ("categories", "id"),
+    ("clients", "id"),
+    ("projects", "id"),
+    ("applications", "id"),
+    ("categorization_rules", "id"),
+    ("activity_records", "id"),
+    ("idle_periods", "id"),
+    ("tags", "id"),
+    ("tag_rules", "id"),
+    ("activity_tags", "id"),
+    ("project_tags", "id"),
+    ("invoice_templates", "id"),
+    ("timesheets", "id"),
+    ("goals", "id"),
+    ("days_off", "id"),
+];
+
+#[derive(Debug, Error)]
+pub enum AuditError {
+    #[error("database error: {0}")]
+    Database(#[from] rusqlite::Error),
+    #[error("invalid audit snapshot: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("nothing to undo")]
+    NothingToUndo,
+    #[error("a submitted timesheet cannot be undone; unlock it with a reason instead")]
+    SubmittedTimesheet,
+}
+
+type Row = Map<String, Value>;
+
+/// Rows of one or more tables captured before or after a change.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Snapshot(BTreeMap<String, Vec<Row>>);
+
+impl Snapshot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Capture rows of `table` matching `filter`.
+    ///
+    /// `columns` limits the snapshot to the primary key plus those columns,
+    /// which keeps large reference updates small; an empty slice captures whole
+    /// rows. Partial rows can only be restored onto rows that still exist.
+    pub fn capture<P: Params>(
+        mut self,
+        conn: &Connection,
+        table: &str,
+        columns: &[&str],
+        filter: &str,
+        params: P,
+    ) -> Result<Self, rusqlite::Error> {
+        let pk = primary_key(table);
+        let select = if columns.is_empty() {
+            "*".to_string()
+        } else {
+            std::iter::once(pk)
+                .chain(columns.iter().copied())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM {} WHERE {} ORDER BY {}",
+            select, table, filter, pk
+        ))?;
+        let names: Vec<String> = stmt.column_names().iter().map(|n| n.to_string()).collect();
+
+        let rows = stmt
+            .query_map(params, |row| {
+                let mut map = Row::new();
+                for (i, name) in names.iter().enumerate() {
+                    map.insert(name.clone(), to_json(row.get_ref(i)?));
+                }
+                Ok(map)
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.0.entry(table.to_string()).or_default().extend(rows);
+        Ok(self)
+    }
+
+    fn rows(&self, table: &str) -> &[Row] {
+        self.0.get(table).map(|r| r.as_slice()).unwrap_or_default()
+    }
+}
+
+/// Log a mutation. Call inside the same transaction as the change.
+pub fn record(
+    conn: &Connection,
+    action: &str,
+    entity_type: &str,
+    entity_id: Option<i64>,
+    before: &Snapshot,
+    after: &Snapshot,
+) -> Result<i64, AuditError> {
+    record_with_reason(conn, action, entity_type, entity_id, before, after, None)
+}
+
+/// Log a mutation together with the user's reason for it.
+pub fn record_with_reason(
+    conn: &Connection,
+    action: &str,
+    entity_type: &str,
+    entity_id: Option<i64>,
+    before: &Snapshot,
+    after: &Snapshot,
+    reason: Option<&str>,
+) -> Result<i64, AuditError> {
+    conn.execute(
+        "INSERT INTO audit_log (action, entity_type, entity_id, before_json, after_json, reason)
+         VALUES (?, ?, ?, ?, ?, ?)",
+        rusqlite::params![
+            action,
+            entity_type,
+            entity_id,
+            serde_json::to_string(before)?,
+            serde_json::to_string(after)?,
+            reason,
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn get_log(
+    conn: &Connection,
+    entity_type: Option<&str>,
+    limit: i64,
+) -> Result<Vec<AuditEntry>, AuditError> {
+    load_entries(
+        conn,
+        "WHERE ?1 IS NULL OR entity_type = ?1 ORDER BY id DESC LIMIT ?2",
+        rusqlite::params![entity_type, limit],
+    )
+}
+
+fn load_entries<P: Params>(
+    conn: &Connection,
+    clause: &str,
+    params: P,
+) -> Result<Vec<AuditEntry>, AuditError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, action, entity_type, entity_id, before_json, after_json, created_at, undone_at,
+                reason
+         FROM audit_log {}",
+        clause
+    ))?;
+
+    let rows = stmt
+        .query_map(params, |row| {
+            Ok((
+                AuditEntry {
+                    id: row.get(0)?,
+                    action: row.get(1)?,
+                    entity_type: row.get(2)?,
+                    entity_id: row.get(3)?,
+                    before: Value::Null,
+                    after: Value::Null,
+                    created_at: row.get(6)?,
+                    undone_at: row.get(7)?,
+                    reason: row.get(8)?,
+                },
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    rows.into_iter()
+        .map(|(mut entry, before, after)| {
+            entry.before = serde_json::from_str(&before)?;
+            entry.after = serde_json::from_str(&after)?;
+            Ok(entry)
+        })
+        .collect()
+}
+
+/// Revert the most recent change that has not been undone yet, restoring its
+/// `before` snapshot inside a transaction.
+pub fn undo_last(conn: &Connection) -> Result<AuditEntry, AuditError> {
+    let tx = conn.unchecked_transaction()?;
+
+    let (id, action, before, after): (i64, String, String, String) = tx
+        .query_row(
+            "SELECT id, action, before_json, after_json FROM audit_log
+             WHERE undone_at IS NULL ORDER BY id DESC LIMIT 1",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => AuditError::NothingToUndo,
+            e => e.into(),
+        })?;
+    // Reopening a week needs the reason `unlock` records
+    if action == "submit_timesheet" {
+        return Err(AuditError::SubmittedTimesheet);
+    }
+
+    let before: Snapshot = serde_json::from_str(&before)?;
+    let after: Snapshot = serde_json::from_str(&after)?;
+    restore(&tx, &before, &after)?;
+
+    tx.execute(
+        "UPDATE audit_log SET undone_at = strftime('%s', 'now') WHERE id = ?",
+        [id],
+    )?;
+    tx.commit()?;
+
+    load_entries(conn, "WHERE id = ?", [id])?
+        .pop()
+        .ok_or(AuditError::NothingToUndo)
+}
+
+fn restore(conn: &Connection, before: &Snapshot, after: &Snapshot) -> Result<(), AuditError> {
+    for &(table, pk) in TABLES {
+        let writable = writable_columns(conn, table)?;
+
+        for row in before.rows(table) {
+            let key = row.get(pk).map(to_sql).unwrap_or(SqlValue::Null);
+            let columns: Vec<&String> = row.keys().filter(|c| writable.contains(c)).collect();
+            let values: Vec<SqlValue> = columns.iter().map(|c| to_sql(&row[*c])).collect();
+
+            let exists: bool = conn.query_row(
+                &format!("SELECT EXISTS(SELECT 1 FROM {} WHERE {} = ?)", table, pk),
+                [&key],
+                |r| r.get(0),
+            )?;
+
+            if exists {
+                let assignments: Vec<String> = columns.iter().map(|c| format!("{} = ?", c)).collect();
+                let mut params = values;
+                params.push(key);
+                conn.execute(
+                    &format!("UPDATE {} SET {} WHERE {} = ?", table, assignments.join(", "), pk),
+                    rusqlite::params_from_iter(params),
+                )?;
+            } else {
+                let names: Vec<&str> = columns.iter().map(|c| c.as_str()).collect();
+                let placeholders = vec!["?"; names.len()].join(", ");
+                conn.execute(
+                    &format!("INSERT INTO {} ({}) VALUES ({})", table, names.join(", "), placeholders),
+                    rusqlite::params_from_iter(values),
+                )?;
+            }
+        }
+    }
+
+    // Rows that only exist after the change were created by it
+    for &(table, pk) in TABLES.iter().rev() {
+        let kept: Vec<&Value> = before.rows(table).iter().filter_map(|r| r.get(pk)).collect();
+        for row in after.rows(table) {
+            if let Some(key) = row.get(pk).filter(|k| !kept.contains(k)) {
+                conn.execute(
+                    &format!("DELETE FROM {} WHERE {} = ?", table, pk),
+                    [to_sql(key)],
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn primary_key(table: &str) -> &'static str {
+    TABLES
+        .iter()
+        .find(|(t, _)| *t == table)
+        .map(|(_, pk)| *pk)
+        .unwrap_or("id")
+}
+
+/// Columns that can be written, i.e. excluding generated columns.
+fn writable_columns(conn: &Connection, table: &str) -> Result<Vec<String>, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_xinfo({})", table))?;
+    let columns = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(1)?, row.get::<_, i64>(6)?))
+        })?
+        .filter_map(|r| match r {
+            Ok((name, 0)) => Some(Ok(name)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        })
+        .collect();
+    columns
+}
+
+fn to_json(value: ValueRef<'_>) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => Value::from(i),
+        ValueRef::Real(f) => Value::from(f),
+        ValueRef::Text(t) => Value::from(String::from_utf8_lossy(t).into_owned()),
+        ValueRef::Blob(b) => Value::from(b.to_vec()),
+    }
+}
+
+fn to_sql(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        Value::Array(a) => SqlValue::Blob(
+            a.iter()
+                .filter_map(|v| v.as_u64().map(|b| b as u8))
+                .collect(),
+        ),
+        Value::Object(_) => SqlValue::Text(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    #[test]
+    fn test_undo_restores_deleted_category_and_references() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+
+        conn.execute(
+            "INSERT INTO applications (name, category_id) VALUES ('Code', 2)",
+            [],
+        )
+        .expect("Failed to insert application");
+        conn.execute(
+            "INSERT INTO activity_records (application_id, window_title, start_time, end_time, category_id)
+             VALUES (1, 'main.rs', 1000, 2000, 2)",
+            [],
+        )
+        .expect("Failed to insert record");
+
+        let capture = |conn: &Connection| {
+            Snapshot::new()
+                .capture(conn, "categories", &[], "id = ?", [2])?
+                .capture(conn, "applications", &["category_id"], "category_id = ?", [2])?
+                .capture(conn, "activity_records", &["category_id"], "category_id = ?", [2])
+        };
+
+        let before = capture(&conn).expect("Failed to capture");
+        conn.execute("DELETE FROM categories WHERE id = 2", [])
+            .expect("Failed to delete category");
+        let after = capture(&conn).expect("Failed to capture");
+        record(&conn, "delete_category", "category", Some(2), &before, &after)
+            .expect("Failed to record");
+
+        let entry = undo_last(&conn).expect("Failed to undo");
+        assert_eq!(entry.action, "delete_category");
+        assert!(entry.undone_at.is_some());
+
+        let (name, app_category, record_category): (String, i64, i64) = conn
+            .query_row(
+                "SELECT c.name, a.category_id, ar.category_id
+                 FROM categories c, applications a, activity_records ar
+                 WHERE c.id = 2 AND a.id = 1 AND ar.id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .expect("Failed to read restored rows");
+        assert_eq!(name, "Development");
+        assert_eq!(app_category, 2);
+        assert_eq!(record_category, 2);
+
+        assert!(matches!(undo_last(&conn), Err(AuditError::NothingToUndo)));
+    }
+
+    #[test]
+    fn test_undo_removes_created_rows() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+
+        conn.execute("INSERT INTO categories (name) VALUES ('Reading')", [])
+            .expect("Failed to insert category");
+        let id = conn.last_insert_rowid();
+        let after = Snapshot::new()
+            .capture(&conn, "categories", &[], "id = ?", [id])
+            .expect("Failed to capture");
+        record(&conn, "create_category", "category", Some(id), &Snapshot::new(), &after)
+            .expect("Failed to record");
+
+        undo_last(&conn).expect("Failed to undo");
+
+        let exists: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM categories WHERE id = ?)",
+                [id],
+                |row| row.get(0),
+            )
+            .expect("Failed to query");
+        assert!(!exists);
+    }
+
+    #[test]
+    fn test_submitted_timesheet_is_not_undone() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+
+        conn.execute(
+            "INSERT INTO timesheets (week_start, status, snapshot_json, submitted_at)
+             VALUES ('2024-03-04', 'submitted', '{}', 0)",
+            [],
+        )
+        .expect("Failed to insert timesheet");
+        let after = Snapshot::new()
+            .capture(&conn, "timesheets", &[], "week_start = '2024-03-04'", [])
+            .expect("Failed to capture");
+        record(&conn, "submit_timesheet", "timesheet", None, &Snapshot::new(), &after)
+            .expect("Failed to record");
+
+        assert!(matches!(undo_last(&conn), Err(AuditError::SubmittedTimesheet)));
+        let status: String = conn
+            .query_row("SELECT status FROM timesheets", [], |row| row.get(0))
+            .expect("Failed to query");
+        assert_eq!(status, "submitted");
+    }
+}
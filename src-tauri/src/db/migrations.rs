@@ -13,7 +13,30 @@ pub fn run_all(conn: &Connection) -> Result<(), rusqlite::Error> {
 
     // Run each migration
     run_migration(conn, "001_initial_schema", migration_001_initial_schema)?;
+    run_migration(conn, "002_activity_source", migration_002_activity_source)?;
+    run_migration(conn, "003_audit_log", migration_003_audit_log)?;
+    run_migration(
+        conn,
+        "004_protected_categories",
+        migration_004_protected_categories,
+    )?;
 
+    run_migration(
+        conn,
+        "005_category_hierarchy",
+        migration_005_category_hierarchy,
+    )?;
+    run_migration(conn, "006_tags", migration_006_tags)?;
+    run_migration(conn, "007_activity_notes", migration_007_activity_notes)?;
+    run_migration(conn, "008_activity_search", migration_008_activity_search)?;
+    run_migration(conn, "009_billing", migration_009_billing)?;
+    run_migration(conn, "010_invoices", migration_010_invoices)?;
+    run_migration(conn, "011_timesheets", migration_011_timesheets)?;
+    run_migration(conn, "012_goals", migration_012_goals)?;
+    run_migration(conn, "013_timer_sessions", migration_013_timer_sessions)?;
+    run_migration(conn, "014_focus_sessions", migration_014_focus_sessions)?;
+    run_migration(conn, "015_days_off", migration_015_days_off)?;
+    run_migration(conn, "016_sessions", migration_016_sessions)?;
     Ok(())
 }
 
@@ -170,3 +193,433 @@ fn migration_001_initial_schema(conn: &Connection) -> Result<(), rusqlite::Error
 
     Ok(())
 }
+
+fn migration_002_activity_source(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        r#"
+        -- Where a record came from: the live tracker or an importer
+        ALTER TABLE activity_records ADD COLUMN source TEXT NOT NULL DEFAULT 'tracker';
+
+        CREATE INDEX IF NOT EXISTS idx_activity_source ON activity_records(source);
+        CREATE INDEX IF NOT EXISTS idx_idle_end ON idle_periods(end_time);
+        "#,
+    )?;
+
+    Ok(())
+}
+
+fn migration_003_audit_log(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        r#"
+        -- Before/after row snapshots of every user edit, used for undo
+        CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            action TEXT NOT NULL,
+            entity_type TEXT NOT NULL,
+            entity_id INTEGER,
+            before_json TEXT NOT NULL,
+            after_json TEXT NOT NULL,
+            undone_at INTEGER,
+            created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_audit_entity ON audit_log(entity_type, entity_id);
+        "#,
+    )?;
+
+    Ok(())
+}
+
+fn migration_004_protected_categories(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        r#"
+        -- Protected categories cannot be deleted; the seeded fallback is one
+        ALTER TABLE categories ADD COLUMN is_protected INTEGER NOT NULL DEFAULT 0;
+
+        UPDATE categories SET is_protected = 1 WHERE name = 'Uncategorized';
+        "#,
+    )?;
+
+    Ok(())
+}
+
+fn migration_005_category_hierarchy(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        r#"
+        -- Subcategories; is_productive holds the effective value, copied from
+        -- the parent while inherit_productive is set
+        ALTER TABLE categories ADD COLUMN parent_id INTEGER REFERENCES categories(id) ON DELETE SET NULL;
+        ALTER TABLE categories ADD COLUMN inherit_productive INTEGER NOT NULL DEFAULT 0;
+
+        CREATE INDEX IF NOT EXISTS idx_categories_parent ON categories(parent_id);
+        "#,
+    )?;
+
+    Ok(())
+}
+
+fn migration_006_tags(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        r#"
+        -- Free-form labels orthogonal to categories
+        CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            color TEXT NOT NULL DEFAULT '#6B7280',
+            created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS activity_tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            activity_id INTEGER NOT NULL REFERENCES activity_records(id) ON DELETE CASCADE,
+            tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+            UNIQUE (activity_id, tag_id)
+        );
+
+        -- Project tags apply to every record of the project
+        CREATE TABLE IF NOT EXISTS project_tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+            tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+            UNIQUE (project_id, tag_id)
+        );
+
+        -- Rules applied by the recorder to tag new records automatically
+        CREATE TABLE IF NOT EXISTS tag_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            rule_type TEXT NOT NULL CHECK (rule_type IN ('app_name', 'window_title', 'url')),
+            pattern TEXT NOT NULL,
+            tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+            is_active INTEGER NOT NULL DEFAULT 1,
+            created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_activity_tags_tag ON activity_tags(tag_id);
+        CREATE INDEX IF NOT EXISTS idx_project_tags_tag ON project_tags(tag_id);
+        "#,
+    )?;
+
+    Ok(())
+}
+
+fn migration_007_activity_notes(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        r#"
+        -- Free-text description of what the user was doing
+        ALTER TABLE activity_records ADD COLUMN notes TEXT;
+        "#,
+    )?;
+
+    Ok(())
+}
+
+fn migration_008_activity_search(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        r#"
+        -- Full-text index over activity records, kept in sync by triggers
+        CREATE VIRTUAL TABLE IF NOT EXISTS activity_search USING fts5(
+            window_title,
+            url,
+            notes,
+            content = 'activity_records',
+            content_rowid = 'id',
+            tokenize = 'unicode61 remove_diacritics 2'
+        );
+
+        CREATE TRIGGER IF NOT EXISTS activity_search_insert
+        AFTER INSERT ON activity_records BEGIN
+            INSERT INTO activity_search (rowid, window_title, url, notes)
+            VALUES (new.id, new.window_title, new.url, new.notes);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS activity_search_delete
+        AFTER DELETE ON activity_records BEGIN
+            INSERT INTO activity_search (activity_search, rowid, window_title, url, notes)
+            VALUES ('delete', old.id, old.window_title, old.url, old.notes);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS activity_search_update
+        AFTER UPDATE OF window_title, url, notes ON activity_records BEGIN
+            INSERT INTO activity_search (activity_search, rowid, window_title, url, notes)
+            VALUES ('delete', old.id, old.window_title, old.url, old.notes);
+            INSERT INTO activity_search (rowid, window_title, url, notes)
+            VALUES (new.id, new.window_title, new.url, new.notes);
+        END;
+
+        INSERT INTO activity_search (activity_search) VALUES ('rebuild');
+        "#,
+    )?;
+
+    Ok(())
+}
+
+fn migration_009_billing(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        r#"
+        -- Clients own projects and set the default rate, currency and rounding
+        CREATE TABLE IF NOT EXISTS clients (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            email TEXT,
+            address TEXT,
+            currency TEXT NOT NULL DEFAULT 'USD',
+            hourly_rate_cents INTEGER,
+            rounding_minutes INTEGER NOT NULL DEFAULT 0,
+            rounding_scope TEXT NOT NULL DEFAULT 'entry' CHECK (rounding_scope IN ('entry', 'day')),
+            created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+        );
+
+        -- Project rate and rounding override the client's when set
+        ALTER TABLE projects ADD COLUMN client_id INTEGER REFERENCES clients(id) ON DELETE SET NULL;
+        ALTER TABLE projects ADD COLUMN hourly_rate_cents INTEGER;
+        ALTER TABLE projects ADD COLUMN rounding_minutes INTEGER;
+        ALTER TABLE projects ADD COLUMN is_billable INTEGER NOT NULL DEFAULT 1;
+
+        -- NULL inherits the project's billable flag
+        ALTER TABLE activity_records ADD COLUMN is_billable INTEGER;
+
+        CREATE INDEX IF NOT EXISTS idx_projects_client ON projects(client_id);
+        "#,
+    )?;
+
+    Ok(())
+}
+
+fn migration_010_invoices(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        r#"
+        -- Branding for generated invoices; header and footer accept placeholders
+        CREATE TABLE IF NOT EXISTS invoice_templates (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            company_name TEXT NOT NULL DEFAULT '',
+            header_text TEXT NOT NULL DEFAULT '',
+            footer_text TEXT NOT NULL DEFAULT '',
+            accent_color TEXT NOT NULL DEFAULT '#3B82F6',
+            payment_terms_days INTEGER NOT NULL DEFAULT 30,
+            created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+        );
+
+        INSERT OR IGNORE INTO invoice_templates (name, footer_text)
+        VALUES ('Default', 'Payment due by {{due_date}}. Thank you for your business.');
+
+        -- Issued invoices are immutable; client details are copied at issue time
+        CREATE TABLE IF NOT EXISTS invoices (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            sequence INTEGER NOT NULL UNIQUE,
+            number TEXT NOT NULL UNIQUE,
+            client_id INTEGER REFERENCES clients(id) ON DELETE SET NULL,
+            client_name TEXT NOT NULL,
+            client_address TEXT,
+            template_id INTEGER REFERENCES invoice_templates(id) ON DELETE SET NULL,
+            start_date TEXT NOT NULL,
+            end_date TEXT NOT NULL,
+            currency TEXT NOT NULL,
+            issue_date TEXT NOT NULL,
+            due_date TEXT NOT NULL,
+            total_cents INTEGER NOT NULL,
+            created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS invoice_lines (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            invoice_id INTEGER NOT NULL REFERENCES invoices(id) ON DELETE CASCADE,
+            project_id INTEGER REFERENCES projects(id) ON DELETE SET NULL,
+            description TEXT NOT NULL,
+            billed_seconds INTEGER NOT NULL,
+            hourly_rate_cents INTEGER NOT NULL,
+            amount_cents INTEGER NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_invoice_lines_invoice ON invoice_lines(invoice_id);
+        "#,
+    )?;
+
+    Ok(())
+}
+
+fn migration_011_timesheets(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        r#"
+        -- Submitted weeks keep a snapshot of their grid and lock their records
+        CREATE TABLE IF NOT EXISTS timesheets (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            week_start TEXT NOT NULL UNIQUE,
+            status TEXT NOT NULL CHECK (status IN ('submitted', 'unlocked')),
+            snapshot_json TEXT NOT NULL,
+            submitted_at INTEGER NOT NULL,
+            unlocked_at INTEGER,
+            unlock_reason TEXT
+        );
+
+        ALTER TABLE audit_log ADD COLUMN reason TEXT;
+
+        -- Reject any write touching a day of a submitted week, whatever its source.
+        -- Reassigning a project or category changes the grid too, so deleting
+        -- one that would SET NULL on a locked record fails as well.
+        CREATE TRIGGER IF NOT EXISTS timesheet_lock_insert
+        BEFORE INSERT ON activity_records
+        WHEN EXISTS (
+            SELECT 1 FROM timesheets t
+            WHERE t.status = 'submitted'
+              AND (date(NEW.start_time, 'unixepoch', 'localtime')
+                       BETWEEN t.week_start AND date(t.week_start, '+6 days')
+                   OR date(NEW.end_time - 1, 'unixepoch', 'localtime')
+                       BETWEEN t.week_start AND date(t.week_start, '+6 days'))
+        )
+        BEGIN
+            SELECT RAISE(ABORT, 'timesheet locked: the week has been submitted');
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS timesheet_lock_update
+        BEFORE UPDATE OF application_id, window_title, url, start_time, end_time, notes,
+                         project_id, category_id, is_billable, is_idle
+        ON activity_records
+        WHEN EXISTS (
+            SELECT 1 FROM timesheets t
+            WHERE t.status = 'submitted'
+              AND (date(OLD.start_time, 'unixepoch', 'localtime')
+                       BETWEEN t.week_start AND date(t.week_start, '+6 days')
+                   OR date(OLD.end_time - 1, 'unixepoch', 'localtime')
+                       BETWEEN t.week_start AND date(t.week_start, '+6 days')
+                   OR date(NEW.start_time, 'unixepoch', 'localtime')
+                       BETWEEN t.week_start AND date(t.week_start, '+6 days')
+                   OR date(NEW.end_time - 1, 'unixepoch', 'localtime')
+                       BETWEEN t.week_start AND date(t.week_start, '+6 days'))
+        )
+        BEGIN
+            SELECT RAISE(ABORT, 'timesheet locked: the week has been submitted');
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS timesheet_lock_delete
+        BEFORE DELETE ON activity_records
+        WHEN EXISTS (
+            SELECT 1 FROM timesheets t
+            WHERE t.status = 'submitted'
+              AND (date(OLD.start_time, 'unixepoch', 'localtime')
+                       BETWEEN t.week_start AND date(t.week_start, '+6 days')
+                   OR date(OLD.end_time - 1, 'unixepoch', 'localtime')
+                       BETWEEN t.week_start AND date(t.week_start, '+6 days'))
+        )
+        BEGIN
+            SELECT RAISE(ABORT, 'timesheet locked: the week has been submitted');
+        END;
+        "#,
+    )?;
+
+    Ok(())
+}
+
+fn migration_012_goals(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        r#"
+        -- Minimum targets and maximum limits on time per category or app
+        CREATE TABLE IF NOT EXISTS goals (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            target_type TEXT NOT NULL CHECK (target_type IN ('category', 'application')),
+            target_id INTEGER NOT NULL,
+            kind TEXT NOT NULL CHECK (kind IN ('min', 'max')),
+            period TEXT NOT NULL CHECK (period IN ('day', 'week')),
+            target_seconds INTEGER NOT NULL CHECK (target_seconds > 0),
+            -- Bitmask of weekdays a daily goal applies to, Monday = 1 ... Sunday = 64
+            active_days INTEGER NOT NULL DEFAULT 127,
+            is_active INTEGER NOT NULL DEFAULT 1,
+            created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+        );
+        "#,
+    )?;
+
+    Ok(())
+}
+
+fn migration_013_timer_sessions(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        r#"
+        -- Finished pomodoro phases; skipped or stopped phases have completed = 0
+        CREATE TABLE IF NOT EXISTS timer_sessions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            phase TEXT NOT NULL CHECK (phase IN ('work', 'short_break', 'long_break')),
+            started_at INTEGER NOT NULL,
+            ended_at INTEGER NOT NULL,
+            planned_seconds INTEGER NOT NULL,
+            actual_seconds INTEGER NOT NULL,
+            completed INTEGER NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_timer_sessions_started ON timer_sessions(started_at);
+        "#,
+    )?;
+
+    Ok(())
+}
+
+fn migration_014_focus_sessions(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        r#"
+        -- Focus blocks; ended_at is NULL while one is running
+        CREATE TABLE IF NOT EXISTS focus_sessions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            started_at INTEGER NOT NULL,
+            ended_at INTEGER
+        );
+
+        -- Stretches spent in non-productive windows during a focus block
+        CREATE TABLE IF NOT EXISTS focus_distractions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id INTEGER NOT NULL REFERENCES focus_sessions(id) ON DELETE CASCADE,
+            application_id INTEGER REFERENCES applications(id) ON DELETE SET NULL,
+            app_name TEXT NOT NULL,
+            window_title TEXT NOT NULL,
+            category_id INTEGER REFERENCES categories(id) ON DELETE SET NULL,
+            started_at INTEGER NOT NULL,
+            ended_at INTEGER
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_focus_sessions_started ON focus_sessions(started_at);
+        CREATE INDEX IF NOT EXISTS idx_focus_distractions_session ON focus_distractions(session_id);
+        "#,
+    )?;
+
+    Ok(())
+}
+
+fn migration_015_days_off(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        r#"
+        -- Vacation and sick days, which carry no expected work time
+        CREATE TABLE IF NOT EXISTS days_off (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            date TEXT NOT NULL UNIQUE,
+            kind TEXT NOT NULL CHECK (kind IN ('vacation', 'sick')),
+            note TEXT,
+            created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+        );
+        "#,
+    )?;
+
+    Ok(())
+}
+
+fn migration_016_sessions(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        r#"
+        -- Stretches of continuous tracking and what started and ended them
+        CREATE TABLE IF NOT EXISTS sessions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            started_at INTEGER NOT NULL,
+            ended_at INTEGER,
+            start_reason TEXT NOT NULL,
+            end_reason TEXT
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_sessions_started_at ON sessions(started_at);
+
+        ALTER TABLE activity_records ADD COLUMN session_id INTEGER REFERENCES sessions(id) ON DELETE SET NULL;
+
+        CREATE INDEX IF NOT EXISTS idx_activity_session ON activity_records(session_id);
+        "#,
+    )?;
+
+    Ok(())
+}
@@ -1,3 +1,4 @@
+pub mod audit;
 pub mod connection;
 pub mod migrations;
 pub mod models;
@@ -5,20 +6,23 @@ pub mod models;
 pub use connection::Database;
 pub use models::*;
 
+/// A migrated database in a temporary directory, removed when the returned
+/// `TempDir` is dropped.
+#[cfg(test)]
+pub(crate) fn test_db() -> (Database, tempfile::TempDir) {
+    let temp_dir = tempfile::TempDir::new().expect("Failed to create temp dir");
+    let db = Database::new(temp_dir.path().to_path_buf()).expect("Failed to create database");
+    (db, temp_dir)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tempfile::TempDir;
-
-    fn create_test_db() -> (Database, TempDir) {
-        let temp_dir = TempDir::new().expect("Failed to create temp dir");
-        let db = Database::new(temp_dir.path().to_path_buf()).expect("Failed to create database");
-        (db, temp_dir)
-    }
+    use crate::settings::AppSettings;
 
     #[test]
     fn test_database_creation() {
-        let (db, _temp_dir) = create_test_db();
+        let (db, _temp_dir) = test_db();
         let conn = db.conn();
 
         // Verify tables were created
@@ -36,7 +40,7 @@ mod tests {
 
     #[test]
     fn test_default_categories_created() {
-        let (db, _temp_dir) = create_test_db();
+        let (db, _temp_dir) = test_db();
         let conn = db.conn();
 
         let category_count: i64 = conn
@@ -49,7 +53,7 @@ mod tests {
 
     #[test]
     fn test_insert_and_retrieve_category() {
-        let (db, _temp_dir) = create_test_db();
+        let (db, _temp_dir) = test_db();
         let conn = db.conn();
 
         // Insert a new category
@@ -72,7 +76,7 @@ mod tests {
 
     #[test]
     fn test_insert_activity_record() {
-        let (db, _temp_dir) = create_test_db();
+        let (db, _temp_dir) = test_db();
         let conn = db.conn();
 
         // Insert an application first
@@ -105,7 +109,7 @@ mod tests {
 
     #[test]
     fn test_database_clone() {
-        let (db, _temp_dir) = create_test_db();
+        let (db, _temp_dir) = test_db();
         let db_clone = db.clone();
 
         // Both should work and access the same data
@@ -143,7 +147,7 @@ mod tests {
 
     #[test]
     fn test_daily_summary_table() {
-        let (db, _temp_dir) = create_test_db();
+        let (db, _temp_dir) = test_db();
         let conn = db.conn();
 
         // Insert a daily summary
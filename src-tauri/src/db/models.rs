@@ -9,6 +9,9 @@ pub struct Category {
     pub is_productive: bool,
     pub created_at: i64,
     pub updated_at: i64,
+    pub is_protected: bool,
+    pub parent_id: Option<i64>,
+    pub inherit_productive: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +22,10 @@ pub struct Project {
     pub category_id: Option<i64>,
     pub color: String,
     pub is_active: bool,
+    pub client_id: Option<i64>,
+    pub hourly_rate_cents: Option<i64>,
+    pub rounding_minutes: Option<i64>,
+    pub is_billable: bool,
     pub created_at: i64,
     pub updated_at: i64,
 }
@@ -47,6 +54,10 @@ pub struct ActivityRecord {
     pub category_id: Option<i64>,
     pub project_id: Option<i64>,
     pub is_idle: bool,
+    pub source: String,
+    pub notes: Option<String>,
+    /// `None` inherits the project's billable flag
+    pub is_billable: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -99,6 +110,7 @@ pub struct TrackingStatus {
     pub current_window: Option<String>,
     pub today_total_seconds: i64,
     pub session_start_time: Option<i64>,
+    pub current_project_id: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -117,23 +129,637 @@ pub struct CategoryStats {
     pub color: String,
     pub total_seconds: i64,
     pub is_productive: bool,
+    pub parent_id: Option<i64>,
+    /// Time recorded directly against this category, excluding subcategories
+    pub own_seconds: i64,
+    pub children: Vec<CategoryStats>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AppSettings {
-    pub idle_threshold_seconds: i64,
-    pub tracking_enabled: bool,
-    pub polling_interval_ms: i64,
-    pub theme: String,
+pub struct TimelineBlock {
+    pub group_id: Option<i64>,
+    pub label: String,
+    pub color: String,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub active_seconds: i64,
+    pub record_count: i64,
+    pub notes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub records_imported: i64,
+    pub records_skipped: i64,
+    pub idle_periods_imported: i64,
+    pub idle_periods_skipped: i64,
+    pub applications_created: i64,
+}
+
+/// Column indexes for a time-entry CSV; `None` means the column is absent.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CsvColumnMapping {
+    pub project: Option<usize>,
+    pub client: Option<usize>,
+    pub description: Option<usize>,
+    pub tags: Option<usize>,
+    #[serde(default)]
+    pub billable: Option<usize>,
+    pub start_date: Option<usize>,
+    pub start_time: Option<usize>,
+    pub end_date: Option<usize>,
+    pub end_time: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvImportPreview {
+    pub format: String,
+    pub headers: Vec<String>,
+    pub mapping: CsvColumnMapping,
+    pub sample_rows: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvConflict {
+    pub row: usize,
+    pub description: String,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub conflicting_seconds: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CsvImportReport {
+    pub format: String,
+    pub dry_run: bool,
+    pub entries_imported: i64,
+    pub entries_skipped: i64,
+    pub projects_created: i64,
+    pub clients_created: i64,
+    pub conflicts: Vec<CsvConflict>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: Option<i64>,
+    pub before: serde_json::Value,
+    pub after: serde_json::Value,
+    pub created_at: i64,
+    pub undone_at: Option<i64>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresetPackInfo {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub category_count: usize,
+    pub rule_count: usize,
+    pub application_count: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PresetImportReport {
+    pub categories_created: i64,
+    pub categories_updated: i64,
+    pub categories_skipped: i64,
+    pub rules_created: i64,
+    pub rules_updated: i64,
+    pub rules_skipped: i64,
+    pub applications_assigned: i64,
+    pub applications_skipped: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+    pub color: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagRule {
+    pub id: i64,
+    pub rule_type: String,
+    pub pattern: String,
+    pub tag_id: i64,
+    pub is_active: bool,
+}
+
+/// Narrows a range tagging operation; unset fields match everything.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecordFilter {
+    pub application_id: Option<i64>,
+    pub category_id: Option<i64>,
+    pub project_id: Option<i64>,
+    pub title_contains: Option<String>,
+}
+
+/// Records sharing a window title that matched a full-text search.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivitySearchMatch {
+    pub window_title: String,
+    pub app_name: Option<String>,
+    /// HTML-escaped excerpt with matched terms wrapped in `<mark>`
+    pub snippet: String,
+    pub record_ids: Vec<i64>,
+    pub total_seconds: i64,
+    pub first_seen: i64,
+    pub last_seen: i64,
+}
+
+/// Whether billing rounds each record or each project's daily total.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RoundingScope {
+    Entry,
+    Day,
+}
+
+impl RoundingScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RoundingScope::Entry => "entry",
+            RoundingScope::Day => "day",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "day" => RoundingScope::Day,
+            _ => RoundingScope::Entry,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Client {
+    pub id: i64,
+    pub name: String,
+    pub email: Option<String>,
+    pub address: Option<String>,
+    pub currency: String,
+    pub hourly_rate_cents: Option<i64>,
+    /// Round billed time up to a multiple of this many minutes; 0 disables
+    pub rounding_minutes: i64,
+    pub rounding_scope: RoundingScope,
+    pub created_at: i64,
+}
+
+/// Editable client fields, used for both create and update.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientInput {
+    pub name: String,
+    pub email: Option<String>,
+    pub address: Option<String>,
+    pub currency: String,
+    pub hourly_rate_cents: Option<i64>,
+    pub rounding_minutes: i64,
+    pub rounding_scope: RoundingScope,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BillingLineItem {
+    pub project_id: i64,
+    pub project_name: String,
+    pub entry_count: i64,
+    /// Billable time as tracked
+    pub tracked_seconds: i64,
+    /// Billable time after rounding
+    pub billed_seconds: i64,
+    pub hourly_rate_cents: i64,
+    pub amount_cents: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BillingSummary {
+    pub client: Client,
+    pub start_date: String,
+    pub end_date: String,
+    pub currency: String,
+    pub line_items: Vec<BillingLineItem>,
+    pub tracked_seconds: i64,
+    pub billed_seconds: i64,
+    pub non_billable_seconds: i64,
+    pub total_cents: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvoiceTemplate {
+    pub id: i64,
+    pub name: String,
+    pub company_name: String,
+    /// Text above the invoice; supports `{{placeholders}}`
+    pub header_text: String,
+    /// Text below the totals; supports `{{placeholders}}`
+    pub footer_text: String,
+    pub accent_color: String,
+    pub payment_terms_days: i64,
+}
+
+/// Editable template fields, used for both create and update.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvoiceTemplateInput {
+    pub name: String,
+    pub company_name: String,
+    pub header_text: String,
+    pub footer_text: String,
+    pub accent_color: String,
+    pub payment_terms_days: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvoiceLine {
+    pub id: i64,
+    pub project_id: Option<i64>,
+    pub description: String,
+    pub billed_seconds: i64,
+    pub hourly_rate_cents: i64,
+    pub amount_cents: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Invoice {
+    pub id: i64,
+    pub number: String,
+    pub client_id: Option<i64>,
+    pub client_name: String,
+    pub client_address: Option<String>,
+    pub template_id: Option<i64>,
+    pub start_date: String,
+    pub end_date: String,
+    pub currency: String,
+    pub issue_date: String,
+    pub due_date: String,
+    pub total_cents: i64,
+    pub lines: Vec<InvoiceLine>,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InvoiceFormat {
+    Html,
+    Pdf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimesheetStatus {
+    Open,
+    Submitted,
+    Unlocked,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimesheetRow {
+    pub project_id: Option<i64>,
+    pub project_name: String,
+    pub color: String,
+    /// Seconds per day, one entry per day of the week
+    pub day_seconds: Vec<i64>,
+    pub total_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Timesheet {
+    pub week_start: String,
+    pub days: Vec<String>,
+    pub rows: Vec<TimesheetRow>,
+    pub day_totals: Vec<i64>,
+    pub total_seconds: i64,
+    pub status: TimesheetStatus,
+    pub submitted_at: Option<i64>,
+    pub unlocked_at: Option<i64>,
+    pub unlock_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GoalTarget {
+    Category,
+    Application,
 }
 
-impl Default for AppSettings {
-    fn default() -> Self {
-        Self {
-            idle_threshold_seconds: 300,
-            tracking_enabled: true,
-            polling_interval_ms: 1000,
-            theme: "system".to_string(),
+impl GoalTarget {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GoalTarget::Category => "category",
+            GoalTarget::Application => "application",
         }
     }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "application" => GoalTarget::Application,
+            _ => GoalTarget::Category,
+        }
+    }
+}
+
+/// `Min` goals are targets to reach, `Max` goals are limits not to exceed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GoalKind {
+    Min,
+    Max,
+}
+
+impl GoalKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GoalKind::Min => "min",
+            GoalKind::Max => "max",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "max" => GoalKind::Max,
+            _ => GoalKind::Min,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GoalPeriod {
+    Day,
+    Week,
+}
+
+impl GoalPeriod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GoalPeriod::Day => "day",
+            GoalPeriod::Week => "week",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "week" => GoalPeriod::Week,
+            _ => GoalPeriod::Day,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Goal {
+    pub id: i64,
+    pub name: String,
+    pub target_type: GoalTarget,
+    pub target_id: i64,
+    pub kind: GoalKind,
+    pub period: GoalPeriod,
+    pub target_seconds: i64,
+    /// Weekdays a daily goal applies to, Monday = 1 ... Sunday = 64
+    pub active_days: i64,
+    pub is_active: bool,
+    pub created_at: i64,
+}
+
+/// Editable goal fields, used for both create and update.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalInput {
+    pub name: String,
+    pub target_type: GoalTarget,
+    pub target_id: i64,
+    pub kind: GoalKind,
+    pub period: GoalPeriod,
+    pub target_seconds: i64,
+    pub active_days: i64,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalStatus {
+    /// A target not reached yet, or a limit not reached
+    InProgress,
+    Met,
+    Exceeded,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalProgress {
+    pub goal: Goal,
+    pub period_start: String,
+    pub period_end: String,
+    pub actual_seconds: i64,
+    pub target_seconds: i64,
+    pub percent: f64,
+    pub status: GoalStatus,
+}
+
+/// The activity the recorder is currently timing, not yet saved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActiveActivity {
+    pub application_id: Option<i64>,
+    pub start_time: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimerPhase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl TimerPhase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimerPhase::Work => "work",
+            TimerPhase::ShortBreak => "short_break",
+            TimerPhase::LongBreak => "long_break",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "short_break" => TimerPhase::ShortBreak,
+            "long_break" => TimerPhase::LongBreak,
+            _ => TimerPhase::Work,
+        }
+    }
+
+    pub fn is_break(&self) -> bool {
+        !matches!(self, TimerPhase::Work)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimerState {
+    Stopped,
+    Running,
+    Paused,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimerStatus {
+    pub state: TimerState,
+    pub phase: TimerPhase,
+    pub phase_started_at: Option<i64>,
+    pub duration_seconds: i64,
+    pub remaining_seconds: i64,
+    /// Work phases completed since the timer was started
+    pub completed_work_sessions: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimerSession {
+    pub id: i64,
+    pub phase: TimerPhase,
+    pub started_at: i64,
+    pub ended_at: i64,
+    pub planned_seconds: i64,
+    pub actual_seconds: i64,
+    pub completed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusDistraction {
+    pub id: i64,
+    pub session_id: i64,
+    pub application_id: Option<i64>,
+    pub app_name: String,
+    pub window_title: String,
+    pub category_id: Option<i64>,
+    pub category_name: Option<String>,
+    pub started_at: i64,
+    pub ended_at: Option<i64>,
+}
+
+/// A focus block with its distractions. Open sessions and distractions are
+/// measured up to now.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusSessionReport {
+    pub id: i64,
+    pub started_at: i64,
+    pub ended_at: Option<i64>,
+    pub duration_seconds: i64,
+    pub distraction_count: i64,
+    pub distraction_seconds: i64,
+    pub focused_seconds: i64,
+    pub distractions: Vec<FocusDistraction>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DayOffKind {
+    Vacation,
+    Sick,
+}
+
+impl DayOffKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DayOffKind::Vacation => "vacation",
+            DayOffKind::Sick => "sick",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "sick" => DayOffKind::Sick,
+            _ => DayOffKind::Vacation,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DayOff {
+    pub id: i64,
+    pub date: String,
+    pub kind: DayOffKind,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkBalanceDay {
+    pub date: String,
+    pub expected_seconds: i64,
+    pub actual_seconds: i64,
+    /// Overtime when positive, undertime when negative
+    pub difference_seconds: i64,
+    /// Running balance at the end of this day
+    pub balance_seconds: i64,
+    pub day_off: Option<DayOffKind>,
+    pub is_holiday: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkBalance {
+    pub start_date: String,
+    pub end_date: String,
+    /// Balance carried in from days before `start_date`
+    pub opening_balance_seconds: i64,
+    pub days: Vec<WorkBalanceDay>,
+    pub expected_seconds: i64,
+    pub actual_seconds: i64,
+    pub difference_seconds: i64,
+    pub closing_balance_seconds: i64,
+}
+
+/// Why a tracking session started or ended. A session's start reason is
+/// what ended the gap before it, so one starting with `idle` is the user
+/// coming back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionReason {
+    User,
+    Schedule,
+    Break,
+    Idle,
+    Suspend,
+    Quit,
+    /// Tracking was still on from the previous run at launch
+    Startup,
+}
+
+impl SessionReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionReason::User => "user",
+            SessionReason::Schedule => "schedule",
+            SessionReason::Break => "break",
+            SessionReason::Idle => "idle",
+            SessionReason::Suspend => "suspend",
+            SessionReason::Quit => "quit",
+            SessionReason::Startup => "startup",
+        }
+    }
+
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "schedule" => SessionReason::Schedule,
+            "break" => SessionReason::Break,
+            "idle" => SessionReason::Idle,
+            "suspend" => SessionReason::Suspend,
+            "quit" => SessionReason::Quit,
+            "startup" => SessionReason::Startup,
+            _ => SessionReason::User,
+        }
+    }
+}
+
+/// A stretch of continuous tracking. An open session is measured up to now.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackingSession {
+    pub id: i64,
+    pub started_at: i64,
+    pub ended_at: Option<i64>,
+    pub start_reason: SessionReason,
+    pub end_reason: Option<SessionReason>,
+    pub duration_seconds: i64,
+    /// Time in the activity records linked to the session
+    pub active_seconds: i64,
+    pub record_count: i64,
 }
@@ -0,0 +1,172 @@
+use super::GroupBy;
+use crate::db::TimelineBlock;
+use chrono::{DateTime, Utc};
+
+const PRODID: &str = "-//Time Tracker//Time Tracker//EN";
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Render timeline blocks as an RFC 5545 calendar.
+///
+/// UIDs are derived from the grouping, group id and block start, so exporting
+/// the same range again updates existing events instead of duplicating them.
+/// Colours are left out: RFC 7986 `COLOR` takes CSS colour names, and
+/// categories store hex values.
+pub fn render_calendar(blocks: &[TimelineBlock], group_by: GroupBy, stamp: i64) -> String {
+    let mut out = String::new();
+
+    push_line(&mut out, "BEGIN:VCALENDAR");
+    push_line(&mut out, "VERSION:2.0");
+    push_line(&mut out, &format!("PRODID:{}", PRODID));
+    push_line(&mut out, "CALSCALE:GREGORIAN");
+    push_line(&mut out, "METHOD:PUBLISH");
+    push_line(&mut out, "X-WR-CALNAME:Time Tracker");
+
+    for block in blocks {
+        push_line(&mut out, "BEGIN:VEVENT");
+        push_line(&mut out, &format!("UID:{}", event_uid(block, group_by)));
+        push_line(&mut out, &format!("DTSTAMP:{}", format_timestamp(stamp)));
+        push_line(&mut out, &format!("DTSTART:{}", format_timestamp(block.start_time)));
+        push_line(&mut out, &format!("DTEND:{}", format_timestamp(block.end_time)));
+        push_line(&mut out, &format!("SUMMARY:{}", escape_text(&block.label)));
+        push_line(
+            &mut out,
+            &format!("DESCRIPTION:{}", escape_text(&describe(block))),
+        );
+        push_line(&mut out, &format!("CATEGORIES:{}", escape_text(&block.label)));
+        push_line(&mut out, "TRANSP:OPAQUE");
+        push_line(&mut out, "END:VEVENT");
+    }
+
+    push_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+pub fn event_uid(block: &TimelineBlock, group_by: GroupBy) -> String {
+    format!(
+        "{}-{}-{}@timetracker",
+        group_by.as_str(),
+        block.group_id.unwrap_or(0),
+        block.start_time
+    )
+}
+
+fn describe(block: &TimelineBlock) -> String {
+    let minutes = block.active_seconds / 60;
+    let mut description = format!(
+        "Tracked {}h {:02}m across {} activities",
+        minutes / 60,
+        minutes % 60,
+        block.record_count
+    );
+    for note in &block.notes {
+        description.push('\n');
+        description.push_str(note);
+    }
+    description
+}
+
+fn format_timestamp(ts: i64) -> String {
+    DateTime::<Utc>::from_timestamp(ts, 0)
+        .unwrap_or_default()
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
+
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Append a content line, folding it at 75 octets without splitting UTF-8 characters.
+fn push_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > MAX_LINE_OCTETS {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(group_id: Option<i64>, label: &str, start: i64, end: i64) -> TimelineBlock {
+        TimelineBlock {
+            group_id,
+            label: label.to_string(),
+            color: "#3B82F6".to_string(),
+            start_time: start,
+            end_time: end,
+            active_seconds: end - start,
+            record_count: 3,
+            notes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_uid_is_stable_across_exports() {
+        let blocks = vec![block(Some(2), "Development", 1_700_000_000, 1_700_003_600)];
+
+        let first = render_calendar(&blocks, GroupBy::Category, 1_700_010_000);
+        let second = render_calendar(&blocks, GroupBy::Category, 1_700_020_000);
+
+        let uid = |ics: &str| {
+            ics.lines()
+                .find(|l| l.starts_with("UID:"))
+                .map(|l| l.to_string())
+        };
+        assert_eq!(uid(&first), uid(&second));
+        assert_eq!(
+            uid(&first).unwrap(),
+            "UID:category-2-1700000000@timetracker"
+        );
+        assert!(!first.contains("COLOR:"));
+    }
+
+    #[test]
+    fn test_event_times_and_escaping() {
+        let blocks = vec![block(Some(1), "Client; A, B", 1_700_000_000, 1_700_003_600)];
+        let ics = render_calendar(&blocks, GroupBy::Project, 1_700_010_000);
+
+        assert!(ics.contains("DTSTART:20231114T221320Z\r\n"));
+        assert!(ics.contains("DTEND:20231114T231320Z\r\n"));
+        assert!(ics.contains("SUMMARY:Client\\; A\\, B\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    }
+
+    #[test]
+    fn test_notes_are_added_to_description() {
+        let mut noted = block(Some(1), "Client", 0, 3600);
+        noted.notes = vec!["Sprint planning".to_string()];
+        let ics = render_calendar(&[noted], GroupBy::Project, 0);
+
+        assert!(ics.contains("activities\\nSprint planning"));
+    }
+
+    #[test]
+    fn test_long_lines_are_folded() {
+        let label = "é".repeat(60);
+        let blocks = vec![block(None, &label, 0, 3600)];
+        let ics = render_calendar(&blocks, GroupBy::Category, 0);
+
+        for line in ics.split("\r\n") {
+            assert!(line.len() <= MAX_LINE_OCTETS, "line too long: {}", line);
+        }
+        assert!(ics.contains("\r\n é"));
+    }
+}
@@ -0,0 +1,4 @@
+pub mod ics;
+pub mod timeline;
+
+pub use timeline::{build_blocks, GroupBy};
@@ -0,0 +1,108 @@
+use crate::db::TimelineBlock;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+/// Records of the same group separated by less than this are merged into one block.
+pub const MERGE_GAP_SECONDS: i64 = 300;
+
+/// Blocks shorter than this are dropped as noise.
+pub const MIN_BLOCK_SECONDS: i64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupBy {
+    Project,
+    Category,
+}
+
+impl GroupBy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GroupBy::Project => "project",
+            GroupBy::Category => "category",
+        }
+    }
+}
+
+/// Merge non-idle activity records between two local dates (inclusive) into
+/// contiguous blocks per project or category.
+///
+/// Records without a project are skipped when grouping by project; records
+/// without a category are grouped as "Uncategorized".
+pub fn build_blocks(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+    group_by: GroupBy,
+) -> Result<Vec<TimelineBlock>, rusqlite::Error> {
+    let sql = match group_by {
+        GroupBy::Project => {
+            "SELECT ar.project_id, p.name, p.color, ar.start_time, ar.end_time, ar.notes
+             FROM activity_records ar
+             JOIN projects p ON ar.project_id = p.id
+             WHERE ar.is_idle = 0
+               AND date(ar.start_time, 'unixepoch', 'localtime') >= ?
+               AND date(ar.start_time, 'unixepoch', 'localtime') <= ?
+             ORDER BY ar.project_id, ar.start_time"
+        }
+        GroupBy::Category => {
+            "SELECT ar.category_id, COALESCE(c.name, 'Uncategorized'),
+                    COALESCE(c.color, '#9CA3AF'), ar.start_time, ar.end_time, ar.notes
+             FROM activity_records ar
+             LEFT JOIN categories c ON ar.category_id = c.id
+             WHERE ar.is_idle = 0
+               AND date(ar.start_time, 'unixepoch', 'localtime') >= ?
+               AND date(ar.start_time, 'unixepoch', 'localtime') <= ?
+             ORDER BY ar.category_id, ar.start_time"
+        }
+    };
+
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt
+        .query_map([start_date, end_date], |row| {
+            Ok(TimelineBlock {
+                group_id: row.get(0)?,
+                label: row.get(1)?,
+                color: row.get(2)?,
+                start_time: row.get(3)?,
+                end_time: row.get(4)?,
+                active_seconds: row.get::<_, i64>(4)? - row.get::<_, i64>(3)?,
+                record_count: 1,
+                notes: row.get::<_, Option<String>>(5)?.into_iter().collect(),
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut blocks = merge_records(rows);
+    blocks.retain(|b| b.end_time - b.start_time >= MIN_BLOCK_SECONDS);
+    blocks.sort_by_key(|b| (b.start_time, b.group_id));
+
+    Ok(blocks)
+}
+
+/// Merge records (already ordered by group, then start time) whose gap is
+/// within `MERGE_GAP_SECONDS`.
+fn merge_records(records: Vec<TimelineBlock>) -> Vec<TimelineBlock> {
+    let mut blocks: Vec<TimelineBlock> = Vec::new();
+
+    for record in records {
+        match blocks.last_mut() {
+            Some(last)
+                if last.group_id == record.group_id
+                    && record.start_time - last.end_time <= MERGE_GAP_SECONDS =>
+            {
+                last.end_time = last.end_time.max(record.end_time);
+                last.active_seconds += record.active_seconds;
+                last.record_count += 1;
+                for note in record.notes {
+                    if !last.notes.contains(&note) {
+                        last.notes.push(note);
+                    }
+                }
+            }
+            _ => blocks.push(record),
+        }
+    }
+
+    blocks
+}
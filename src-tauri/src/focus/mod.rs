@@ -0,0 +1,234 @@
+use crate::db::{FocusDistraction, FocusSessionReport};
+use rusqlite::{Connection, OptionalExtension, Row};
+
+const DISTRACTION_COLUMNS: &str = "d.id, d.session_id, d.application_id, d.app_name,
+     d.window_title, d.category_id, c.name, d.started_at, d.ended_at";
+
+fn map_distraction(row: &Row) -> rusqlite::Result<FocusDistraction> {
+    Ok(FocusDistraction {
+        id: row.get(0)?,
+        session_id: row.get(1)?,
+        application_id: row.get(2)?,
+        app_name: row.get(3)?,
+        window_title: row.get(4)?,
+        category_id: row.get(5)?,
+        category_name: row.get(6)?,
+        started_at: row.get(7)?,
+        ended_at: row.get(8)?,
+    })
+}
+
+fn get_distraction(conn: &Connection, id: i64) -> rusqlite::Result<FocusDistraction> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM focus_distractions d
+             LEFT JOIN categories c ON d.category_id = c.id
+             WHERE d.id = ?",
+            DISTRACTION_COLUMNS
+        ),
+        [id],
+        map_distraction,
+    )
+}
+
+pub fn start_session(conn: &Connection, now: i64) -> rusqlite::Result<i64> {
+    conn.execute("INSERT INTO focus_sessions (started_at) VALUES (?)", [now])?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn end_session(conn: &Connection, session_id: i64, now: i64) -> rusqlite::Result<()> {
+    end_distraction(conn, session_id, now)?;
+    conn.execute(
+        "UPDATE focus_sessions SET ended_at = ? WHERE id = ? AND ended_at IS NULL",
+        [now, session_id],
+    )?;
+    Ok(())
+}
+
+/// Close sessions left open by a previous run at the last moment anything
+/// was recorded in them.
+pub fn close_stale_sessions(conn: &Connection) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE focus_distractions SET ended_at = started_at WHERE ended_at IS NULL",
+        [],
+    )?;
+    conn.execute(
+        "UPDATE focus_sessions
+         SET ended_at = MAX(started_at, COALESCE(
+             (SELECT MAX(end_time) FROM activity_records
+              WHERE start_time >= focus_sessions.started_at), started_at))
+         WHERE ended_at IS NULL",
+        [],
+    )
+}
+
+/// Close the session's open distraction, if any.
+pub fn end_distraction(conn: &Connection, session_id: i64, now: i64) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE focus_distractions SET ended_at = ? WHERE session_id = ? AND ended_at IS NULL",
+        [now, session_id],
+    )?;
+    Ok(())
+}
+
+/// Check a newly active window against the focus session. Switching to an
+/// app in a non-productive category opens a distraction, which is returned;
+/// moving between distracting windows continues it, and anything else ends
+/// it. Uncategorized apps do not count as distractions.
+pub fn on_window_change(
+    conn: &Connection,
+    session_id: i64,
+    application_id: Option<i64>,
+    app_name: &str,
+    window_title: &str,
+    now: i64,
+) -> rusqlite::Result<Option<FocusDistraction>> {
+    let distracting_category: Option<i64> = match application_id {
+        Some(app_id) => conn
+            .query_row(
+                "SELECT c.id FROM applications a
+                 JOIN categories c ON a.category_id = c.id
+                 WHERE a.id = ? AND c.is_productive = 0",
+                [app_id],
+                |row| row.get(0),
+            )
+            .optional()?,
+        None => None,
+    };
+
+    let open: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM focus_distractions WHERE session_id = ? AND ended_at IS NULL",
+            [session_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    match (distracting_category, open) {
+        (Some(_), Some(_)) => Ok(None),
+        (Some(category_id), None) => {
+            conn.execute(
+                "INSERT INTO focus_distractions
+                    (session_id, application_id, app_name, window_title, category_id, started_at)
+                 VALUES (?, ?, ?, ?, ?, ?)",
+                rusqlite::params![
+                    session_id,
+                    application_id,
+                    app_name,
+                    window_title,
+                    category_id,
+                    now
+                ],
+            )?;
+            get_distraction(conn, conn.last_insert_rowid()).map(Some)
+        }
+        (None, Some(_)) => {
+            end_distraction(conn, session_id, now)?;
+            Ok(None)
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+/// Focus sessions that started between two dates, newest first, with
+/// distraction counts and time lost.
+pub fn report(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+    now: i64,
+) -> rusqlite::Result<Vec<FocusSessionReport>> {
+    let mut stmt = conn.prepare(
+        "SELECT id FROM focus_sessions
+         WHERE date(started_at, 'unixepoch', 'localtime') BETWEEN ? AND ?
+         ORDER BY started_at DESC",
+    )?;
+    let ids = stmt
+        .query_map([start_date, end_date], |row| row.get::<_, i64>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    ids.into_iter()
+        .map(|id| session_report(conn, id, now))
+        .collect()
+}
+
+pub fn session_report(
+    conn: &Connection,
+    session_id: i64,
+    now: i64,
+) -> rusqlite::Result<FocusSessionReport> {
+    let (started_at, ended_at): (i64, Option<i64>) = conn.query_row(
+        "SELECT started_at, ended_at FROM focus_sessions WHERE id = ?",
+        [session_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM focus_distractions d
+         LEFT JOIN categories c ON d.category_id = c.id
+         WHERE d.session_id = ?
+         ORDER BY d.started_at",
+        DISTRACTION_COLUMNS
+    ))?;
+    let distractions = stmt
+        .query_map([session_id], map_distraction)?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let end = ended_at.unwrap_or(now);
+    let duration = (end - started_at).max(0);
+    let distraction_seconds: i64 = distractions
+        .iter()
+        .map(|d| (d.ended_at.unwrap_or(end) - d.started_at).max(0))
+        .sum();
+
+    Ok(FocusSessionReport {
+        id: session_id,
+        started_at,
+        ended_at,
+        duration_seconds: duration,
+        distraction_count: distractions.len() as i64,
+        distraction_seconds,
+        focused_seconds: (duration - distraction_seconds).max(0),
+        distractions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    #[test]
+    fn test_distractions_are_logged_per_session() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+        conn.execute_batch(
+            "INSERT INTO categories (id, name, color, is_productive)
+                 VALUES (100, 'Games', '#000000', 0), (101, 'Deep Work', '#000000', 1);
+             INSERT INTO applications (id, name, category_id)
+                 VALUES (1, 'Editor', 101), (2, 'Game', 100), (3, 'Video', 100), (4, 'Misc', NULL);",
+        )
+        .unwrap();
+
+        let session = start_session(&conn, 1000).unwrap();
+        let change = |app: i64, name: &str, at: i64| {
+            on_window_change(&conn, session, Some(app), name, "window", at).unwrap()
+        };
+
+        assert!(change(1, "Editor", 1000).is_none());
+        let first = change(2, "Game", 1100).unwrap();
+        assert_eq!(first.app_name, "Game");
+        assert_eq!(first.category_name.as_deref(), Some("Games"));
+        // Hopping between distracting apps is one distraction
+        assert!(change(3, "Video", 1150).is_none());
+        assert!(change(4, "Misc", 1200).is_none());
+        assert!(change(3, "Video", 1500).is_some());
+        end_session(&conn, session, 1560).unwrap();
+
+        let report = session_report(&conn, session, 9999).unwrap();
+        assert_eq!(report.duration_seconds, 560);
+        assert_eq!(report.distraction_count, 2);
+        assert_eq!(report.distraction_seconds, 100 + 60);
+        assert_eq!(report.focused_seconds, 400);
+    }
+}
@@ -0,0 +1,255 @@
+use crate::db::{ActiveActivity, Goal, GoalKind, GoalPeriod, GoalProgress, GoalStatus, GoalTarget};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone};
+use rusqlite::{Connection, Row};
+
+pub const GOAL_COLUMNS: &str = "id, name, target_type, target_id, kind, period, target_seconds,
+     active_days, is_active, created_at";
+
+/// Matches category ids in the subtree rooted at the bound parameter.
+const CATEGORY_SUBTREE: &str = "(
+    WITH RECURSIVE subtree(id) AS (
+        SELECT ?
+        UNION ALL
+        SELECT c.id FROM categories c JOIN subtree s ON c.parent_id = s.id
+    )
+    SELECT id FROM subtree
+)";
+
+pub fn map_goal(row: &Row) -> rusqlite::Result<Goal> {
+    Ok(Goal {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        target_type: GoalTarget::parse(&row.get::<_, String>(2)?),
+        target_id: row.get(3)?,
+        kind: GoalKind::parse(&row.get::<_, String>(4)?),
+        period: GoalPeriod::parse(&row.get::<_, String>(5)?),
+        target_seconds: row.get(6)?,
+        active_days: row.get(7)?,
+        is_active: row.get::<_, i32>(8)? == 1,
+        created_at: row.get(9)?,
+    })
+}
+
+pub fn get_goal(conn: &Connection, id: i64) -> rusqlite::Result<Goal> {
+    conn.query_row(
+        &format!("SELECT {} FROM goals WHERE id = ?", GOAL_COLUMNS),
+        [id],
+        map_goal,
+    )
+}
+
+pub fn list(conn: &Connection) -> rusqlite::Result<Vec<Goal>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM goals ORDER BY name, id",
+        GOAL_COLUMNS
+    ))?;
+    let goals = stmt
+        .query_map([], map_goal)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(goals)
+}
+
+/// First and last day of the period containing `today`. Weeks run Monday
+/// to Sunday.
+pub fn period_bounds(period: GoalPeriod, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+    match period {
+        GoalPeriod::Day => (today, today),
+        GoalPeriod::Week => {
+            let start = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+            (start, start + Duration::days(6))
+        }
+    }
+}
+
+/// Whether a goal applies on `day`. Weekly goals always do; daily goals only
+/// on the weekdays in their `active_days` mask.
+pub fn applies_on(goal: &Goal, day: NaiveDate) -> bool {
+    match goal.period {
+        GoalPeriod::Week => true,
+        GoalPeriod::Day => goal.active_days & (1 << day.weekday().num_days_from_monday()) != 0,
+    }
+}
+
+/// Progress of every active goal that applies at `now`, counting saved
+/// records plus the activity the recorder is still timing.
+pub fn evaluate(
+    conn: &Connection,
+    now: DateTime<Local>,
+    in_progress: Option<ActiveActivity>,
+) -> rusqlite::Result<Vec<GoalProgress>> {
+    let today = now.date_naive();
+    let mut progress = Vec::new();
+
+    for goal in list(conn)? {
+        if !goal.is_active || !applies_on(&goal, today) {
+            continue;
+        }
+
+        let (start, end) = period_bounds(goal.period, today);
+        let mut actual = recorded_seconds(conn, &goal, start, end)?;
+        if let Some(activity) = in_progress {
+            if matches_activity(conn, &goal, activity)? {
+                let period_start = Local
+                    .from_local_datetime(&start.and_hms_opt(0, 0, 0).expect("midnight is valid"))
+                    .earliest()
+                    .map_or(activity.start_time, |dt| dt.timestamp());
+                actual += (now.timestamp() - activity.start_time.max(period_start)).max(0);
+            }
+        }
+
+        progress.push(GoalProgress {
+            period_start: start.to_string(),
+            period_end: end.to_string(),
+            actual_seconds: actual,
+            target_seconds: goal.target_seconds,
+            percent: actual as f64 * 100.0 / goal.target_seconds as f64,
+            status: status(goal.kind, actual, goal.target_seconds),
+            goal,
+        });
+    }
+
+    Ok(progress)
+}
+
+fn status(kind: GoalKind, actual: i64, target: i64) -> GoalStatus {
+    match kind {
+        GoalKind::Min if actual >= target => GoalStatus::Met,
+        GoalKind::Max if actual > target => GoalStatus::Exceeded,
+        _ => GoalStatus::InProgress,
+    }
+}
+
+fn recorded_seconds(
+    conn: &Connection,
+    goal: &Goal,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> rusqlite::Result<i64> {
+    let target = match goal.target_type {
+        GoalTarget::Category => format!("category_id IN {}", CATEGORY_SUBTREE),
+        GoalTarget::Application => "application_id = ?".to_string(),
+    };
+    conn.query_row(
+        &format!(
+            "SELECT COALESCE(SUM(duration_seconds), 0) FROM activity_records
+             WHERE is_idle = 0
+               AND date(start_time, 'unixepoch', 'localtime') BETWEEN ? AND ?
+               AND {}",
+            target
+        ),
+        rusqlite::params![start.to_string(), end.to_string(), goal.target_id],
+        |row| row.get(0),
+    )
+}
+
+fn matches_activity(
+    conn: &Connection,
+    goal: &Goal,
+    activity: ActiveActivity,
+) -> rusqlite::Result<bool> {
+    let Some(app_id) = activity.application_id else {
+        return Ok(false);
+    };
+    match goal.target_type {
+        GoalTarget::Application => Ok(app_id == goal.target_id),
+        GoalTarget::Category => conn.query_row(
+            &format!(
+                "SELECT EXISTS (SELECT 1 FROM applications
+                                WHERE id = ? AND category_id IN {})",
+                CATEGORY_SUBTREE
+            ),
+            [app_id, goal.target_id],
+            |row| row.get(0),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    fn local(s: &str) -> DateTime<Local> {
+        let naive = chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+        Local.from_local_datetime(&naive).earliest().unwrap()
+    }
+
+    #[test]
+    fn test_period_bounds() {
+        // 2024-03-07 is a Thursday
+        let day = NaiveDate::from_ymd_opt(2024, 3, 7).unwrap();
+        assert_eq!(period_bounds(GoalPeriod::Day, day), (day, day));
+        assert_eq!(
+            period_bounds(GoalPeriod::Week, day),
+            (
+                NaiveDate::from_ymd_opt(2024, 3, 4).unwrap(),
+                NaiveDate::from_ymd_opt(2024, 3, 10).unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn test_evaluate_includes_subcategories_and_in_progress() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+
+        conn.execute_batch(
+            "INSERT INTO categories (id, name, color) VALUES (100, 'Social', '#000000');
+             INSERT INTO categories (id, name, color, parent_id) VALUES (101, 'Chat', '#000000', 100);
+             INSERT INTO applications (id, name, category_id) VALUES (1, 'Chat App', 101);
+             INSERT INTO goals (name, target_type, target_id, kind, period, target_seconds)
+                 VALUES ('Less social', 'category', 100, 'max', 'day', 3600);
+             INSERT INTO goals (name, target_type, target_id, kind, period, target_seconds)
+                 VALUES ('Weekly chat', 'application', 1, 'min', 'week', 7200);
+             INSERT INTO goals (name, target_type, target_id, kind, period, target_seconds,
+                                active_days)
+                 VALUES ('Weekends only', 'application', 1, 'min', 'day', 60, 96);",
+        )
+        .unwrap();
+        // 50 minutes on Monday, 20 minutes on Thursday
+        for (start, minutes) in [("2024-03-04 09:00", 50), ("2024-03-07 09:00", 20)] {
+            conn.execute(
+                "INSERT INTO activity_records
+                     (application_id, window_title, start_time, end_time, category_id)
+                 VALUES (1, 'chat', ?1, ?1 + ?2, 101)",
+                rusqlite::params![local(start).timestamp(), minutes * 60],
+            )
+            .unwrap();
+        }
+
+        let now = local("2024-03-07 12:00");
+        let progress = evaluate(&conn, now, None).unwrap();
+        // The weekend-only daily goal does not apply on a Thursday
+        assert_eq!(progress.len(), 2);
+        let daily = progress
+            .iter()
+            .find(|p| p.goal.name == "Less social")
+            .unwrap();
+        assert_eq!(daily.actual_seconds, 20 * 60);
+        assert_eq!(daily.status, GoalStatus::InProgress);
+        let weekly = progress
+            .iter()
+            .find(|p| p.goal.name == "Weekly chat")
+            .unwrap();
+        assert_eq!(weekly.period_start, "2024-03-04");
+        assert_eq!(weekly.actual_seconds, 70 * 60);
+
+        // Another 55 minutes in the app, still being timed
+        let active = ActiveActivity {
+            application_id: Some(1),
+            start_time: now.timestamp() - 55 * 60,
+        };
+        let progress = evaluate(&conn, now, Some(active)).unwrap();
+        let daily = progress
+            .iter()
+            .find(|p| p.goal.name == "Less social")
+            .unwrap();
+        assert_eq!(daily.actual_seconds, 75 * 60);
+        assert_eq!(daily.status, GoalStatus::Exceeded);
+        let weekly = progress
+            .iter()
+            .find(|p| p.goal.name == "Weekly chat")
+            .unwrap();
+        assert_eq!(weekly.status, GoalStatus::Met);
+    }
+}
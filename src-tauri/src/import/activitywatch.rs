@@ -0,0 +1,326 @@
+use super::{in_savepoint, overlaps_activity, ImportError};
+use crate::db::ImportReport;
+use chrono::DateTime;
+use rusqlite::Connection;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+const SOURCE: &str = "activitywatch";
+
+#[derive(Debug, Deserialize)]
+struct Export {
+    buckets: HashMap<String, Bucket>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Bucket {
+    #[serde(rename = "type")]
+    bucket_type: String,
+    #[serde(default)]
+    events: Vec<Event>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Event {
+    timestamp: String,
+    duration: f64,
+    #[serde(default)]
+    data: serde_json::Value,
+}
+
+#[derive(Debug, Clone)]
+struct WindowSpan {
+    app: String,
+    title: String,
+    start: i64,
+    end: i64,
+}
+
+/// Window and AFK spans extracted from an ActivityWatch export.
+#[derive(Debug, Default)]
+pub struct ActivityWatchData {
+    windows: Vec<WindowSpan>,
+    afk: Vec<(i64, i64)>,
+    skipped: i64,
+}
+
+/// Parse an ActivityWatch export (`/api/0/export` or a single bucket export).
+/// Only `currentwindow` and `afkstatus` buckets are read.
+pub fn parse(json: &str) -> Result<ActivityWatchData, ImportError> {
+    let export: Export = serde_json::from_str(json)?;
+    let mut data = ActivityWatchData::default();
+    let mut found = false;
+
+    for bucket in export.buckets.into_values() {
+        match bucket.bucket_type.as_str() {
+            "currentwindow" => {
+                found = true;
+                for event in bucket.events {
+                    let span = event_span(&event).and_then(|(start, end)| {
+                        let app = event.data.get("app")?.as_str()?.to_string();
+                        let title = event
+                            .data
+                            .get("title")
+                            .and_then(|t| t.as_str())
+                            .unwrap_or_default()
+                            .to_string();
+                        Some(WindowSpan { app, title, start, end })
+                    });
+                    match span {
+                        Some(span) => data.windows.push(span),
+                        None => data.skipped += 1,
+                    }
+                }
+            }
+            "afkstatus" => {
+                found = true;
+                for event in bucket.events {
+                    let is_afk = event.data.get("status").and_then(|s| s.as_str()) == Some("afk");
+                    if let (true, Some(span)) = (is_afk, event_span(&event)) {
+                        data.afk.push(span);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !found {
+        return Err(ImportError::Invalid(
+            "export contains no currentwindow or afkstatus buckets".to_string(),
+        ));
+    }
+
+    data.windows.sort_by_key(|w| w.start);
+    data.afk.sort();
+    data.afk = merge_ranges(&data.afk);
+
+    Ok(data)
+}
+
+/// Write parsed data all or nothing. Ranges overlapping each other in the
+/// export, AFK time, or records already in the database are skipped.
+pub fn import(conn: &Connection, data: &ActivityWatchData) -> Result<ImportReport, ImportError> {
+    in_savepoint(conn, true, || write(conn, data))
+}
+
+fn write(tx: &Connection, data: &ActivityWatchData) -> Result<ImportReport, ImportError> {
+    let mut report = ImportReport {
+        records_skipped: data.skipped,
+        ..Default::default()
+    };
+    let mut app_ids: HashMap<&str, Option<i64>> = HashMap::new();
+    let mut covered_until = i64::MIN;
+
+    for window in &data.windows {
+        let start = window.start.max(covered_until);
+        if window.end - start < 1 {
+            report.records_skipped += 1;
+            continue;
+        }
+        covered_until = covered_until.max(window.end);
+
+        let pieces = subtract_ranges((start, window.end), &data.afk);
+        let mut inserted = false;
+
+        for (piece_start, piece_end) in pieces {
+            if piece_end - piece_start < 1 || overlaps_activity(tx, piece_start, piece_end)? {
+                continue;
+            }
+
+            let app_id = match app_ids.get(window.app.as_str()) {
+                Some(id) => *id,
+                None => {
+                    let (id, created) = get_or_create_application(tx, &window.app)?;
+                    if created {
+                        report.applications_created += 1;
+                    }
+                    app_ids.insert(window.app.as_str(), id);
+                    id
+                }
+            };
+
+            tx.execute(
+                "INSERT INTO activity_records
+                    (application_id, window_title, start_time, end_time, category_id, is_idle, source)
+                 VALUES (?1, ?2, ?3, ?4,
+                         (SELECT category_id FROM applications WHERE id = ?1), 0, ?5)",
+                rusqlite::params![app_id, window.title, piece_start, piece_end, SOURCE],
+            )?;
+            inserted = true;
+        }
+
+        if inserted {
+            report.records_imported += 1;
+        } else {
+            report.records_skipped += 1;
+        }
+    }
+
+    for &(start, end) in &data.afk {
+        let exists: bool = tx.query_row(
+            "SELECT EXISTS(SELECT 1 FROM idle_periods WHERE start_time < ? AND end_time > ?)",
+            [end, start],
+            |row| row.get(0),
+        )?;
+        if exists {
+            report.idle_periods_skipped += 1;
+            continue;
+        }
+
+        tx.execute(
+            "INSERT INTO idle_periods (start_time, end_time, duration_seconds) VALUES (?, ?, ?)",
+            [start, end, end - start],
+        )?;
+        report.idle_periods_imported += 1;
+    }
+
+    Ok(report)
+}
+
+fn event_span(event: &Event) -> Option<(i64, i64)> {
+    let start = DateTime::parse_from_rfc3339(&event.timestamp).ok()?.timestamp();
+    let duration = event.duration.round() as i64;
+    (duration >= 1).then_some((start, start + duration))
+}
+
+fn get_or_create_application(
+    conn: &Connection,
+    name: &str,
+) -> Result<(Option<i64>, bool), rusqlite::Error> {
+    let existing: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM applications WHERE identifier = ?1 OR name = ?1",
+            [name],
+            |row| row.get(0),
+        )
+        .ok();
+
+    if existing.is_some() {
+        return Ok((existing, false));
+    }
+
+    conn.execute(
+        "INSERT INTO applications (name, identifier) VALUES (?1, ?1)",
+        [name],
+    )?;
+    Ok((Some(conn.last_insert_rowid()), true))
+}
+
+/// Merge overlapping ranges in a sorted list.
+fn merge_ranges(ranges: &[(i64, i64)]) -> Vec<(i64, i64)> {
+    let mut merged: Vec<(i64, i64)> = Vec::new();
+    for &(start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Remove the sorted, non-overlapping `holes` from `range`.
+fn subtract_ranges(range: (i64, i64), holes: &[(i64, i64)]) -> Vec<(i64, i64)> {
+    let (mut start, end) = range;
+    let mut pieces = Vec::new();
+
+    for &(hole_start, hole_end) in holes {
+        if hole_end <= start {
+            continue;
+        }
+        if hole_start >= end {
+            break;
+        }
+        if hole_start > start {
+            pieces.push((start, hole_start));
+        }
+        start = start.max(hole_end);
+        if start >= end {
+            return pieces;
+        }
+    }
+
+    pieces.push((start, end));
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    const EXPORT: &str = r#"{
+        "buckets": {
+            "aw-watcher-window_host": {
+                "id": "aw-watcher-window_host",
+                "type": "currentwindow",
+                "events": [
+                    {"timestamp": "2024-01-15T09:00:00+00:00", "duration": 600.0,
+                     "data": {"app": "Code", "title": "main.rs"}},
+                    {"timestamp": "2024-01-15T09:05:00+00:00", "duration": 600.0,
+                     "data": {"app": "Firefox", "title": "Docs"}},
+                    {"timestamp": "2024-01-15T09:20:00+00:00", "duration": 600.0,
+                     "data": {"app": "Code", "title": "lib.rs"}},
+                    {"timestamp": "2024-01-15T10:00:00+00:00", "duration": 0.2,
+                     "data": {"app": "Code", "title": "blip"}}
+                ]
+            },
+            "aw-watcher-afk_host": {
+                "id": "aw-watcher-afk_host",
+                "type": "afkstatus",
+                "events": [
+                    {"timestamp": "2024-01-15T09:25:00+00:00", "duration": 300.0,
+                     "data": {"status": "afk"}},
+                    {"timestamp": "2024-01-15T09:00:00+00:00", "duration": 1500.0,
+                     "data": {"status": "not-afk"}}
+                ]
+            }
+        }
+    }"#;
+
+    #[test]
+    fn test_import_trims_overlaps_and_afk() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+
+        let data = parse(EXPORT).expect("Failed to parse export");
+        let report = import(&conn, &data).expect("Failed to import");
+
+        assert_eq!(report.records_imported, 3);
+        assert_eq!(report.records_skipped, 1);
+        assert_eq!(report.idle_periods_imported, 1);
+        assert_eq!(report.applications_created, 2);
+
+        // 09:00-09:10 Code, 09:10-09:15 Firefox (trimmed), 09:20-09:25 Code (AFK removed)
+        let total: i64 = conn
+            .query_row(
+                "SELECT SUM(duration_seconds) FROM activity_records WHERE source = 'activitywatch'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("Failed to sum records");
+        assert_eq!(total, 600 + 300 + 300);
+    }
+
+    #[test]
+    fn test_reimport_skips_existing_ranges() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+
+        let data = parse(EXPORT).expect("Failed to parse export");
+        import(&conn, &data).expect("Failed to import");
+        let report = import(&conn, &data).expect("Failed to re-import");
+
+        assert_eq!(report.records_imported, 0);
+        assert_eq!(report.records_skipped, 4);
+        assert_eq!(report.idle_periods_skipped, 1);
+        assert_eq!(report.applications_created, 0);
+    }
+
+    #[test]
+    fn test_subtract_ranges() {
+        assert_eq!(subtract_ranges((0, 10), &[(2, 4), (6, 8)]), vec![(0, 2), (4, 6), (8, 10)]);
+        assert_eq!(subtract_ranges((3, 7), &[(0, 10)]), vec![]);
+        assert_eq!(subtract_ranges((0, 5), &[(5, 10)]), vec![(0, 5)]);
+    }
+}
@@ -0,0 +1,50 @@
+pub mod activitywatch;
+pub mod time_entries;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ImportError {
+    #[error("failed to read import file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid CSV: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("database error: {0}")]
+    Database(#[from] rusqlite::Error),
+    #[error("{0}")]
+    Invalid(String),
+}
+
+/// Whether any non-idle activity record overlaps the half-open range `[start, end)`.
+pub(crate) fn overlaps_activity(
+    conn: &rusqlite::Connection,
+    start: i64,
+    end: i64,
+) -> Result<bool, rusqlite::Error> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM activity_records
+                       WHERE is_idle = 0 AND start_time < ? AND end_time > ?)",
+        [end, start],
+        |row| row.get(0),
+    )
+}
+
+/// Run `write` inside a savepoint, keeping its changes only if it succeeds
+/// and `keep` is set. Savepoints nest, so this also works inside a caller's
+/// transaction.
+pub(crate) fn in_savepoint<T>(
+    conn: &rusqlite::Connection,
+    keep: bool,
+    write: impl FnOnce() -> Result<T, ImportError>,
+) -> Result<T, ImportError> {
+    conn.execute_batch("SAVEPOINT import")?;
+    let result = write();
+    if keep && result.is_ok() {
+        conn.execute_batch("RELEASE import")?;
+    } else {
+        conn.execute_batch("ROLLBACK TO import; RELEASE import")?;
+    }
+    result
+}
@@ -0,0 +1,424 @@
+use super::{in_savepoint, ImportError};
+use crate::db::{CsvColumnMapping, CsvConflict, CsvImportPreview, CsvImportReport};
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use rusqlite::Connection;
+use std::collections::HashMap;
+
+const SAMPLE_ROWS: usize = 5;
+const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%m/%d/%Y", "%d.%m.%Y", "%d/%m/%Y"];
+const TIME_FORMATS: &[&str] = &["%H:%M:%S", "%H:%M", "%I:%M:%S %p", "%I:%M %p"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvFormat {
+    Toggl,
+    Clockify,
+    Generic,
+}
+
+impl CsvFormat {
+    /// Also used as the `source` of imported activity records.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CsvFormat::Toggl => "toggl",
+            CsvFormat::Clockify => "clockify",
+            CsvFormat::Generic => "csv",
+        }
+    }
+}
+
+/// Detect the export format and propose a column mapping, with a few sample rows.
+pub fn preview(text: &str) -> Result<CsvImportPreview, ImportError> {
+    let (headers, rows) = read_rows(text)?;
+
+    Ok(CsvImportPreview {
+        format: detect_format(&headers).as_str().to_string(),
+        mapping: detect_mapping(&headers),
+        sample_rows: rows.into_iter().take(SAMPLE_ROWS).collect(),
+        headers,
+    })
+}
+
+/// Import time entries as manual activity records, creating projects by name.
+///
+/// Entries overlapping already tracked time are reported as conflicts and
+/// skipped. With `dry_run` nothing is written, but the report is the same.
+pub fn import(
+    conn: &Connection,
+    text: &str,
+    mapping: Option<CsvColumnMapping>,
+    dry_run: bool,
+) -> Result<CsvImportReport, ImportError> {
+    let (headers, rows) = read_rows(text)?;
+    let format = detect_format(&headers);
+    let mapping = mapping.unwrap_or_else(|| detect_mapping(&headers));
+
+    let (start_date, start_time, end_time) =
+        match (mapping.start_date, mapping.start_time, mapping.end_time) {
+            (Some(sd), Some(st), Some(et)) => (sd, st, et),
+            _ => {
+                return Err(ImportError::Invalid(
+                    "mapping needs start date, start time and end time columns".to_string(),
+                ))
+            }
+        };
+
+    let report = CsvImportReport {
+        format: format.as_str().to_string(),
+        dry_run,
+        ..Default::default()
+    };
+
+    in_savepoint(conn, !dry_run, || {
+        write(
+            conn,
+            &rows,
+            &mapping,
+            (start_date, start_time, end_time),
+            report,
+        )
+    })
+}
+
+/// Insert the rows, filling in `report`.
+fn write(
+    tx: &Connection,
+    rows: &[Vec<String>],
+    mapping: &CsvColumnMapping,
+    (start_date, start_time, end_time): (usize, usize, usize),
+    mut report: CsvImportReport,
+) -> Result<CsvImportReport, ImportError> {
+    let mut projects: HashMap<String, (i64, Option<i64>)> = HashMap::new();
+
+    for (index, row) in rows.iter().enumerate() {
+        // Line number in the file, counting the header
+        let line = index + 2;
+        let cell = |col: Option<usize>| {
+            col.and_then(|c| row.get(c))
+                .map(|v| v.trim())
+                .filter(|v| !v.is_empty())
+        };
+
+        let start = parse_datetime(cell(Some(start_date)), cell(Some(start_time)));
+        let end_day = cell(mapping.end_date).or(cell(Some(start_date)));
+        let end = parse_datetime(end_day, cell(Some(end_time)));
+
+        let (start, mut end) = match (start, end) {
+            (Some(s), Some(e)) => (s, e),
+            _ => {
+                report.errors.push(format!("Row {}: invalid start or end time", line));
+                report.entries_skipped += 1;
+                continue;
+            }
+        };
+        // Entries crossing midnight without an end date column
+        if end <= start && mapping.end_date.is_none() {
+            end += 24 * 60 * 60;
+        }
+        if end <= start {
+            report.errors.push(format!("Row {}: end time is before start time", line));
+            report.entries_skipped += 1;
+            continue;
+        }
+
+        let project = cell(mapping.project);
+        let description = cell(mapping.description).or(project).unwrap_or("Imported entry");
+        let title = description.to_string();
+
+        let conflicting_seconds: i64 = tx.query_row(
+            "SELECT COALESCE(SUM(MIN(end_time, ?1) - MAX(start_time, ?2)), 0)
+             FROM activity_records
+             WHERE is_idle = 0 AND start_time < ?1 AND end_time > ?2",
+            [end, start],
+            |row| row.get(0),
+        )?;
+        if conflicting_seconds > 0 {
+            report.conflicts.push(CsvConflict {
+                row: line,
+                description: description.to_string(),
+                start_time: start,
+                end_time: end,
+                conflicting_seconds,
+            });
+            report.entries_skipped += 1;
+            continue;
+        }
+
+        let (project_id, category_id) = match project {
+            Some(name) => {
+                let key = name.to_lowercase();
+                let found = match projects.get(&key) {
+                    Some(found) => *found,
+                    None => {
+                        let client_id = match cell(mapping.client) {
+                            Some(client) => {
+                                let (id, created) = get_or_create_client(tx, client)?;
+                                if created {
+                                    report.clients_created += 1;
+                                }
+                                Some(id)
+                            }
+                            None => None,
+                        };
+                        let (found, created) = get_or_create_project(tx, name, client_id)?;
+                        if created {
+                            report.projects_created += 1;
+                        }
+                        projects.insert(key, found);
+                        found
+                    }
+                };
+                (Some(found.0), found.1)
+            }
+            None => (None, None),
+        };
+
+        tx.execute(
+            "INSERT INTO activity_records
+                (window_title, start_time, end_time, category_id, project_id, is_idle, source,
+                 is_billable)
+             VALUES (?, ?, ?, ?, ?, 0, ?, ?)",
+            rusqlite::params![
+                title,
+                start,
+                end,
+                category_id,
+                project_id,
+                report.format.as_str(),
+                cell(mapping.billable).and_then(parse_bool).map(i32::from)
+            ],
+        )?;
+        let record_id = tx.last_insert_rowid();
+
+        for tag in cell(mapping.tags)
+            .unwrap_or("")
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+        {
+            tx.execute("INSERT OR IGNORE INTO tags (name) VALUES (?)", [tag])?;
+            tx.execute(
+                "INSERT OR IGNORE INTO activity_tags (activity_id, tag_id)
+                 SELECT ?, id FROM tags WHERE name = ?",
+                rusqlite::params![record_id, tag],
+            )?;
+        }
+        report.entries_imported += 1;
+    }
+
+    Ok(report)
+}
+
+fn read_rows(text: &str) -> Result<(Vec<String>, Vec<Vec<String>>), ImportError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(text.as_bytes());
+
+    let headers = reader
+        .headers()?
+        .iter()
+        .map(|h| h.trim_start_matches('\u{feff}').trim().to_string())
+        .collect();
+
+    let rows = reader
+        .records()
+        .map(|r| r.map(|record| record.iter().map(str::to_string).collect()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok((headers, rows))
+}
+
+fn detect_format(headers: &[String]) -> CsvFormat {
+    let has = |name: &str| headers.iter().any(|h| h.eq_ignore_ascii_case(name));
+
+    if has("Duration (h)") || has("Duration (decimal)") {
+        CsvFormat::Clockify
+    } else if has("Duration") && has("Start date") {
+        CsvFormat::Toggl
+    } else {
+        CsvFormat::Generic
+    }
+}
+
+fn detect_mapping(headers: &[String]) -> CsvColumnMapping {
+    // Names are tried in order of preference
+    let find = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|n| headers.iter().position(|h| h.eq_ignore_ascii_case(n)))
+    };
+
+    CsvColumnMapping {
+        project: find(&["Project"]),
+        client: find(&["Client"]),
+        description: find(&["Description", "Task"]),
+        tags: find(&["Tags", "Tag"]),
+        billable: find(&["Billable"]),
+        start_date: find(&["Start date", "Start"]),
+        start_time: find(&["Start time"]),
+        end_date: find(&["End date", "Stop date", "End"]),
+        end_time: find(&["End time", "Stop time"]),
+    }
+}
+
+/// Parse a local date and time into a unix timestamp.
+fn parse_datetime(date: Option<&str>, time: Option<&str>) -> Option<i64> {
+    let date = date?;
+    let time = time?;
+
+    let date = DATE_FORMATS
+        .iter()
+        .find_map(|f| NaiveDate::parse_from_str(date, f).ok())?;
+    let time = TIME_FORMATS
+        .iter()
+        .find_map(|f| NaiveTime::parse_from_str(time, f).ok())?;
+
+    Local
+        .from_local_datetime(&NaiveDateTime::new(date, time))
+        .earliest()
+        .map(|dt| dt.timestamp())
+}
+
+fn get_or_create_project(
+    conn: &Connection,
+    name: &str,
+    client_id: Option<i64>,
+) -> Result<((i64, Option<i64>), bool), rusqlite::Error> {
+    let existing = conn
+        .query_row(
+            "SELECT id, category_id FROM projects WHERE name = ? COLLATE NOCASE",
+            [name],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .ok();
+
+    if let Some(found) = existing {
+        return Ok((found, false));
+    }
+
+    conn.execute(
+        "INSERT INTO projects (name, client_id) VALUES (?, ?)",
+        rusqlite::params![name, client_id],
+    )?;
+    Ok(((conn.last_insert_rowid(), None), true))
+}
+
+fn get_or_create_client(conn: &Connection, name: &str) -> Result<(i64, bool), rusqlite::Error> {
+    let existing = conn
+        .query_row(
+            "SELECT id FROM clients WHERE name = ? COLLATE NOCASE",
+            [name],
+            |row| row.get(0),
+        )
+        .ok();
+
+    if let Some(id) = existing {
+        return Ok((id, false));
+    }
+
+    conn.execute("INSERT INTO clients (name) VALUES (?)", [name])?;
+    Ok((conn.last_insert_rowid(), true))
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "yes" | "true" | "1" | "y" => Some(true),
+        "no" | "false" | "0" | "n" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    const TOGGL: &str = "\u{feff}User,Email,Client,Project,Task,Description,Billable,Start date,Start time,End date,End time,Duration,Tags
+Ann,ann@example.com,Acme,Website,,Standup,Yes,2024-01-15,09:00:00,2024-01-15,09:15:00,00:15:00,meeting
+Ann,ann@example.com,Acme,Website,,Design review,Yes,2024-01-15,10:00:00,2024-01-15,11:00:00,01:00:00,\"meeting, design\"
+Ann,ann@example.com,,,,Broken,No,not-a-date,10:00:00,2024-01-15,11:00:00,01:00:00,
+";
+
+    const CLOCKIFY: &str = "Project,Client,Description,Task,User,Group,Email,Tags,Billable,Start Date,Start Time,End Date,End Time,Duration (h),Duration (decimal)
+Website,Acme,Call,,Ann,,ann@example.com,,Yes,01/15/2024,02:00:00 PM,01/15/2024,02:30:00 PM,00:30:00,0.50
+";
+
+    #[test]
+    fn test_preview_detects_formats() {
+        let toggl = preview(TOGGL).expect("Failed to preview Toggl CSV");
+        assert_eq!(toggl.format, "toggl");
+        assert_eq!(toggl.headers[0], "User");
+        assert_eq!(toggl.mapping.project, Some(3));
+        assert_eq!(toggl.mapping.start_time, Some(8));
+        assert_eq!(toggl.sample_rows.len(), 3);
+
+        let clockify = preview(CLOCKIFY).expect("Failed to preview Clockify CSV");
+        assert_eq!(clockify.format, "clockify");
+        assert_eq!(clockify.mapping.end_time, Some(12));
+    }
+
+    #[test]
+    fn test_dry_run_writes_nothing() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+
+        let report = import(&conn, TOGGL, None, true).expect("Failed to dry run");
+        assert_eq!(report.entries_imported, 2);
+        assert_eq!(report.entries_skipped, 1);
+        assert_eq!(report.projects_created, 1);
+        assert_eq!(report.clients_created, 1);
+        assert_eq!(report.errors.len(), 1);
+
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM activity_records", [], |row| {
+                row.get(0)
+            })
+            .expect("Failed to count records");
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_import_reports_conflicts() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+
+        import(&conn, TOGGL, None, false).expect("Failed to import");
+        let clockify = import(&conn, CLOCKIFY, None, false).expect("Failed to import");
+        assert_eq!(clockify.entries_imported, 1);
+        assert_eq!(clockify.projects_created, 0);
+
+        let (client, billable): (String, i64) = conn
+            .query_row(
+                "SELECT c.name, SUM(ar.is_billable) FROM activity_records ar
+                 JOIN projects p ON ar.project_id = p.id
+                 JOIN clients c ON p.client_id = c.id",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .expect("Failed to read client");
+        assert_eq!(client, "Acme");
+        assert_eq!(billable, 3);
+
+        let again = import(&conn, TOGGL, None, true).expect("Failed to dry run");
+        assert_eq!(again.entries_imported, 0);
+        assert_eq!(again.conflicts.len(), 2);
+        assert_eq!(again.conflicts[1].conflicting_seconds, 3600);
+
+        let (title, tags): (String, String) = conn
+            .query_row(
+                "SELECT ar.window_title, GROUP_CONCAT(t.name, ',')
+                 FROM activity_records ar
+                 JOIN activity_tags at ON at.activity_id = ar.id
+                 JOIN tags t ON t.id = at.tag_id
+                 WHERE ar.source = 'toggl'
+                 GROUP BY ar.id
+                 ORDER BY ar.start_time DESC",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .expect("Failed to read record");
+        assert_eq!(title, "Design review");
+        let mut tags: Vec<&str> = tags.split(',').collect();
+        tags.sort();
+        assert_eq!(tags, vec!["design", "meeting"]);
+    }
+}
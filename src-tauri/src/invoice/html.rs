@@ -0,0 +1,93 @@
+use super::{fill_placeholders, format_hours, format_money};
+use crate::db::{Invoice, InvoiceTemplate};
+
+/// Render an invoice as a self-contained HTML document with inline styles.
+pub fn render_html(invoice: &Invoice, template: &InvoiceTemplate) -> String {
+    let accent = escape(&template.accent_color);
+    let header = fill_placeholders(&template.header_text, invoice, template);
+    let footer = fill_placeholders(&template.footer_text, invoice, template);
+
+    let mut rows = String::new();
+    for line in &invoice.lines {
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+            escape(&line.description),
+            format_hours(line.billed_seconds),
+            format_money(line.hourly_rate_cents, &invoice.currency),
+            format_money(line.amount_cents, &invoice.currency),
+        ));
+    }
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Invoice {number}</title>
+<style>
+body {{ font-family: Helvetica, Arial, sans-serif; color: #111827; margin: 40px; }}
+h1 {{ color: {accent}; margin: 0 0 4px; }}
+.company {{ font-size: 20px; font-weight: bold; color: {accent}; }}
+.muted {{ color: #6B7280; }}
+.meta {{ display: flex; justify-content: space-between; margin: 32px 0; }}
+table {{ width: 100%; border-collapse: collapse; }}
+th {{ text-align: left; border-bottom: 2px solid {accent}; padding: 8px 4px; }}
+td {{ border-bottom: 1px solid #E5E7EB; padding: 8px 4px; }}
+.num {{ text-align: right; white-space: nowrap; }}
+.total td {{ font-weight: bold; border-bottom: none; }}
+footer {{ margin-top: 48px; font-size: 12px; }}
+</style>
+</head>
+<body>
+<header>
+<div class="company">{company}</div>
+<div class="muted">{header}</div>
+</header>
+<div class="meta">
+<div>
+<h1>Invoice {number}</h1>
+<div>Issued {issue_date} &middot; Due {due_date}</div>
+<div class="muted">Period {start_date} to {end_date}</div>
+</div>
+<div>
+<strong>Bill to</strong><br>
+{client}<br>
+{address}
+</div>
+</div>
+<table>
+<thead><tr><th>Description</th><th class="num">Hours</th><th class="num">Rate</th><th class="num">Amount</th></tr></thead>
+<tbody>
+{rows}<tr class="total"><td colspan="3" class="num">Total</td><td class="num">{total}</td></tr>
+</tbody>
+</table>
+<footer class="muted">{footer}</footer>
+</body>
+</html>
+"#,
+        number = escape(&invoice.number),
+        accent = accent,
+        company = escape(&template.company_name),
+        header = multiline(&header),
+        issue_date = escape(&invoice.issue_date),
+        due_date = escape(&invoice.due_date),
+        start_date = escape(&invoice.start_date),
+        end_date = escape(&invoice.end_date),
+        client = escape(&invoice.client_name),
+        address = multiline(invoice.client_address.as_deref().unwrap_or("")),
+        rows = rows,
+        total = format_money(invoice.total_cents, &invoice.currency),
+        footer = multiline(&footer),
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn multiline(text: &str) -> String {
+    escape(text).replace('\n', "<br>\n")
+}
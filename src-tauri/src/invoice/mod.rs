@@ -0,0 +1,286 @@
+pub mod html;
+pub mod pdf;
+
+use crate::billing;
+use crate::db::{Invoice, InvoiceLine, InvoiceTemplate};
+use chrono::{Datelike, Duration, NaiveDate};
+use rusqlite::{Connection, OptionalExtension};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum InvoiceError {
+    #[error("database error: {0}")]
+    Database(#[from] rusqlite::Error),
+    #[error("failed to write invoice: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("no billable time for the selected projects and period")]
+    Empty,
+    #[error("{0} not found")]
+    NotFound(String),
+}
+
+pub const TEMPLATE_COLUMNS: &str = "id, name, company_name, header_text, footer_text,
+                                    accent_color, payment_terms_days";
+
+pub fn map_template(row: &rusqlite::Row) -> rusqlite::Result<InvoiceTemplate> {
+    Ok(InvoiceTemplate {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        company_name: row.get(2)?,
+        header_text: row.get(3)?,
+        footer_text: row.get(4)?,
+        accent_color: row.get(5)?,
+        payment_terms_days: row.get(6)?,
+    })
+}
+
+/// Load a template, or the oldest one when `id` is `None`.
+pub fn get_template(conn: &Connection, id: Option<i64>) -> Result<InvoiceTemplate, InvoiceError> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM invoice_templates WHERE ?1 IS NULL OR id = ?1 ORDER BY id LIMIT 1",
+            TEMPLATE_COLUMNS
+        ),
+        [id],
+        map_template,
+    )
+    .optional()?
+    .ok_or_else(|| match id {
+        Some(id) => InvoiceError::NotFound(format!("Invoice template {}", id)),
+        None => InvoiceError::NotFound("Invoice template".to_string()),
+    })
+}
+
+/// Issue an invoice for a client's billable time between two local dates.
+///
+/// `project_ids` limits the invoice to those projects; empty means all of the
+/// client's projects. Numbers are sequential and never reused.
+pub fn create(
+    conn: &Connection,
+    client_id: i64,
+    project_ids: &[i64],
+    start_date: &str,
+    end_date: &str,
+    template_id: Option<i64>,
+    issue_date: NaiveDate,
+) -> Result<Invoice, InvoiceError> {
+    let mut summary = match billing::summarize(conn, client_id, start_date, end_date) {
+        Err(rusqlite::Error::QueryReturnedNoRows) => {
+            return Err(InvoiceError::NotFound(format!("Client {}", client_id)))
+        }
+        result => result?,
+    };
+    if !project_ids.is_empty() {
+        summary
+            .line_items
+            .retain(|item| project_ids.contains(&item.project_id));
+    }
+    summary.line_items.retain(|item| item.billed_seconds > 0);
+    if summary.line_items.is_empty() {
+        return Err(InvoiceError::Empty);
+    }
+
+    let template = get_template(conn, template_id)?;
+    let sequence: i64 = conn.query_row(
+        "SELECT COALESCE(MAX(sequence), 0) + 1 FROM invoices",
+        [],
+        |row| row.get(0),
+    )?;
+    let number = format!("INV-{}-{:04}", issue_date.year(), sequence);
+    let due_date = issue_date + Duration::days(template.payment_terms_days);
+    let total_cents: i64 = summary.line_items.iter().map(|l| l.amount_cents).sum();
+
+    conn.execute(
+        "INSERT INTO invoices
+            (sequence, number, client_id, client_name, client_address, template_id,
+             start_date, end_date, currency, issue_date, due_date, total_cents)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        rusqlite::params![
+            sequence,
+            number,
+            client_id,
+            summary.client.name,
+            summary.client.address,
+            template.id,
+            start_date,
+            end_date,
+            summary.currency,
+            issue_date.to_string(),
+            due_date.to_string(),
+            total_cents,
+        ],
+    )?;
+    let invoice_id = conn.last_insert_rowid();
+
+    for item in &summary.line_items {
+        conn.execute(
+            "INSERT INTO invoice_lines
+                (invoice_id, project_id, description, billed_seconds, hourly_rate_cents, amount_cents)
+             VALUES (?, ?, ?, ?, ?, ?)",
+            rusqlite::params![
+                invoice_id,
+                item.project_id,
+                item.project_name,
+                item.billed_seconds,
+                item.hourly_rate_cents,
+                item.amount_cents,
+            ],
+        )?;
+    }
+
+    load(conn, invoice_id)
+}
+
+pub fn load(conn: &Connection, id: i64) -> Result<Invoice, InvoiceError> {
+    list(conn, Some(id))?
+        .pop()
+        .ok_or_else(|| InvoiceError::NotFound(format!("Invoice {}", id)))
+}
+
+/// All invoices, newest first, or just the one with `id`.
+pub fn list(conn: &Connection, id: Option<i64>) -> Result<Vec<Invoice>, InvoiceError> {
+    let mut stmt = conn.prepare(
+        "SELECT id, number, client_id, client_name, client_address, template_id,
+                start_date, end_date, currency, issue_date, due_date, total_cents, created_at
+         FROM invoices
+         WHERE ?1 IS NULL OR id = ?1
+         ORDER BY sequence DESC",
+    )?;
+    let mut invoices = stmt
+        .query_map([id], |row| {
+            Ok(Invoice {
+                id: row.get(0)?,
+                number: row.get(1)?,
+                client_id: row.get(2)?,
+                client_name: row.get(3)?,
+                client_address: row.get(4)?,
+                template_id: row.get(5)?,
+                start_date: row.get(6)?,
+                end_date: row.get(7)?,
+                currency: row.get(8)?,
+                issue_date: row.get(9)?,
+                due_date: row.get(10)?,
+                total_cents: row.get(11)?,
+                lines: Vec::new(),
+                created_at: row.get(12)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut lines = conn.prepare(
+        "SELECT id, project_id, description, billed_seconds, hourly_rate_cents, amount_cents
+         FROM invoice_lines WHERE invoice_id = ? ORDER BY id",
+    )?;
+    for invoice in &mut invoices {
+        invoice.lines = lines
+            .query_map([invoice.id], |row| {
+                Ok(InvoiceLine {
+                    id: row.get(0)?,
+                    project_id: row.get(1)?,
+                    description: row.get(2)?,
+                    billed_seconds: row.get(3)?,
+                    hourly_rate_cents: row.get(4)?,
+                    amount_cents: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+    }
+
+    Ok(invoices)
+}
+
+/// Replace `{{placeholders}}` in template text with invoice details.
+pub fn fill_placeholders(text: &str, invoice: &Invoice, template: &InvoiceTemplate) -> String {
+    [
+        ("{{company}}", template.company_name.as_str()),
+        ("{{client}}", invoice.client_name.as_str()),
+        ("{{number}}", invoice.number.as_str()),
+        ("{{issue_date}}", invoice.issue_date.as_str()),
+        ("{{due_date}}", invoice.due_date.as_str()),
+        ("{{start_date}}", invoice.start_date.as_str()),
+        ("{{end_date}}", invoice.end_date.as_str()),
+        ("{{currency}}", invoice.currency.as_str()),
+    ]
+    .iter()
+    .fold(text.to_string(), |text, (placeholder, value)| {
+        text.replace(placeholder, value)
+    })
+}
+
+/// Format cents as e.g. `1,234.50 EUR`.
+pub fn format_money(cents: i64, currency: &str) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    let cents = cents.abs();
+    let units = (cents / 100).to_string();
+
+    let mut grouped = String::new();
+    for (i, digit) in units.chars().enumerate() {
+        if i > 0 && (units.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+
+    format!("{}{}.{:02} {}", sign, grouped, cents % 100, currency)
+}
+
+/// Format billed time as decimal hours, e.g. `1.25`.
+pub fn format_hours(seconds: i64) -> String {
+    format!("{:.2}", seconds as f64 / 3600.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    fn seed(conn: &Connection) {
+        conn.execute_batch(
+            "INSERT INTO clients (id, name, address, currency, hourly_rate_cents)
+                 VALUES (1, 'Acme & Sons', '1 Main St', 'EUR', 12000);
+             INSERT INTO projects (id, name, client_id) VALUES (1, 'Website', 1), (2, 'Support', 1);
+             INSERT INTO activity_records (window_title, start_time, end_time, project_id) VALUES
+                 ('a', strftime('%s', '2024-03-04 09:00:00', 'utc'), strftime('%s', '2024-03-04 10:30:00', 'utc'), 1),
+                 ('b', strftime('%s', '2024-03-04 11:00:00', 'utc'), strftime('%s', '2024-03-04 11:15:00', 'utc'), 2);",
+        )
+        .expect("Failed to seed");
+    }
+
+    #[test]
+    fn test_create_numbers_invoices_sequentially() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+        seed(&conn);
+        let issued = NaiveDate::from_ymd_opt(2024, 3, 31).unwrap();
+
+        let first = create(&conn, 1, &[1], "2024-03-01", "2024-03-31", None, issued)
+            .expect("Failed to create invoice");
+        assert_eq!(first.number, "INV-2024-0001");
+        assert_eq!(first.due_date, "2024-04-30");
+        assert_eq!(first.lines.len(), 1);
+        assert_eq!(first.total_cents, 18000);
+
+        let template = get_template(&conn, first.template_id).expect("Failed to load template");
+        let html = html::render_html(&first, &template);
+        assert!(html.contains("Acme &amp; Sons"));
+        assert!(html.contains("180.00 EUR"));
+        assert!(html.contains("Payment due by 2024-04-30."));
+
+        let second = create(&conn, 1, &[], "2024-03-01", "2024-03-31", None, issued)
+            .expect("Failed to create invoice");
+        assert_eq!(second.number, "INV-2024-0002");
+        assert_eq!(second.total_cents, 21000);
+        assert_eq!(list(&conn, None).unwrap()[0].id, second.id);
+
+        let empty = create(&conn, 1, &[1], "2024-04-01", "2024-04-30", None, issued);
+        assert!(matches!(empty, Err(InvoiceError::Empty)));
+    }
+
+    #[test]
+    fn test_formatting() {
+        assert_eq!(format_money(123456789, "USD"), "1,234,567.89 USD");
+        assert_eq!(format_money(5, "EUR"), "0.05 EUR");
+        assert_eq!(format_money(-100000, "EUR"), "-1,000.00 EUR");
+        assert_eq!(format_hours(5400), "1.50");
+    }
+}
@@ -0,0 +1,372 @@
+use super::{fill_placeholders, format_hours, format_money};
+use crate::db::{Invoice, InvoiceTemplate};
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+const LINE_HEIGHT: f32 = 16.0;
+
+const REGULAR: Name = Name(b"F1");
+const BOLD: Name = Name(b"F2");
+
+// Right edges of the numeric table columns
+const HOURS_RIGHT: f32 = 350.0;
+const RATE_RIGHT: f32 = 450.0;
+const AMOUNT_RIGHT: f32 = PAGE_WIDTH - MARGIN;
+const DESCRIPTION_WIDTH: f32 = 250.0;
+
+const BLACK: (f32, f32, f32) = (0.07, 0.09, 0.15);
+const GRAY: (f32, f32, f32) = (0.42, 0.45, 0.5);
+
+/// Render an invoice as a PDF using the standard Helvetica fonts, so no font
+/// data has to be embedded. Text outside Windows-1252 is replaced with `?`.
+pub fn render_pdf(invoice: &Invoice, template: &InvoiceTemplate) -> Vec<u8> {
+    let accent = parse_color(&template.accent_color);
+    let mut layout = Layout::new();
+
+    if !template.company_name.is_empty() {
+        layout.text(MARGIN, BOLD, 18.0, &template.company_name, accent);
+        layout.advance(22.0);
+    }
+    let header = fill_placeholders(&template.header_text, invoice, template);
+    for line in header.lines() {
+        layout.text(MARGIN, REGULAR, 9.0, line, GRAY);
+        layout.advance(12.0);
+    }
+
+    layout.advance(24.0);
+    layout.text(
+        MARGIN,
+        BOLD,
+        22.0,
+        &format!("Invoice {}", invoice.number),
+        accent,
+    );
+    layout.advance(LINE_HEIGHT + 8.0);
+    for line in [
+        format!("Issued {}", invoice.issue_date),
+        format!("Due {}", invoice.due_date),
+        format!("Period {} to {}", invoice.start_date, invoice.end_date),
+    ] {
+        layout.text(MARGIN, REGULAR, 10.0, &line, BLACK);
+        layout.advance(LINE_HEIGHT);
+    }
+
+    layout.advance(LINE_HEIGHT);
+    layout.text(MARGIN, BOLD, 10.0, "Bill to", BLACK);
+    layout.advance(LINE_HEIGHT);
+    layout.text(MARGIN, REGULAR, 10.0, &invoice.client_name, BLACK);
+    layout.advance(LINE_HEIGHT);
+    for line in invoice.client_address.as_deref().unwrap_or("").lines() {
+        layout.text(MARGIN, REGULAR, 10.0, line, BLACK);
+        layout.advance(LINE_HEIGHT);
+    }
+
+    layout.advance(LINE_HEIGHT);
+    table_header(&mut layout, accent);
+    for line in &invoice.lines {
+        if layout.ensure(LINE_HEIGHT * 2.0) {
+            table_header(&mut layout, accent);
+        }
+        let description = truncate(&line.description, 10.0, DESCRIPTION_WIDTH);
+        layout.text(MARGIN, REGULAR, 10.0, &description, BLACK);
+        let hours = format_hours(line.billed_seconds);
+        layout.text_right(HOURS_RIGHT, REGULAR, 10.0, &hours, BLACK);
+        let rate = format_money(line.hourly_rate_cents, &invoice.currency);
+        layout.text_right(RATE_RIGHT, REGULAR, 10.0, &rate, BLACK);
+        let amount = format_money(line.amount_cents, &invoice.currency);
+        layout.text_right(AMOUNT_RIGHT, REGULAR, 10.0, &amount, BLACK);
+        layout.advance(LINE_HEIGHT);
+    }
+
+    layout.ensure(LINE_HEIGHT * 2.0);
+    layout.rule(RATE_RIGHT - 80.0, AMOUNT_RIGHT, 0.5, GRAY);
+    layout.advance(LINE_HEIGHT);
+    layout.text_right(RATE_RIGHT, BOLD, 11.0, "Total", BLACK);
+    let total = format_money(invoice.total_cents, &invoice.currency);
+    layout.text_right(AMOUNT_RIGHT, BOLD, 11.0, &total, BLACK);
+    layout.advance(LINE_HEIGHT * 3.0);
+
+    let footer = fill_placeholders(&template.footer_text, invoice, template);
+    for line in footer.lines() {
+        layout.ensure(12.0);
+        layout.text(MARGIN, REGULAR, 9.0, line, GRAY);
+        layout.advance(12.0);
+    }
+
+    layout.finish(&format!("Invoice {}", invoice.number))
+}
+
+fn table_header(layout: &mut Layout, accent: (f32, f32, f32)) {
+    layout.text(MARGIN, BOLD, 10.0, "Description", BLACK);
+    layout.text_right(HOURS_RIGHT, BOLD, 10.0, "Hours", BLACK);
+    layout.text_right(RATE_RIGHT, BOLD, 10.0, "Rate", BLACK);
+    layout.text_right(AMOUNT_RIGHT, BOLD, 10.0, "Amount", BLACK);
+    layout.advance(6.0);
+    layout.rule(MARGIN, AMOUNT_RIGHT, 1.5, accent);
+    layout.advance(LINE_HEIGHT);
+}
+
+/// Content streams of all pages plus the current baseline.
+struct Layout {
+    pages: Vec<Content>,
+    y: f32,
+}
+
+impl Layout {
+    fn new() -> Self {
+        Self {
+            pages: vec![Content::new()],
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    fn page(&mut self) -> &mut Content {
+        self.pages.last_mut().expect("layout always has a page")
+    }
+
+    fn advance(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    /// Start a new page unless `height` still fits. Returns whether it did.
+    fn ensure(&mut self, height: f32) -> bool {
+        if self.y - height >= MARGIN {
+            return false;
+        }
+        self.pages.push(Content::new());
+        self.y = PAGE_HEIGHT - MARGIN;
+        true
+    }
+
+    fn text(&mut self, x: f32, font: Name, size: f32, text: &str, color: (f32, f32, f32)) {
+        let y = self.y;
+        let encoded = encode(text);
+        self.page()
+            .set_fill_rgb(color.0, color.1, color.2)
+            .begin_text()
+            .set_font(font, size)
+            .next_line(x, y)
+            .show(Str(&encoded))
+            .end_text();
+    }
+
+    fn text_right(
+        &mut self,
+        right: f32,
+        font: Name,
+        size: f32,
+        text: &str,
+        color: (f32, f32, f32),
+    ) {
+        self.text(right - text_width(text, size), font, size, text, color);
+    }
+
+    fn rule(&mut self, from: f32, to: f32, width: f32, color: (f32, f32, f32)) {
+        let y = self.y;
+        self.page()
+            .set_stroke_rgb(color.0, color.1, color.2)
+            .set_line_width(width)
+            .move_to(from, y)
+            .line_to(to, y)
+            .stroke();
+    }
+
+    fn finish(self, title: &str) -> Vec<u8> {
+        let catalog_id = Ref::new(1);
+        let tree_id = Ref::new(2);
+        let regular_id = Ref::new(3);
+        let bold_id = Ref::new(4);
+        let info_id = Ref::new(5);
+        let first_page = 6;
+
+        let page_ids: Vec<Ref> = (0..self.pages.len())
+            .map(|i| Ref::new(first_page + 2 * i as i32))
+            .collect();
+
+        let mut pdf = Pdf::new();
+        pdf.catalog(catalog_id).pages(tree_id);
+        pdf.pages(tree_id)
+            .kids(page_ids.iter().copied())
+            .count(page_ids.len() as i32);
+        pdf.document_info(info_id)
+            .title(TextStr(title))
+            .producer(TextStr("Time Tracker"));
+
+        for (page_id, content) in page_ids.iter().zip(self.pages) {
+            let content_id = Ref::new(page_id.get() + 1);
+            let mut page = pdf.page(*page_id);
+            page.parent(tree_id)
+                .media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT))
+                .contents(content_id);
+            let mut resources = page.resources();
+            resources
+                .fonts()
+                .pair(REGULAR, regular_id)
+                .pair(BOLD, bold_id);
+            resources.finish();
+            page.finish();
+            pdf.stream(content_id, &content.finish());
+        }
+
+        for (id, font) in [(regular_id, "Helvetica"), (bold_id, "Helvetica-Bold")] {
+            pdf.type1_font(id)
+                .base_font(Name(font.as_bytes()))
+                .encoding_predefined(Name(b"WinAnsiEncoding"));
+        }
+
+        pdf.finish()
+    }
+}
+
+/// Encode text as Windows-1252 for the standard fonts' WinAnsiEncoding.
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' | '\u{A0}'..='\u{FF}' => c as u8,
+            '€' => 0x80,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            _ => b'?',
+        })
+        .collect()
+}
+
+/// Approximate width of text in Helvetica at `size` points.
+fn text_width(text: &str, size: f32) -> f32 {
+    let units: u32 = encode(text).iter().map(|&b| glyph_width(b)).sum();
+    units as f32 * size / 1000.0
+}
+
+/// Helvetica advance widths in 1/1000 em; other glyphs use the digit width.
+fn glyph_width(byte: u8) -> u32 {
+    match byte {
+        b' ' | b'!' | b',' | b'.' | b'/' | b':' | b';' | b'I' | b'[' | b'\\' | b']' | b'f'
+        | b't' => 278,
+        b'i' | b'j' | b'l' => 222,
+        b'\'' => 191,
+        b'"' => 355,
+        b'(' | b')' | b'-' | b'`' | b'r' => 333,
+        b'*' => 389,
+        b'+' | b'<' | b'=' | b'>' | b'~' => 584,
+        b'%' => 889,
+        b'&' | b'A' | b'B' | b'E' | b'K' | b'P' | b'S' | b'V' | b'X' | b'Y' => 667,
+        b'C' | b'D' | b'H' | b'N' | b'R' | b'U' | b'w' => 722,
+        b'F' | b'T' | b'Z' => 611,
+        b'G' | b'O' | b'Q' => 778,
+        b'J' | b'c' | b'k' | b's' | b'v' | b'x' | b'y' | b'z' => 500,
+        b'L' => 556,
+        b'M' | b'm' => 833,
+        b'W' => 944,
+        b'@' => 1015,
+        b'^' => 469,
+        b'{' | b'}' => 334,
+        b'|' => 260,
+        _ => 556,
+    }
+}
+
+/// Shorten text with an ellipsis so it fits in `max_width` points.
+fn truncate(text: &str, size: f32, max_width: f32) -> String {
+    if text_width(text, size) <= max_width {
+        return text.to_string();
+    }
+    let mut truncated: String = text.to_string();
+    while !truncated.is_empty() && text_width(&format!("{}…", truncated), size) > max_width {
+        truncated.pop();
+    }
+    format!("{}…", truncated.trim_end())
+}
+
+/// Parse `#RRGGBB` into RGB components, falling back to the default accent.
+fn parse_color(hex: &str) -> (f32, f32, f32) {
+    let channel = |i: usize| {
+        hex.get(i..i + 2)
+            .and_then(|c| u8::from_str_radix(c, 16).ok())
+            .map(|c| c as f32 / 255.0)
+    };
+    match (hex.len(), channel(1), channel(3), channel(5)) {
+        (7, Some(r), Some(g), Some(b)) if hex.starts_with('#') => (r, g, b),
+        _ => (0.23, 0.51, 0.96),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::InvoiceLine;
+
+    fn invoice(lines: usize) -> Invoice {
+        Invoice {
+            id: 1,
+            number: "INV-2024-0007".to_string(),
+            client_id: Some(1),
+            client_name: "Müller GmbH".to_string(),
+            client_address: Some("Hauptstraße 1\n10115 Berlin".to_string()),
+            template_id: Some(1),
+            start_date: "2024-03-01".to_string(),
+            end_date: "2024-03-31".to_string(),
+            currency: "EUR".to_string(),
+            issue_date: "2024-04-01".to_string(),
+            due_date: "2024-05-01".to_string(),
+            total_cents: 100_000 * lines as i64,
+            lines: (0..lines)
+                .map(|i| InvoiceLine {
+                    id: i as i64,
+                    project_id: None,
+                    description: format!("Project {}", i),
+                    billed_seconds: 36_000,
+                    hourly_rate_cents: 10_000,
+                    amount_cents: 100_000,
+                })
+                .collect(),
+            created_at: 0,
+        }
+    }
+
+    fn template() -> InvoiceTemplate {
+        InvoiceTemplate {
+            id: 1,
+            name: "Default".to_string(),
+            company_name: "Studio".to_string(),
+            header_text: "studio@example.com".to_string(),
+            footer_text: "Due {{due_date}}".to_string(),
+            accent_color: "#10B981".to_string(),
+            payment_terms_days: 30,
+        }
+    }
+
+    #[test]
+    fn test_pdf_is_well_formed_and_paginates() {
+        let pdf = render_pdf(&invoice(3), &template());
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(pdf.starts_with(b"%PDF-"));
+        assert!(text.trim_end().ends_with("%%EOF"));
+        assert!(text.contains("/Count 1"));
+        assert!(text.contains("(Invoice INV-2024-0007)"));
+        // Latin-1 text is encoded as single bytes ("Müller")
+        assert!(text.to_uppercase().contains("<4DFC6C6C6572"));
+        assert!(text.contains("(Due 2024-05-01)"));
+
+        let long = render_pdf(&invoice(80), &template());
+        assert!(String::from_utf8_lossy(&long).contains("/Count 3"));
+    }
+
+    #[test]
+    fn test_truncate_and_color() {
+        let long = "A very long project description that will not fit the column";
+        let short = truncate(long, 10.0, 100.0);
+        assert!(short.ends_with('…'));
+        assert!(text_width(&short, 10.0) <= 100.0);
+        assert_eq!(truncate("Short", 10.0, 100.0), "Short");
+        assert_eq!(parse_color("#FF0000"), (1.0, 0.0, 0.0));
+        assert_eq!(parse_color("red"), (0.23, 0.51, 0.96));
+    }
+}
@@ -1,13 +1,30 @@
+mod billing;
 mod commands;
 mod db;
+mod export;
+mod focus;
+mod goals;
+mod import;
+mod invoice;
+mod notifications;
+mod presets;
+mod schedule;
+mod search;
+mod sessions;
+mod settings;
 mod state;
+mod timer;
+mod timesheet;
 mod tracking;
 mod tray;
+mod workhours;
 
-use db::Database;
+use db::{Database, SessionReason};
+use notifications::{NotificationPolicy, NotificationSettings};
 use state::AppState;
+use std::collections::HashSet;
 use std::time::Duration;
-use tauri::{Emitter, Manager};
+use tauri::{Emitter, Manager, RunEvent};
 use tracking::{ActivityRecorder, WindowDetector};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -15,6 +32,7 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .setup(|app| {
             // Initialize logging
@@ -35,6 +53,14 @@ pub fn run() {
             // Initialize database
             let db = Database::new(app_data_dir).expect("Failed to initialize database");
 
+            // Focus sessions cannot survive a restart
+            if let Err(e) = focus::close_stale_sessions(&db.conn()) {
+                log::error!("Failed to close stale focus sessions: {}", e);
+            }
+            if let Err(e) = sessions::close_stale_sessions(&db.conn()) {
+                log::error!("Failed to close stale tracking sessions: {}", e);
+            }
+
             // Create app state
             let state = AppState::new(db.clone());
             app.manage(state);
@@ -57,47 +83,156 @@ pub fn run() {
             commands::start_tracking,
             commands::stop_tracking,
             commands::get_tracking_status,
+            commands::get_sessions,
             commands::get_current_window,
             commands::get_idle_time,
+            commands::set_current_project,
+            // Schedule
+            commands::get_schedule,
+            commands::set_schedule,
+            commands::get_schedule_status,
+            commands::track_anyway_until,
+            commands::clear_schedule_override,
             // Categories
             commands::get_categories,
             commands::create_category,
             commands::update_category,
             commands::delete_category,
             commands::assign_app_to_category,
+            // Preset packs
+            commands::list_builtin_packs,
+            commands::export_preset_pack,
+            commands::import_preset_pack,
+            commands::import_builtin_pack,
+            // Projects and clients
+            commands::get_projects,
+            commands::create_project,
+            commands::set_project_billing,
+            commands::get_clients,
+            commands::create_client,
+            commands::update_client,
+            commands::delete_client,
+            commands::get_billing_summary,
+            // Invoices
+            commands::get_invoice_templates,
+            commands::create_invoice_template,
+            commands::update_invoice_template,
+            commands::delete_invoice_template,
+            commands::create_invoice,
+            commands::get_invoices,
+            commands::export_invoice,
+            // Timesheets
+            commands::get_timesheet,
+            commands::submit_timesheet,
+            commands::unlock_timesheet,
+            // Work hours
+            commands::get_work_balance,
+            commands::get_work_hours_settings,
+            commands::update_work_hours_settings,
+            commands::get_days_off,
+            commands::add_days_off,
+            commands::remove_day_off,
+            // Goals
+            commands::get_goals,
+            commands::create_goal,
+            commands::update_goal,
+            commands::delete_goal,
+            commands::get_goal_progress,
+            // Focus timer and sessions
+            commands::get_timer_status,
+            commands::start_timer,
+            commands::pause_timer,
+            commands::resume_timer,
+            commands::skip_timer_phase,
+            commands::stop_timer,
+            commands::get_timer_sessions,
+            commands::get_timer_settings,
+            commands::update_timer_settings,
+            commands::start_focus_session,
+            commands::stop_focus_session,
+            commands::get_focus_session,
+            commands::get_focus_report,
+            // Tags
+            commands::get_tags,
+            commands::create_tag,
+            commands::update_tag,
+            commands::delete_tag,
+            commands::get_activity_tags,
+            commands::tag_activity_records,
+            commands::untag_activity_records,
+            commands::tag_activity_range,
+            commands::tag_project,
+            commands::untag_project,
+            commands::get_tag_rules,
+            commands::create_tag_rule,
+            commands::delete_tag_rule,
+            commands::apply_tag_rules,
+            // Activity records
+            commands::create_manual_entry,
+            commands::update_activity_record,
+            commands::split_activity_record,
+            commands::merge_activity_records,
+            commands::delete_activity_record,
+            commands::set_activity_note,
+            commands::add_note_to_range,
+            commands::search_notes,
+            commands::set_records_billable,
+            commands::resolve_idle_period,
             // Reports
             commands::get_daily_summary,
             commands::get_activity_range,
             commands::get_app_usage,
             commands::get_category_breakdown,
             commands::get_today_summary,
+            // Export
+            commands::export_ics,
+            // Import
+            commands::import_activitywatch,
+            commands::preview_csv_import,
+            commands::import_csv_time_entries,
+            // Search
+            commands::search_activity,
+            // Audit
+            commands::get_audit_log,
+            commands::undo_last_change,
             // Settings
             commands::get_settings,
             commands::update_settings,
+            commands::get_setting,
+            commands::set_setting,
             commands::get_idle_threshold,
             commands::set_idle_threshold,
+            commands::get_notification_settings,
+            commands::update_notification_settings,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let RunEvent::Exit = event {
+                if let Some(state) = app.try_state::<AppState>() {
+                    state.end_session(SessionReason::Quit);
+                }
+            }
+        });
 }
 
+/// A pass of the tracking loop this late means the machine was suspended.
+const SUSPEND_GAP_SECONDS: i64 = 60;
+
 fn run_tracking_loop(app_handle: tauri::AppHandle, db: Database) {
     let detector = WindowDetector::new();
     let mut recorder = ActivityRecorder::new(db.clone());
     let mut last_idle_check = std::time::Instant::now();
-
-    // Get idle threshold from settings
-    let idle_threshold: u64 = {
-        let conn = db.conn();
-        conn.query_row(
-            "SELECT value FROM settings WHERE key = 'idle_threshold_seconds'",
-            [],
-            |row| row.get::<_, String>(0),
-        )
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(300)
-    };
+    let mut last_goal_check = std::time::Instant::now();
+    let mut last_tray_refresh = std::time::Instant::now();
+    // (goal id, period start) pairs already reported as exceeded
+    let mut exceeded_goals: HashSet<(i64, String)> = HashSet::new();
+    let mut policy = NotificationPolicy::new();
+    let mut notification_settings = NotificationSettings::load(&db.conn());
+    // When the current idle stretch began, i.e. when recording last stopped
+    let mut idle_since: Option<i64> = None;
+    // Wall-clock time of the previous pass, to notice the machine sleeping
+    let mut last_tick = chrono::Utc::now().timestamp();
 
     loop {
         std::thread::sleep(Duration::from_secs(1));
@@ -108,8 +243,37 @@ fn run_tracking_loop(app_handle: tauri::AppHandle, db: Database) {
             None => continue,
         };
 
+        // A long gap between passes means the machine was asleep: end the
+        // session when it went to sleep and start another now
+        let now = chrono::Utc::now().timestamp();
+        if now - last_tick > SUSPEND_GAP_SECONDS && state.get_session_id().is_some() {
+            log::info!("Resumed after {} seconds asleep", now - last_tick);
+            recorder.flush_at(last_tick);
+            state.set_active_activity(None);
+            state.end_session_at(SessionReason::Suspend, last_tick);
+            state.begin_session(SessionReason::Suspend);
+        }
+        last_tick = now;
+
+        // The focus timer runs whether or not tracking is on
+        tick_timer(&app_handle, &state);
+        if schedule::apply(&app_handle, &state) {
+            tray::refresh(&app_handle);
+        }
+
+        if last_tray_refresh.elapsed() >= Duration::from_secs(5) {
+            last_tray_refresh = std::time::Instant::now();
+            tray::refresh(&app_handle);
+        }
+
         // Check if tracking is enabled
         if !state.is_tracking() {
+            // Close the open record so paused time is not counted
+            if state.get_active_activity().is_some() {
+                recorder.flush_current();
+                state.set_active_activity(None);
+                end_focus_distraction(&db, &state);
+            }
             continue;
         }
 
@@ -117,21 +281,41 @@ fn run_tracking_loop(app_handle: tauri::AppHandle, db: Database) {
         if last_idle_check.elapsed() >= Duration::from_secs(5) {
             last_idle_check = std::time::Instant::now();
             let idle_seconds = detector.get_idle_time_seconds();
-            let is_idle = idle_seconds >= idle_threshold;
+            let is_idle = idle_seconds >= state.idle_threshold();
 
             if is_idle != state.is_idle() {
                 state.set_idle(is_idle);
+                let now = chrono::Utc::now().timestamp();
                 if is_idle {
                     log::info!("User went idle after {} seconds", idle_seconds);
-                    recorder.flush_current();
+                    // The stretch began at the last input, not when it was noticed
+                    let since = now - idle_seconds as i64;
+                    recorder.flush_at(since);
+                    state.set_active_activity(None);
+                    state.end_session_at(SessionReason::Idle, since);
+                    end_focus_distraction(&db, &state);
+                    policy.on_idle();
+                    idle_since = Some(since);
                 } else {
                     log::info!("User returned from idle");
+                    state.begin_session(SessionReason::Idle);
+                    if let Some(since) = idle_since.take() {
+                        handle_idle_return(
+                            &app_handle,
+                            &recorder,
+                            &mut policy,
+                            &notification_settings,
+                            since,
+                            now,
+                        );
+                    }
                 }
 
                 // Emit event to frontend
                 if let Some(window) = app_handle.get_webview_window("main") {
                     let _ = window.emit("idle:changed", is_idle);
                 }
+                tray::refresh(&app_handle);
             }
         }
 
@@ -143,7 +327,159 @@ fn run_tracking_loop(app_handle: tauri::AppHandle, db: Database) {
         // Get active window
         if let Some(window) = detector.get_active_window() {
             state.set_current_window(Some(window.clone()));
-            recorder.record_window_change(&window);
+            recorder.set_project(state.get_current_project());
+            recorder.set_session(state.get_session_id());
+            if state.take_tag_rules_changed() {
+                recorder.reload_tag_rules();
+            }
+            let changed = recorder.record_window_change(&window);
+            state.set_active_activity(recorder.in_progress());
+            if changed {
+                check_focus(&app_handle, &db, &state, &window);
+            }
+
+            let now = chrono::Local::now();
+            if let Some(n) = policy.on_active(&notification_settings, now.timestamp(), now.time()) {
+                notifications::show(&app_handle, &n);
+            }
+        }
+
+        if last_goal_check.elapsed() >= Duration::from_secs(60) {
+            last_goal_check = std::time::Instant::now();
+            emit_goal_progress(&app_handle, &db, &state, &mut exceeded_goals);
+
+            notification_settings = NotificationSettings::load(&db.conn());
+            let now = chrono::Local::now();
+            let today_seconds = state.today_seconds_with_live();
+            if let Some(n) = policy.check_daily_limit(
+                &notification_settings,
+                now.date_naive(),
+                today_seconds,
+                now.time(),
+            ) {
+                notifications::show(&app_handle, &n);
+            }
+        }
+    }
+}
+
+/// Log a distraction if the new window pulls away from a focus session.
+fn check_focus(
+    app_handle: &tauri::AppHandle,
+    db: &Database,
+    state: &AppState,
+    window: &db::WindowEvent,
+) {
+    let Some(session_id) = state.get_focus_session() else {
+        return;
+    };
+    let application_id = state.get_active_activity().and_then(|a| a.application_id);
+    let result = focus::on_window_change(
+        &db.conn(),
+        session_id,
+        application_id,
+        &window.app_name,
+        &window.window_title,
+        chrono::Utc::now().timestamp(),
+    );
+    match result {
+        Ok(Some(distraction)) => commands::emit_distraction(app_handle, &distraction),
+        Ok(None) => {}
+        Err(e) => log::error!("Failed to check focus: {}", e),
+    }
+}
+
+/// Time away or paused is not time lost to a distraction.
+fn end_focus_distraction(db: &Database, state: &AppState) {
+    if let Some(session_id) = state.get_focus_session() {
+        let now = chrono::Utc::now().timestamp();
+        if let Err(e) = focus::end_distraction(&db.conn(), session_id, now) {
+            log::error!("Failed to end focus distraction: {}", e);
+        }
+    }
+}
+
+/// Advance the focus timer, emitting `timer:tick` while it runs.
+fn tick_timer(app_handle: &tauri::AppHandle, state: &AppState) {
+    let now = chrono::Utc::now().timestamp();
+    let tracking_changed = {
+        let mut timer = state.timer.lock();
+        if timer.state() != db::TimerState::Running {
+            return;
+        }
+
+        let tracking_changed = match timer.tick(now) {
+            Some(transition) => {
+                timer::apply_transition(app_handle, state, &mut timer, &transition, now)
+            }
+            None => false,
+        };
+        if let Some(window) = app_handle.get_webview_window("main") {
+            let _ = window.emit("timer:tick", timer.status(now));
+        }
+        tracking_changed
+    };
+
+    if tracking_changed {
+        tray::refresh(app_handle);
+    }
+}
+
+/// Store an idle stretch that just ended and, if it was long enough, ask
+/// whether to keep it. The webview gets `idle:returned` to show the choice.
+fn handle_idle_return(
+    app_handle: &tauri::AppHandle,
+    recorder: &ActivityRecorder,
+    policy: &mut NotificationPolicy,
+    settings: &NotificationSettings,
+    since: i64,
+    now: i64,
+) {
+    let Some(period) = recorder.save_idle_period(since, now) else {
+        return;
+    };
+    if now - since < settings.idle_prompt_minutes * 60 {
+        return;
+    }
+
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.emit("idle:returned", &period);
+    }
+    if let Some(n) = policy.on_idle_return(settings, now - since, chrono::Local::now().time()) {
+        notifications::show(app_handle, &n);
+    }
+}
+
+/// Emit `goal:progress` with every applicable goal, and `goal:exceeded` the
+/// first time a limit is passed in each period.
+fn emit_goal_progress(
+    app_handle: &tauri::AppHandle,
+    db: &Database,
+    state: &AppState,
+    exceeded_goals: &mut HashSet<(i64, String)>,
+) {
+    let progress = {
+        let conn = db.conn();
+        goals::evaluate(&conn, chrono::Local::now(), state.live_activity())
+    };
+    let progress = match progress {
+        Ok(p) => p,
+        Err(e) => {
+            log::error!("Failed to evaluate goals: {}", e);
+            return;
+        }
+    };
+
+    let Some(window) = app_handle.get_webview_window("main") else {
+        return;
+    };
+    for p in &progress {
+        if p.status == db::GoalStatus::Exceeded
+            && exceeded_goals.insert((p.goal.id, p.period_start.clone()))
+        {
+            log::info!("Goal '{}' exceeded", p.goal.name);
+            let _ = window.emit("goal:exceeded", p);
         }
     }
+    let _ = window.emit("goal:progress", &progress);
 }
@@ -0,0 +1,314 @@
+use chrono::{NaiveDate, NaiveTime};
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_notification::NotificationExt;
+
+pub use crate::settings::NotificationSettings;
+
+/// A gap longer than this between two active ticks (tracking paused, the
+/// machine asleep) ends a stretch of continuous activity.
+const ACTIVITY_GAP_SECONDS: i64 = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationKind {
+    BreakReminder,
+    DailyLimit,
+    IdleReturn,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: String,
+}
+
+impl NotificationSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, minutes) in [
+            ("Break reminder", self.break_reminder_minutes),
+            ("Daily limit", self.daily_limit_minutes),
+            ("Idle prompt", self.idle_prompt_minutes),
+        ] {
+            if minutes <= 0 {
+                return Err(format!("{} minutes must be positive", name));
+            }
+        }
+        match (
+            parse_time(&self.quiet_hours_start),
+            parse_time(&self.quiet_hours_end),
+        ) {
+            (Ok(None), Ok(None)) | (Ok(Some(_)), Ok(Some(_))) => Ok(()),
+            (Err(value), _) | (_, Err(value)) => Err(format!(
+                "Invalid quiet hours time '{}', expected HH:MM",
+                value
+            )),
+            _ => Err("Quiet hours need both a start and an end time".to_string()),
+        }
+    }
+
+    fn is_enabled(&self, kind: NotificationKind) -> bool {
+        match kind {
+            NotificationKind::BreakReminder => self.break_reminder_enabled,
+            NotificationKind::DailyLimit => self.daily_limit_enabled,
+            NotificationKind::IdleReturn => self.idle_prompt_enabled,
+        }
+    }
+
+    /// Whether `time` falls in quiet hours. Ranges may wrap past midnight.
+    pub fn is_quiet(&self, time: NaiveTime) -> bool {
+        let (Ok(Some(start)), Ok(Some(end))) = (
+            parse_time(&self.quiet_hours_start),
+            parse_time(&self.quiet_hours_end),
+        ) else {
+            return false;
+        };
+        if start <= end {
+            start <= time && time < end
+        } else {
+            time >= start || time < end
+        }
+    }
+}
+
+fn parse_time(value: &str) -> Result<Option<NaiveTime>, &str> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+    NaiveTime::parse_from_str(value, "%H:%M")
+        .map(Some)
+        .map_err(|_| value)
+}
+
+fn format_minutes(seconds: i64) -> String {
+    let minutes = seconds / 60;
+    if minutes < 60 {
+        format!("{} min", minutes)
+    } else {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    }
+}
+
+/// Decides when to notify. The tracking loop feeds it activity and idle
+/// transitions; it returns the notifications that are due and allowed.
+#[derive(Debug, Default)]
+pub struct NotificationPolicy {
+    active_since: Option<i64>,
+    last_active: Option<i64>,
+    limit_warned_on: Option<NaiveDate>,
+}
+
+impl NotificationPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call on every tick the user is active. Returns a break reminder once
+    /// per `break_reminder_minutes` of continuous activity.
+    pub fn on_active(
+        &mut self,
+        settings: &NotificationSettings,
+        now: i64,
+        local_time: NaiveTime,
+    ) -> Option<Notification> {
+        let continuing = match self.last_active {
+            Some(last) => now - last <= ACTIVITY_GAP_SECONDS,
+            None => false,
+        };
+        if !continuing {
+            self.active_since = Some(now);
+        }
+        self.last_active = Some(now);
+
+        let since = self.active_since?;
+        if now - since < settings.break_reminder_minutes * 60 {
+            return None;
+        }
+        // Restart the count whether or not the reminder is shown
+        self.active_since = Some(now);
+        self.allow(
+            settings,
+            local_time,
+            Notification {
+                kind: NotificationKind::BreakReminder,
+                title: "Time for a break".to_string(),
+                body: format!(
+                    "You have been active for {} without a break.",
+                    format_minutes(now - since)
+                ),
+            },
+        )
+    }
+
+    /// Going idle counts as a break.
+    pub fn on_idle(&mut self) {
+        self.active_since = None;
+        self.last_active = None;
+    }
+
+    /// Prompt about an idle stretch that has just ended, if it was long
+    /// enough to ask about.
+    pub fn on_idle_return(
+        &mut self,
+        settings: &NotificationSettings,
+        idle_seconds: i64,
+        local_time: NaiveTime,
+    ) -> Option<Notification> {
+        if idle_seconds < settings.idle_prompt_minutes * 60 {
+            return None;
+        }
+        self.allow(
+            settings,
+            local_time,
+            Notification {
+                kind: NotificationKind::IdleReturn,
+                title: "Welcome back".to_string(),
+                body: format!(
+                    "You were idle for {}. Open Time Tracker to keep or discard it.",
+                    format_minutes(idle_seconds)
+                ),
+            },
+        )
+    }
+
+    /// Warn once per day when today's tracked time passes the daily limit.
+    pub fn check_daily_limit(
+        &mut self,
+        settings: &NotificationSettings,
+        today: NaiveDate,
+        today_seconds: i64,
+        local_time: NaiveTime,
+    ) -> Option<Notification> {
+        if today_seconds < settings.daily_limit_minutes * 60 || self.limit_warned_on == Some(today)
+        {
+            return None;
+        }
+        let notification = self.allow(
+            settings,
+            local_time,
+            Notification {
+                kind: NotificationKind::DailyLimit,
+                title: "Daily limit reached".to_string(),
+                body: format!(
+                    "You have tracked {} today, past your limit of {}.",
+                    format_minutes(today_seconds),
+                    format_minutes(settings.daily_limit_minutes * 60)
+                ),
+            },
+        );
+        // Warnings suppressed by quiet hours are shown once they end
+        if notification.is_some() || !settings.daily_limit_enabled {
+            self.limit_warned_on = Some(today);
+        }
+        notification
+    }
+
+    fn allow(
+        &self,
+        settings: &NotificationSettings,
+        local_time: NaiveTime,
+        notification: Notification,
+    ) -> Option<Notification> {
+        (settings.is_enabled(notification.kind) && !settings.is_quiet(local_time))
+            .then_some(notification)
+    }
+}
+
+/// Show a native notification, which reaches the user even when the main
+/// window is hidden in the tray.
+pub fn show<R: Runtime>(app: &AppHandle<R>, notification: &Notification) {
+    let result = app
+        .notification()
+        .builder()
+        .title(&notification.title)
+        .body(&notification.body)
+        .show();
+    if let Err(e) = result {
+        log::error!("Failed to show notification: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(s: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(s, "%H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_quiet_hours_wrap_midnight() {
+        let settings = NotificationSettings {
+            quiet_hours_start: "22:00".to_string(),
+            quiet_hours_end: "07:30".to_string(),
+            ..Default::default()
+        };
+        assert!(settings.validate().is_ok());
+        assert!(settings.is_quiet(time("23:15")));
+        assert!(settings.is_quiet(time("03:00")));
+        assert!(!settings.is_quiet(time("07:30")));
+        assert!(!settings.is_quiet(time("12:00")));
+        assert!(!NotificationSettings::default().is_quiet(time("03:00")));
+
+        let half = NotificationSettings {
+            quiet_hours_end: String::new(),
+            ..settings
+        };
+        assert!(half.validate().is_err());
+    }
+
+    #[test]
+    fn test_break_reminder_resets_on_idle() {
+        let settings = NotificationSettings {
+            break_reminder_minutes: 30,
+            ..Default::default()
+        };
+        let mut policy = NotificationPolicy::new();
+        let noon = time("12:00");
+
+        let tick = |policy: &mut NotificationPolicy, from: i64, to: i64| {
+            (from..=to)
+                .filter_map(|minute| policy.on_active(&settings, minute * 60, noon))
+                .count()
+        };
+
+        assert_eq!(tick(&mut policy, 0, 29), 0);
+        // Idling resets the stretch
+        policy.on_idle();
+        assert_eq!(tick(&mut policy, 35, 64), 0);
+        let reminder = policy.on_active(&settings, 65 * 60, noon).unwrap();
+        assert_eq!(reminder.kind, NotificationKind::BreakReminder);
+        // The next reminder is another 30 minutes away
+        assert!(policy.on_active(&settings, 66 * 60, noon).is_none());
+    }
+
+    #[test]
+    fn test_daily_limit_warns_once_outside_quiet_hours() {
+        let settings = NotificationSettings {
+            daily_limit_enabled: true,
+            daily_limit_minutes: 60,
+            quiet_hours_start: "18:00".to_string(),
+            quiet_hours_end: "19:00".to_string(),
+            ..Default::default()
+        };
+        let mut policy = NotificationPolicy::new();
+        let today = NaiveDate::from_ymd_opt(2024, 3, 7).unwrap();
+
+        assert!(policy
+            .check_daily_limit(&settings, today, 59 * 60, time("17:00"))
+            .is_none());
+        assert!(policy
+            .check_daily_limit(&settings, today, 61 * 60, time("18:30"))
+            .is_none());
+        assert!(policy
+            .check_daily_limit(&settings, today, 95 * 60, time("19:05"))
+            .is_some());
+        assert!(policy
+            .check_daily_limit(&settings, today, 96 * 60, time("19:06"))
+            .is_none());
+
+        let short = policy.on_idle_return(&settings, 5 * 60, time("12:00"));
+        assert!(short.is_none());
+        let long = policy.on_idle_return(&settings, 25 * 60, time("12:00"));
+        assert_eq!(long.unwrap().kind, NotificationKind::IdleReturn);
+    }
+}
@@ -0,0 +1,471 @@
+use crate::db::{PresetImportReport, PresetPackInfo};
+use crate::import::ImportError;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Current pack format; packs with a newer version are rejected.
+pub const FORMAT_VERSION: u32 = 1;
+
+const BUILTIN_PACKS: &[(&str, &str)] = &[
+    ("developer", include_str!("packs/developer.json")),
+    ("designer", include_str!("packs/designer.json")),
+    ("manager", include_str!("packs/manager.json")),
+];
+
+/// Portable categories, rules and app assignments. Everything references
+/// categories by name so packs can move between databases.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresetPack {
+    pub format_version: u32,
+    pub name: String,
+    pub description: Option<String>,
+    pub categories: Vec<PackCategory>,
+    #[serde(default)]
+    pub rules: Vec<PackRule>,
+    #[serde(default)]
+    pub applications: Vec<PackApplication>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackCategory {
+    pub name: String,
+    pub color: String,
+    pub icon: Option<String>,
+    /// `None` inherits from the parent category
+    pub is_productive: Option<bool>,
+    pub parent: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackRule {
+    pub name: String,
+    pub rule_type: String,
+    pub pattern: String,
+    pub category: Option<String>,
+    pub priority: i32,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackApplication {
+    pub name: String,
+    pub identifier: Option<String>,
+    pub category: String,
+}
+
+/// What to do when a pack entry already exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictStrategy {
+    /// Keep the existing entry
+    Skip,
+    /// Replace the existing entry's properties with the pack's
+    Overwrite,
+    /// Import a copy under a new name, leaving the existing entry alone
+    Rename,
+}
+
+pub fn parse(json: &str) -> Result<PresetPack, ImportError> {
+    let pack: PresetPack = serde_json::from_str(json)?;
+    if pack.format_version > FORMAT_VERSION {
+        return Err(ImportError::Invalid(format!(
+            "pack format version {} is newer than supported version {}",
+            pack.format_version, FORMAT_VERSION
+        )));
+    }
+    Ok(pack)
+}
+
+pub fn builtin_packs() -> Result<Vec<PresetPackInfo>, ImportError> {
+    BUILTIN_PACKS
+        .iter()
+        .map(|(id, json)| {
+            let pack = parse(json)?;
+            Ok(PresetPackInfo {
+                id: id.to_string(),
+                name: pack.name,
+                description: pack.description,
+                category_count: pack.categories.len(),
+                rule_count: pack.rules.len(),
+                application_count: pack.applications.len(),
+            })
+        })
+        .collect()
+}
+
+pub fn builtin_pack(id: &str) -> Result<PresetPack, ImportError> {
+    let (_, json) = BUILTIN_PACKS
+        .iter()
+        .find(|(pack_id, _)| *pack_id == id)
+        .ok_or_else(|| ImportError::Invalid(format!("unknown preset pack '{}'", id)))?;
+    parse(json)
+}
+
+pub fn export(
+    conn: &Connection,
+    name: String,
+    description: Option<String>,
+) -> Result<PresetPack, rusqlite::Error> {
+    let categories = conn
+        .prepare(
+            "SELECT c.name, c.color, c.icon, c.is_productive, c.inherit_productive, p.name
+             FROM categories c
+             LEFT JOIN categories p ON c.parent_id = p.id
+             ORDER BY c.parent_id IS NOT NULL, c.id",
+        )?
+        .query_map([], |row| {
+            let parent: Option<String> = row.get(5)?;
+            let inherits = parent.is_some() && row.get::<_, i32>(4)? == 1;
+            Ok(PackCategory {
+                name: row.get(0)?,
+                color: row.get(1)?,
+                icon: row.get(2)?,
+                is_productive: (!inherits).then_some(row.get::<_, i32>(3)? == 1),
+                parent,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let rules = conn
+        .prepare(
+            "SELECT r.name, r.rule_type, r.pattern, c.name, r.priority, r.is_active
+             FROM categorization_rules r
+             LEFT JOIN categories c ON r.category_id = c.id
+             ORDER BY r.priority DESC, r.id",
+        )?
+        .query_map([], |row| {
+            Ok(PackRule {
+                name: row.get(0)?,
+                rule_type: row.get(1)?,
+                pattern: row.get(2)?,
+                category: row.get(3)?,
+                priority: row.get(4)?,
+                is_active: row.get::<_, i32>(5)? == 1,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let applications = conn
+        .prepare(
+            "SELECT a.name, a.identifier, c.name
+             FROM applications a
+             JOIN categories c ON a.category_id = c.id
+             ORDER BY a.name",
+        )?
+        .query_map([], |row| {
+            Ok(PackApplication {
+                name: row.get(0)?,
+                identifier: row.get(1)?,
+                category: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(PresetPack {
+        format_version: FORMAT_VERSION,
+        name,
+        description,
+        categories,
+        rules,
+        applications,
+    })
+}
+
+/// Apply a pack. Call inside a transaction; nothing is rolled back on error here.
+pub fn import(
+    conn: &Connection,
+    pack: &PresetPack,
+    strategy: ConflictStrategy,
+) -> Result<PresetImportReport, ImportError> {
+    let mut report = PresetImportReport::default();
+    // Pack category name -> database id, plus whether we may change its parent
+    let mut categories: HashMap<&str, (i64, bool)> = HashMap::new();
+
+    for category in &pack.categories {
+        let existing = find_id(conn, "categories", &category.name)?;
+        let productive = category.is_productive.unwrap_or(true) as i32;
+        let inherit = (category.is_productive.is_none() && category.parent.is_some()) as i32;
+
+        let entry = match (existing, strategy) {
+            (Some(id), ConflictStrategy::Skip) => {
+                report.categories_skipped += 1;
+                (id, false)
+            }
+            (Some(id), ConflictStrategy::Overwrite) => {
+                conn.execute(
+                    "UPDATE categories
+                     SET color = ?, icon = ?, is_productive = ?, inherit_productive = ?,
+                         updated_at = strftime('%s', 'now')
+                     WHERE id = ?",
+                    rusqlite::params![category.color, category.icon, productive, inherit, id],
+                )?;
+                report.categories_updated += 1;
+                (id, true)
+            }
+            (existing, _) => {
+                let name = match existing {
+                    Some(_) => unique_name(conn, "categories", &category.name)?,
+                    None => category.name.clone(),
+                };
+                conn.execute(
+                    "INSERT INTO categories (name, color, icon, is_productive, inherit_productive)
+                     VALUES (?, ?, ?, ?, ?)",
+                    rusqlite::params![name, category.color, category.icon, productive, inherit],
+                )?;
+                report.categories_created += 1;
+                (conn.last_insert_rowid(), true)
+            }
+        };
+        categories.insert(category.name.as_str(), entry);
+    }
+
+    // Parents are linked once every pack category exists; packs list parents
+    // first, so inherited productivity can be copied in the same pass
+    for category in &pack.categories {
+        let (id, editable) = categories[category.name.as_str()];
+        let Some(parent_name) = category.parent.as_deref().filter(|_| editable) else {
+            continue;
+        };
+        let parent_id = match categories.get(parent_name) {
+            Some((parent_id, _)) => Some(*parent_id),
+            None => find_id(conn, "categories", parent_name)?,
+        };
+        let Some(parent_id) = parent_id.filter(|p| !is_in_subtree(conn, id, *p).unwrap_or(true))
+        else {
+            log::warn!(
+                "Skipping parent '{}' of category '{}'",
+                parent_name,
+                category.name
+            );
+            continue;
+        };
+
+        conn.execute(
+            "UPDATE categories SET parent_id = ? WHERE id = ?",
+            [parent_id, id],
+        )?;
+        conn.execute(
+            "UPDATE categories
+             SET is_productive = (SELECT is_productive FROM categories WHERE id = ?1)
+             WHERE id = ?2 AND inherit_productive = 1",
+            [parent_id, id],
+        )?;
+    }
+
+    let category_id = |name: &str| -> Result<Option<i64>, rusqlite::Error> {
+        match categories.get(name) {
+            Some((id, _)) => Ok(Some(*id)),
+            None => find_id(conn, "categories", name),
+        }
+    };
+
+    for rule in &pack.rules {
+        let target = match rule.category.as_deref() {
+            Some(name) => category_id(name)?,
+            None => None,
+        };
+        let existing: Option<i64> = conn
+            .query_row(
+                "SELECT id FROM categorization_rules WHERE rule_type = ? AND pattern = ?",
+                [&rule.rule_type, &rule.pattern],
+                |row| row.get(0),
+            )
+            .ok();
+
+        match (existing, strategy) {
+            (Some(_), ConflictStrategy::Skip) => report.rules_skipped += 1,
+            (Some(id), ConflictStrategy::Overwrite) => {
+                conn.execute(
+                    "UPDATE categorization_rules
+                     SET name = ?, category_id = ?, priority = ?, is_active = ?
+                     WHERE id = ?",
+                    rusqlite::params![rule.name, target, rule.priority, rule.is_active as i32, id],
+                )?;
+                report.rules_updated += 1;
+            }
+            (existing, _) => {
+                let name = match existing {
+                    Some(_) => unique_name(conn, "categorization_rules", &rule.name)?,
+                    None => rule.name.clone(),
+                };
+                conn.execute(
+                    "INSERT INTO categorization_rules
+                        (name, rule_type, pattern, category_id, priority, is_active)
+                     VALUES (?, ?, ?, ?, ?, ?)",
+                    rusqlite::params![
+                        name,
+                        rule.rule_type,
+                        rule.pattern,
+                        target,
+                        rule.priority,
+                        rule.is_active as i32
+                    ],
+                )
+                .map_err(|e| {
+                    ImportError::Invalid(format!("invalid rule '{}': {}", rule.name, e))
+                })?;
+                report.rules_created += 1;
+            }
+        }
+    }
+
+    for app in &pack.applications {
+        let Some(target) = category_id(&app.category)? else {
+            report.applications_skipped += 1;
+            continue;
+        };
+        let identifier = app.identifier.as_deref().unwrap_or(&app.name);
+        let existing: Option<(i64, Option<i64>)> = conn
+            .query_row(
+                "SELECT id, category_id FROM applications WHERE identifier = ? OR name = ?",
+                [identifier, &app.name],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .ok();
+
+        match existing {
+            // Only overwrite replaces an assignment the user already made
+            Some((_, Some(_))) if strategy != ConflictStrategy::Overwrite => {
+                report.applications_skipped += 1;
+            }
+            Some((id, _)) => {
+                conn.execute(
+                    "UPDATE applications SET category_id = ?, updated_at = strftime('%s', 'now')
+                     WHERE id = ?",
+                    [target, id],
+                )?;
+                report.applications_assigned += 1;
+            }
+            None => {
+                conn.execute(
+                    "INSERT INTO applications (name, identifier, category_id) VALUES (?, ?, ?)",
+                    rusqlite::params![app.name, identifier, target],
+                )?;
+                report.applications_assigned += 1;
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+fn find_id(conn: &Connection, table: &str, name: &str) -> Result<Option<i64>, rusqlite::Error> {
+    match conn.query_row(
+        &format!(
+            "SELECT id FROM {} WHERE name = ? ORDER BY id LIMIT 1",
+            table
+        ),
+        [name],
+        |row| row.get(0),
+    ) {
+        Ok(id) => Ok(Some(id)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// First of "Name (2)", "Name (3)", ... not yet used in `table`.
+fn unique_name(conn: &Connection, table: &str, base: &str) -> Result<String, rusqlite::Error> {
+    let mut n = 2;
+    loop {
+        let candidate = format!("{} ({})", base, n);
+        if find_id(conn, table, &candidate)?.is_none() {
+            return Ok(candidate);
+        }
+        n += 1;
+    }
+}
+
+/// Whether `candidate` is `id` or one of its descendants.
+fn is_in_subtree(conn: &Connection, id: i64, candidate: i64) -> Result<bool, rusqlite::Error> {
+    conn.query_row(
+        "WITH RECURSIVE subtree(id) AS (
+             SELECT ?1
+             UNION ALL
+             SELECT c.id FROM categories c JOIN subtree s ON c.parent_id = s.id
+         )
+         SELECT EXISTS(SELECT 1 FROM subtree WHERE id = ?2)",
+        [id, candidate],
+        |row| row.get(0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    #[test]
+    fn test_builtin_packs_parse() {
+        let packs = builtin_packs().expect("Failed to parse built-in packs");
+        assert_eq!(packs.len(), 3);
+        assert!(packs.iter().all(|p| p.category_count > 0));
+    }
+
+    #[test]
+    fn test_import_strategies() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+        let pack = builtin_pack("developer").expect("Failed to load pack");
+
+        let first = import(&conn, &pack, ConflictStrategy::Skip).expect("Failed to import");
+        // Development, Communication and Entertainment are seeded already
+        assert_eq!(first.categories_created, 3);
+        assert_eq!(first.categories_skipped, 3);
+        assert_eq!(first.rules_created, 5);
+        assert_eq!(first.applications_assigned, 6);
+
+        let (parent, productive): (String, bool) = conn
+            .query_row(
+                "SELECT p.name, c.is_productive FROM categories c
+                 JOIN categories p ON c.parent_id = p.id WHERE c.name = 'Code Review'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .expect("Failed to read subcategory");
+        assert_eq!(parent, "Development");
+        assert!(productive);
+
+        let skipped = import(&conn, &pack, ConflictStrategy::Skip).expect("Failed to re-import");
+        assert_eq!(skipped.categories_created, 0);
+        assert_eq!(skipped.rules_skipped, 5);
+        assert_eq!(skipped.applications_skipped, 6);
+
+        let renamed = import(&conn, &pack, ConflictStrategy::Rename).expect("Failed to rename");
+        assert_eq!(renamed.categories_created, 6);
+        let exists: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM categories WHERE name = 'Coding (2)')",
+                [],
+                |row| row.get(0),
+            )
+            .expect("Failed to query");
+        assert!(exists);
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+        let pack = builtin_pack("manager").expect("Failed to load pack");
+        import(&conn, &pack, ConflictStrategy::Overwrite).expect("Failed to import");
+
+        let exported = export(&conn, "Team".to_string(), None).expect("Failed to export");
+        let json = serde_json::to_string(&exported).expect("Failed to serialize");
+        let parsed = parse(&json).expect("Failed to parse exported pack");
+
+        let email = parsed
+            .categories
+            .iter()
+            .find(|c| c.name == "Email")
+            .expect("Missing Email category");
+        assert_eq!(email.parent.as_deref(), Some("Communication"));
+        assert_eq!(email.is_productive, None);
+        assert_eq!(parsed.rules.len(), 4);
+        assert!(parsed
+            .applications
+            .iter()
+            .any(|a| a.name == "Slack" && a.category == "Chat"));
+    }
+}
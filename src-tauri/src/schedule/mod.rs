@@ -0,0 +1,235 @@
+use crate::db::SessionReason;
+use crate::state::AppState;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeRange {
+    /// Local "HH:MM", inclusive
+    pub start: String,
+    /// Local "HH:MM", exclusive
+    pub end: String,
+}
+
+/// Work hours outside of which tracking pauses itself.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrackingSchedule {
+    pub enabled: bool,
+    /// Ranges for each weekday, Monday first
+    pub days: Vec<Vec<TimeRange>>,
+    /// "YYYY-MM-DD" dates with no tracking at all
+    pub holidays: Vec<String>,
+}
+
+impl Default for TrackingSchedule {
+    fn default() -> Self {
+        let workday = || {
+            vec![TimeRange {
+                start: "09:00".to_string(),
+                end: "17:00".to_string(),
+            }]
+        };
+        Self {
+            enabled: false,
+            days: (0..7)
+                .map(|day| if day < 5 { workday() } else { Vec::new() })
+                .collect(),
+            holidays: Vec::new(),
+        }
+    }
+}
+
+fn parse_time(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value, "%H:%M")
+        .map_err(|_| format!("Invalid time '{}', expected HH:MM", value))
+}
+
+impl TrackingSchedule {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.days.len() != 7 {
+            return Err(format!(
+                "Schedule needs ranges for 7 weekdays, got {}",
+                self.days.len()
+            ));
+        }
+        for range in self.days.iter().flatten() {
+            if parse_time(&range.start)? >= parse_time(&range.end)? {
+                return Err(format!(
+                    "Schedule range {}-{} must end after it starts",
+                    range.start, range.end
+                ));
+            }
+        }
+        for date in &self.holidays {
+            NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|_| format!("Invalid holiday '{}', expected YYYY-MM-DD", date))?;
+        }
+        Ok(())
+    }
+
+    pub fn is_holiday(&self, date: NaiveDate) -> bool {
+        self.holidays.contains(&date.to_string())
+    }
+
+    /// Whether `at` falls inside the schedule. A disabled schedule allows
+    /// any time.
+    pub fn contains(&self, at: NaiveDateTime) -> bool {
+        if !self.enabled {
+            return true;
+        }
+        if self.is_holiday(at.date()) {
+            return false;
+        }
+        let time = at.time();
+        self.days
+            .get(at.weekday().num_days_from_monday() as usize)
+            .into_iter()
+            .flatten()
+            .any(
+                |range| match (parse_time(&range.start), parse_time(&range.end)) {
+                    (Ok(start), Ok(end)) => start <= time && time < end,
+                    _ => false,
+                },
+            )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleStatus {
+    pub enabled: bool,
+    /// Whether now is inside the scheduled hours
+    pub in_schedule: bool,
+    pub override_until: Option<i64>,
+    /// Whether tracking may run, counting the override
+    pub tracking_allowed: bool,
+}
+
+/// The schedule plus what the tracking loop needs to act on it.
+#[derive(Debug, Default)]
+pub struct ScheduleState {
+    pub schedule: TrackingSchedule,
+    /// "Track anyway" until this time, ignoring the schedule
+    pub override_until: Option<i64>,
+    /// Set when the schedule paused tracking, so it may resume it
+    pub paused_tracking: bool,
+    allowed: Option<bool>,
+}
+
+impl ScheduleState {
+    pub fn new(schedule: TrackingSchedule) -> Self {
+        Self {
+            schedule,
+            ..Default::default()
+        }
+    }
+
+    pub fn status(&self, local: NaiveDateTime, now: i64) -> ScheduleStatus {
+        let in_schedule = self.schedule.contains(local);
+        let override_until = self.override_until.filter(|until| *until > now);
+        ScheduleStatus {
+            enabled: self.schedule.enabled,
+            in_schedule,
+            override_until,
+            tracking_allowed: in_schedule || override_until.is_some(),
+        }
+    }
+
+    /// Re-evaluate the schedule, returning the new status when whether
+    /// tracking is allowed has changed since the last check.
+    pub fn check(&mut self, local: NaiveDateTime, now: i64) -> Option<ScheduleStatus> {
+        if self.override_until.is_some_and(|until| until <= now) {
+            self.override_until = None;
+        }
+        let status = self.status(local, now);
+        if self.allowed == Some(status.tracking_allowed) {
+            return None;
+        }
+        self.allowed = Some(status.tracking_allowed);
+        Some(status)
+    }
+}
+
+/// Pause tracking when work hours end and resume it when they start again,
+/// emitting `schedule:changed` on each change. Returns whether anything
+/// changed, so the caller can refresh the tray.
+#[must_use]
+pub fn apply<R: Runtime>(app: &AppHandle<R>, state: &AppState) -> bool {
+    let now = chrono::Local::now();
+    let status = {
+        let mut schedule = state.schedule.lock();
+        let Some(status) = schedule.check(now.naive_local(), now.timestamp()) else {
+            return false;
+        };
+        if !status.tracking_allowed && state.is_tracking() {
+            log::info!("Outside scheduled hours, pausing tracking");
+            state.stop_session(SessionReason::Schedule);
+            schedule.paused_tracking = true;
+        } else if status.tracking_allowed && schedule.paused_tracking {
+            log::info!("Scheduled hours started, resuming tracking");
+            state.start_session(SessionReason::Schedule);
+            schedule.paused_tracking = false;
+        }
+        status
+    };
+
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.emit("schedule:changed", &status);
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_schedule_ranges_and_holidays() {
+        let mut schedule = TrackingSchedule {
+            enabled: true,
+            holidays: vec!["2024-03-08".to_string()],
+            ..Default::default()
+        };
+        schedule.days[0].push(TimeRange {
+            start: "18:00".to_string(),
+            end: "19:30".to_string(),
+        });
+        assert!(schedule.validate().is_ok());
+
+        // 2024-03-04 is a Monday
+        assert!(schedule.contains(at("2024-03-04 09:00")));
+        assert!(!schedule.contains(at("2024-03-04 17:00")));
+        assert!(schedule.contains(at("2024-03-04 18:45")));
+        assert!(!schedule.contains(at("2024-03-08 10:00")));
+        assert!(!schedule.contains(at("2024-03-09 10:00")));
+
+        schedule.days[1][0].end = "08:00".to_string();
+        assert!(schedule.validate().is_err());
+    }
+
+    #[test]
+    fn test_check_reports_changes_and_override() {
+        let mut state = ScheduleState::new(TrackingSchedule {
+            enabled: true,
+            ..Default::default()
+        });
+        let evening = at("2024-03-04 18:00");
+        let now = evening.and_utc().timestamp();
+
+        let first = state.check(evening, now).unwrap();
+        assert!(!first.tracking_allowed);
+        assert!(state.check(evening, now + 1).is_none());
+
+        state.override_until = Some(now + 3600);
+        assert!(state.check(evening, now + 2).unwrap().tracking_allowed);
+        assert!(state.check(evening, now + 1800).is_none());
+        // The override runs out
+        let expired = state.check(evening, now + 3600).unwrap();
+        assert!(!expired.tracking_allowed);
+        assert_eq!(state.override_until, None);
+    }
+}
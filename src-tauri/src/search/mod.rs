@@ -0,0 +1,168 @@
+use crate::db::ActivitySearchMatch;
+use rusqlite::types::Value;
+use rusqlite::Connection;
+use std::collections::HashMap;
+
+// Private-use markers passed to snippet() so highlighting survives escaping
+const MARK_START: &str = "\u{E000}";
+const MARK_END: &str = "\u{E001}";
+
+/// Turn free text into an FTS5 query: every word must match, as a prefix.
+///
+/// Words are quoted so punctuation such as `ABC-123` is matched as a phrase
+/// instead of being parsed as query syntax. Returns `None` for blank input.
+pub fn fts_query(text: &str, column: Option<&str>) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        return None;
+    }
+
+    let query = terms.join(" ");
+    Some(match column {
+        Some(column) => format!("{} : ({})", column, query),
+        None => query,
+    })
+}
+
+/// Search window titles, URLs and notes of records between two optional
+/// local dates. Matches are grouped by window title, best match first.
+pub fn search(
+    conn: &Connection,
+    query: &str,
+    start_date: Option<&str>,
+    end_date: Option<&str>,
+    limit: usize,
+) -> Result<Vec<ActivitySearchMatch>, rusqlite::Error> {
+    let Some(query) = fts_query(query, None) else {
+        return Ok(Vec::new());
+    };
+
+    let mut sql = format!(
+        "SELECT ar.id, ar.window_title, a.name, ar.start_time, ar.end_time,
+                snippet(activity_search, -1, '{}', '{}', '…', 12)
+         FROM activity_search
+         JOIN activity_records ar ON ar.id = activity_search.rowid
+         LEFT JOIN applications a ON ar.application_id = a.id
+         WHERE activity_search MATCH ? AND ar.is_idle = 0",
+        MARK_START, MARK_END
+    );
+    let mut params = vec![Value::Text(query)];
+    if let Some(start_date) = start_date {
+        sql.push_str(" AND date(ar.start_time, 'unixepoch', 'localtime') >= ?");
+        params.push(Value::Text(start_date.to_string()));
+    }
+    if let Some(end_date) = end_date {
+        sql.push_str(" AND date(ar.start_time, 'unixepoch', 'localtime') <= ?");
+        params.push(Value::Text(end_date.to_string()));
+    }
+    sql.push_str(" ORDER BY bm25(activity_search), ar.start_time DESC");
+
+    let mut stmt = conn.prepare(&sql)?;
+    let mut rows = stmt.query(rusqlite::params_from_iter(params))?;
+
+    let mut matches: Vec<ActivitySearchMatch> = Vec::new();
+    let mut by_title: HashMap<String, usize> = HashMap::new();
+
+    while let Some(row) = rows.next()? {
+        let id: i64 = row.get(0)?;
+        let title: String = row.get(1)?;
+        let start: i64 = row.get(3)?;
+        let end: i64 = row.get(4)?;
+
+        match by_title.get(&title) {
+            Some(&index) => {
+                let found = &mut matches[index];
+                found.record_ids.push(id);
+                found.total_seconds += end - start;
+                found.first_seen = found.first_seen.min(start);
+                found.last_seen = found.last_seen.max(end);
+            }
+            None => {
+                by_title.insert(title.clone(), matches.len());
+                matches.push(ActivitySearchMatch {
+                    window_title: title,
+                    app_name: row.get(2)?,
+                    snippet: highlight(&row.get::<_, String>(5)?),
+                    record_ids: vec![id],
+                    total_seconds: end - start,
+                    first_seen: start,
+                    last_seen: end,
+                });
+            }
+        }
+    }
+
+    matches.truncate(limit);
+    Ok(matches)
+}
+
+/// Escape snippet text for HTML and swap the markers for `<mark>` tags.
+fn highlight(snippet: &str) -> String {
+    snippet
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace(MARK_START, "<mark>")
+        .replace(MARK_END, "</mark>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    #[test]
+    fn test_fts_query_quotes_terms() {
+        assert_eq!(
+            fts_query("ABC-123 login", None).unwrap(),
+            "\"ABC-123\"* \"login\"*"
+        );
+        assert_eq!(
+            fts_query("say \"hi\"", Some("notes")).unwrap(),
+            "notes : (\"say\"* \"\"\"hi\"\"\"*)"
+        );
+        assert!(fts_query("   ", None).is_none());
+    }
+
+    #[test]
+    fn test_search_groups_by_title_and_tracks_edits() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+
+        conn.execute_batch(
+            "INSERT INTO activity_records (id, window_title, start_time, end_time) VALUES
+                 (1, 'ABC-123 Fix <login> - Jira', 1000, 1600),
+                 (2, 'Inbox', 1600, 1700),
+                 (3, 'ABC-123 Fix <login> - Jira', 1700, 2000),
+                 (4, 'ABC-124 Other ticket', 2000, 2100);",
+        )
+        .expect("Failed to seed records");
+
+        let results = search(&conn, "abc-123", None, None, 10).expect("Failed to search");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].record_ids.len(), 2);
+        assert_eq!(results[0].total_seconds, 900);
+        assert_eq!(results[0].last_seen, 2000);
+        assert_eq!(
+            results[0].snippet,
+            "<mark>ABC-123</mark> Fix &lt;login&gt; - Jira"
+        );
+
+        conn.execute(
+            "UPDATE activity_records SET notes = 'pairing on ABC-123' WHERE id = 2",
+            [],
+        )
+        .expect("Failed to add note");
+        conn.execute("DELETE FROM activity_records WHERE id = 1", [])
+            .expect("Failed to delete record");
+
+        let results = search(&conn, "abc-123", None, None, 10).expect("Failed to search");
+        let ids: Vec<i64> = results.iter().flat_map(|m| m.record_ids.clone()).collect();
+        assert_eq!(results.len(), 2);
+        assert!(ids.contains(&2) && ids.contains(&3) && !ids.contains(&1));
+    }
+}
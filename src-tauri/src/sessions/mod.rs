@@ -0,0 +1,123 @@
+use crate::db::{SessionReason, TrackingSession};
+use rusqlite::Connection;
+
+pub fn start(conn: &Connection, reason: SessionReason, now: i64) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO sessions (started_at, start_reason) VALUES (?, ?)",
+        rusqlite::params![now, reason.as_str()],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn end(
+    conn: &Connection,
+    session_id: i64,
+    reason: SessionReason,
+    at: i64,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE sessions SET ended_at = MAX(started_at, ?), end_reason = ?
+         WHERE id = ? AND ended_at IS NULL",
+        rusqlite::params![at, reason.as_str(), session_id],
+    )?;
+    Ok(())
+}
+
+/// Close sessions a previous run never ended, which means it did not quit
+/// cleanly, at the last moment anything was recorded in them.
+pub fn close_stale_sessions(conn: &Connection) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE sessions
+         SET ended_at = MAX(started_at, COALESCE(
+                 (SELECT MAX(end_time) FROM activity_records
+                  WHERE session_id = sessions.id), started_at)),
+             end_reason = 'quit'
+         WHERE ended_at IS NULL",
+        [],
+    )
+}
+
+/// Sessions that started between two dates, oldest first, with the time
+/// recorded in each.
+pub fn list(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+    now: i64,
+) -> rusqlite::Result<Vec<TrackingSession>> {
+    let mut stmt = conn.prepare(
+        "SELECT s.id, s.started_at, s.ended_at, s.start_reason, s.end_reason,
+                COALESCE(SUM(a.duration_seconds), 0), COUNT(a.id)
+         FROM sessions s
+         LEFT JOIN activity_records a ON a.session_id = s.id AND a.is_idle = 0
+         WHERE date(s.started_at, 'unixepoch', 'localtime') BETWEEN ? AND ?
+         GROUP BY s.id
+         ORDER BY s.started_at",
+    )?;
+    let sessions = stmt
+        .query_map([start_date, end_date], |row| {
+            let started_at: i64 = row.get(1)?;
+            let ended_at: Option<i64> = row.get(2)?;
+            Ok(TrackingSession {
+                id: row.get(0)?,
+                started_at,
+                ended_at,
+                start_reason: SessionReason::parse(&row.get::<_, String>(3)?),
+                end_reason: row
+                    .get::<_, Option<String>>(4)?
+                    .map(|r| SessionReason::parse(&r)),
+                duration_seconds: (ended_at.unwrap_or(now) - started_at).max(0),
+                active_seconds: row.get(5)?,
+                record_count: row.get(6)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(sessions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_sessions_record_reasons_and_linked_time() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+        let day = "2024-03-04";
+        let base = chrono::Local
+            .with_ymd_and_hms(2024, 3, 4, 12, 0, 0)
+            .unwrap()
+            .timestamp();
+
+        let first = start(&conn, SessionReason::User, base).unwrap();
+        conn.execute(
+            "INSERT INTO activity_records (window_title, start_time, end_time, session_id)
+             VALUES ('a', ?1, ?1 + 600, ?2), ('b', ?1 + 600, ?1 + 900, ?2)",
+            [base, first],
+        )
+        .unwrap();
+        end(&conn, first, SessionReason::Idle, base + 900).unwrap();
+
+        // A session left open by a crash closes at its last record
+        let second = start(&conn, SessionReason::Idle, base + 1800).unwrap();
+        conn.execute(
+            "INSERT INTO activity_records (window_title, start_time, end_time, session_id)
+             VALUES ('c', ?1 + 1800, ?1 + 2400, ?2)",
+            [base, second],
+        )
+        .unwrap();
+        assert_eq!(close_stale_sessions(&conn).unwrap(), 1);
+
+        let sessions = list(&conn, day, day, base + 9999).unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].end_reason, Some(SessionReason::Idle));
+        assert_eq!(sessions[0].active_seconds, 900);
+        assert_eq!(sessions[0].record_count, 2);
+        assert_eq!(sessions[1].start_reason, SessionReason::Idle);
+        assert_eq!(sessions[1].end_reason, Some(SessionReason::Quit));
+        assert_eq!(sessions[1].ended_at, Some(base + 2400));
+        assert_eq!(sessions[1].duration_seconds, 600);
+    }
+}
@@ -0,0 +1,400 @@
+use crate::db::SessionReason;
+use crate::schedule::TrackingSchedule;
+use crate::state::AppState;
+use crate::workhours::WorkHoursSettings;
+use chrono::NaiveTime;
+use rusqlite::Connection;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The type of a setting's value and the values it accepts.
+#[derive(Debug, Clone, Copy)]
+pub enum SettingKind {
+    Integer { min: i64, max: i64 },
+    Boolean,
+    Choice(&'static [&'static str]),
+    /// Local "HH:MM", or empty for none
+    Time,
+    /// A record id, or null for none
+    Id,
+    /// A JSON document, checked by the given function
+    Json(fn(&Value) -> Result<(), String>),
+}
+
+/// One key in the settings table.
+pub struct SettingDef {
+    pub key: &'static str,
+    pub kind: SettingKind,
+    pub default: fn() -> Value,
+    /// Applies a new value to the running app once it has been saved
+    pub on_change: Option<fn(&AppState, &Value)>,
+}
+
+impl SettingDef {
+    /// Check a value and return its stored form.
+    pub fn to_stored(&self, value: &Value) -> Result<String, String> {
+        match self.kind {
+            SettingKind::Integer { min, max } => {
+                let n = value
+                    .as_i64()
+                    .ok_or_else(|| format!("{} must be a whole number", self.key))?;
+                if !(min..=max).contains(&n) {
+                    return Err(format!(
+                        "{} must be between {} and {}, got {}",
+                        self.key, min, max, n
+                    ));
+                }
+                Ok(n.to_string())
+            }
+            SettingKind::Boolean => value
+                .as_bool()
+                .map(|b| b.to_string())
+                .ok_or_else(|| format!("{} must be true or false", self.key)),
+            SettingKind::Choice(choices) => match value.as_str() {
+                Some(s) if choices.contains(&s) => Ok(s.to_string()),
+                _ => Err(format!(
+                    "{} must be one of {}",
+                    self.key,
+                    choices.join(", ")
+                )),
+            },
+            SettingKind::Time => match value.as_str() {
+                Some("") => Ok(String::new()),
+                Some(s) if NaiveTime::parse_from_str(s, "%H:%M").is_ok() => Ok(s.to_string()),
+                _ => Err(format!("{} must be a time as HH:MM or empty", self.key)),
+            },
+            SettingKind::Id => match value {
+                Value::Null => Ok(String::new()),
+                _ => match value.as_i64() {
+                    Some(id) if id > 0 => Ok(id.to_string()),
+                    _ => Err(format!("{} must be an id or null", self.key)),
+                },
+            },
+            SettingKind::Json(check) => {
+                check(value).map_err(|e| format!("{}: {}", self.key, e))?;
+                Ok(value.to_string())
+            }
+        }
+    }
+
+    /// The value for a stored string, or None if it is not a valid one.
+    fn parse_stored(&self, stored: &str) -> Option<Value> {
+        let value = match self.kind {
+            SettingKind::Integer { .. } => Value::from(stored.parse::<i64>().ok()?),
+            SettingKind::Boolean => Value::from(stored.parse::<bool>().ok()?),
+            SettingKind::Choice(_) | SettingKind::Time => Value::from(stored),
+            SettingKind::Id if stored.is_empty() => Value::Null,
+            SettingKind::Id => Value::from(stored.parse::<i64>().ok()?),
+            SettingKind::Json(_) => serde_json::from_str(stored).ok()?,
+        };
+        self.to_stored(&value).ok().map(|_| value)
+    }
+
+    /// The stored value, falling back to the default when it is missing or
+    /// invalid, such as out of range.
+    pub fn load(&self, conn: &Connection) -> Value {
+        let stored = conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = ?",
+                [self.key],
+                |row| row.get::<_, String>(0),
+            )
+            .ok();
+        stored
+            .and_then(|stored| {
+                let value = self.parse_stored(&stored);
+                if value.is_none() {
+                    log::warn!("Ignoring invalid {} setting '{}'", self.key, stored);
+                }
+                value
+            })
+            .unwrap_or_else(self.default)
+    }
+}
+
+/// Declares every stored setting once, grouped into the structs the app
+/// reads them as, generating those structs and the `REGISTRY` the generic
+/// commands work from.
+macro_rules! app_settings {
+    ($(
+        $(#[doc = $group_doc:literal])*
+        $group:ident {
+            $(
+                $(#[doc = $doc:literal])*
+                $field:ident: $ty:ty = $default:expr, $key:literal, $kind:expr, $on_change:expr;
+            )*
+        }
+    )*) => {
+        $(
+            $(#[doc = $group_doc])*
+            #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+            pub struct $group {
+                $(
+                    $(#[doc = $doc])*
+                    pub $field: $ty,
+                )*
+            }
+
+            impl Default for $group {
+                fn default() -> Self {
+                    Self {
+                        $($field: $default.into(),)*
+                    }
+                }
+            }
+
+            impl $group {
+                pub fn load(conn: &Connection) -> Self {
+                    let defaults = Self::default();
+                    Self {
+                        $(
+                            $field: serde_json::from_value(
+                                lookup($key).map_or(Value::Null, |def| def.load(conn)),
+                            )
+                            .unwrap_or(defaults.$field),
+                        )*
+                    }
+                }
+
+                /// Stored form of each setting, checked against its definition.
+                #[allow(dead_code)] // Some groups are only saved one key at a time
+                pub fn values(&self) -> Result<Vec<(&'static str, String)>, String> {
+                    let mut values = Vec::new();
+                    $(
+                        let def = lookup($key).ok_or("Unknown setting")?;
+                        let value = serde_json::to_value(&self.$field).map_err(|e| e.to_string())?;
+                        values.push((def.key, def.to_stored(&value)?));
+                    )*
+                    Ok(values)
+                }
+            }
+        )*
+
+        pub static REGISTRY: &[SettingDef] = &[
+            $($(
+                SettingDef {
+                    key: $key,
+                    kind: $kind,
+                    default: || serde_json::to_value(<$ty>::from($default)).unwrap_or(Value::Null),
+                    on_change: $on_change,
+                },
+            )*)*
+        ];
+    };
+}
+
+/// Parse a JSON setting as `T`, for the checks of `SettingKind::Json`.
+fn parse_json<T: DeserializeOwned>(value: &Value) -> Result<T, String> {
+    serde_json::from_value(value.clone()).map_err(|e| e.to_string())
+}
+
+fn reload_timer_settings(state: &AppState, _: &Value) {
+    let settings = TimerSettings::load(&state.db.conn());
+    state.timer.lock().set_settings(settings);
+}
+
+app_settings! {
+    AppSettings {
+        /// Seconds without input before the user counts as idle
+        idle_threshold_seconds: i64 = 300, "idle_threshold_seconds",
+            SettingKind::Integer { min: 30, max: 7200 },
+            Some(|state, value| {
+                if let Some(seconds) = value.as_u64() {
+                    state.set_idle_threshold(seconds);
+                }
+            });
+        tracking_enabled: bool = true, "tracking_enabled",
+            SettingKind::Boolean,
+            Some(|state, value| {
+                let enabled = value.as_bool().unwrap_or(true);
+                if enabled != state.is_tracking() {
+                    if enabled {
+                        state.start_session(SessionReason::User);
+                    } else {
+                        state.stop_session(SessionReason::User);
+                    }
+                }
+            });
+        polling_interval_ms: i64 = 1000, "polling_interval_ms",
+            SettingKind::Integer { min: 250, max: 10_000 },
+            None;
+        theme: String = "system", "theme",
+            SettingKind::Choice(&["system", "light", "dark"]),
+            None;
+    }
+
+    /// What the app picks up again on the next launch.
+    TrackingState {
+        /// Project new activity is attributed to
+        current_project_id: Option<i64> = None::<i64>, "current_project_id",
+            SettingKind::Id,
+            Some(|state, value| state.set_current_project(value.as_i64()));
+    }
+
+    NotificationSettings {
+        break_reminder_enabled: bool = true, "notify_break_reminder",
+            SettingKind::Boolean,
+            None;
+        break_reminder_minutes: i64 = 50, "break_reminder_minutes",
+            SettingKind::Integer { min: 1, max: 24 * 60 },
+            None;
+        daily_limit_enabled: bool = false, "notify_daily_limit",
+            SettingKind::Boolean,
+            None;
+        daily_limit_minutes: i64 = 480, "daily_limit_minutes",
+            SettingKind::Integer { min: 1, max: 24 * 60 },
+            None;
+        idle_prompt_enabled: bool = true, "notify_idle_prompt",
+            SettingKind::Boolean,
+            None;
+        /// Only ask about idle stretches at least this long
+        idle_prompt_minutes: i64 = 20, "idle_prompt_minutes",
+            SettingKind::Integer { min: 1, max: 24 * 60 },
+            None;
+        /// Local "HH:MM"; no notifications are shown from start until end. Empty
+        /// strings disable quiet hours.
+        quiet_hours_start: String = "", "quiet_hours_start",
+            SettingKind::Time,
+            None;
+        quiet_hours_end: String = "", "quiet_hours_end",
+            SettingKind::Time,
+            None;
+    }
+
+    TimerSettings {
+        work_minutes: i64 = 25, "timer_work_minutes",
+            SettingKind::Integer { min: 1, max: 24 * 60 },
+            Some(reload_timer_settings);
+        short_break_minutes: i64 = 5, "timer_short_break_minutes",
+            SettingKind::Integer { min: 1, max: 24 * 60 },
+            Some(reload_timer_settings);
+        long_break_minutes: i64 = 15, "timer_long_break_minutes",
+            SettingKind::Integer { min: 1, max: 24 * 60 },
+            Some(reload_timer_settings);
+        /// Every this many completed work phases, the break is a long one
+        sessions_before_long_break: i64 = 4, "timer_sessions_before_long_break",
+            SettingKind::Integer { min: 1, max: 100 },
+            Some(reload_timer_settings);
+        /// Pause tracking while a break runs and resume it when work starts
+        auto_pause_tracking: bool = false, "timer_auto_pause_tracking",
+            SettingKind::Boolean,
+            Some(reload_timer_settings);
+    }
+
+    /// When work is expected to happen.
+    WorkSchedule {
+        /// Hours outside of which tracking pauses itself
+        tracking_schedule: TrackingSchedule = TrackingSchedule::default(), "tracking_schedule",
+            SettingKind::Json(|value| parse_json::<TrackingSchedule>(value)?.validate()),
+            Some(|state, value| {
+                if let Ok(schedule) = parse_json(value) {
+                    state.schedule.lock().schedule = schedule;
+                }
+            });
+        work_hours: WorkHoursSettings = WorkHoursSettings::default(), "work_hours",
+            SettingKind::Json(|value| parse_json::<WorkHoursSettings>(value)?.validate()),
+            None;
+    }
+}
+
+pub fn lookup(key: &str) -> Option<&'static SettingDef> {
+    REGISTRY.iter().find(|def| def.key == key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    #[test]
+    fn test_registry_validates_and_falls_back_to_defaults() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+
+        let idle = lookup("idle_threshold_seconds").unwrap();
+        assert_eq!(idle.to_stored(&Value::from(600)).unwrap(), "600");
+        assert!(idle.to_stored(&Value::from(0)).is_err());
+        assert!(idle.to_stored(&Value::from("600")).is_err());
+        assert!(lookup("theme")
+            .unwrap()
+            .to_stored(&Value::from("neon"))
+            .is_err());
+
+        // A bad stored value reads as the default
+        conn.execute(
+            "UPDATE settings SET value = 'soon' WHERE key = 'idle_threshold_seconds'",
+            [],
+        )
+        .unwrap();
+        conn.execute("DELETE FROM settings WHERE key = 'theme'", [])
+            .unwrap();
+        let settings = AppSettings::load(&conn);
+        assert_eq!(settings, AppSettings::default());
+
+        let invalid = AppSettings {
+            idle_threshold_seconds: 0,
+            ..Default::default()
+        };
+        assert!(invalid.values().is_err());
+        assert_eq!(AppSettings::default().values().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_out_of_range_values_fall_back_to_defaults() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value)
+             VALUES ('idle_threshold_seconds', '10'), ('timer_work_minutes', '5000')",
+            [],
+        )
+        .unwrap();
+        assert_eq!(lookup("idle_threshold_seconds").unwrap().load(&conn), Value::from(300));
+        assert_eq!(AppSettings::load(&conn).idle_threshold_seconds, 300);
+        assert_eq!(TimerSettings::load(&conn).work_minutes, 25);
+    }
+
+    #[test]
+    fn test_grouped_settings_round_trip() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+
+        let mut work = WorkSchedule::default();
+        work.tracking_schedule.enabled = true;
+        work.work_hours.day_minutes[4] = 240;
+        let notifications = NotificationSettings {
+            quiet_hours_start: "22:00".to_string(),
+            quiet_hours_end: "07:00".to_string(),
+            ..Default::default()
+        };
+        let tracking = TrackingState {
+            current_project_id: Some(7),
+        };
+        for (key, value) in [work.values(), notifications.values(), tracking.values()]
+            .into_iter()
+            .flat_map(Result::unwrap)
+        {
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)",
+                [key, &value],
+            )
+            .unwrap();
+        }
+        assert_eq!(WorkSchedule::load(&conn), work);
+        assert_eq!(NotificationSettings::load(&conn), notifications);
+        assert_eq!(TrackingState::load(&conn), tracking);
+
+        let schedule = lookup("tracking_schedule").unwrap();
+        assert!(schedule.to_stored(&serde_json::json!({ "enabled": true })).is_err());
+        assert!(lookup("quiet_hours_start")
+            .unwrap()
+            .to_stored(&Value::from("late"))
+            .is_err());
+        assert_eq!(
+            lookup("current_project_id").unwrap().to_stored(&Value::Null).unwrap(),
+            ""
+        );
+    }
+}
@@ -1,25 +1,74 @@
-use parking_lot::RwLock;
-use std::sync::atomic::{AtomicBool, Ordering};
+use parking_lot::{Mutex, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
-use crate::db::{Database, TrackingStatus, WindowEvent};
+use crate::db::{ActiveActivity, Database, SessionReason, TrackingStatus, WindowEvent};
+use crate::schedule::ScheduleState;
+use crate::sessions;
+use crate::settings::{self, AppSettings, TimerSettings, TrackingState, WorkSchedule};
+use crate::timer::PomodoroTimer;
+use rusqlite::Connection;
+use serde_json::Value;
+
+const TRACKING_ENABLED_KEY: &str = "tracking_enabled";
+const CURRENT_PROJECT_KEY: &str = "current_project_id";
+
+/// The project selected when the previous run ended, unless it has been
+/// deleted or archived since.
+fn load_current_project(conn: &Connection) -> Option<i64> {
+    TrackingState::load(conn)
+        .current_project_id
+        .filter(|id| {
+            conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM projects WHERE id = ? AND is_active = 1)",
+                [id],
+                |row| row.get(0),
+            )
+            .unwrap_or(false)
+        })
+}
 
 pub struct AppState {
     pub db: Database,
     pub tracking_enabled: AtomicBool,
     pub is_idle: AtomicBool,
+    pub idle_threshold_seconds: AtomicU64,
     pub current_window: RwLock<Option<WindowEvent>>,
     pub session_start: RwLock<Option<i64>>,
+    pub session_id: RwLock<Option<i64>>,
+    pub active_activity: RwLock<Option<ActiveActivity>>,
+    pub timer: Mutex<PomodoroTimer>,
+    pub focus_session: RwLock<Option<i64>>,
+    pub current_project_id: RwLock<Option<i64>>,
+    pub schedule: Mutex<ScheduleState>,
+    pub tag_rules_stale: AtomicBool,
 }
 
 impl AppState {
     pub fn new(db: Database) -> Self {
-        Self {
+        let timer_settings = TimerSettings::load(&db.conn());
+        let schedule = WorkSchedule::load(&db.conn()).tracking_schedule;
+        let settings = AppSettings::load(&db.conn());
+        let project_id = load_current_project(&db.conn());
+        let state = Self {
             db,
-            tracking_enabled: AtomicBool::new(true),
+            tracking_enabled: AtomicBool::new(settings.tracking_enabled),
             is_idle: AtomicBool::new(false),
+            idle_threshold_seconds: AtomicU64::new(settings.idle_threshold_seconds.max(0) as u64),
             current_window: RwLock::new(None),
             session_start: RwLock::new(None),
+            session_id: RwLock::new(None),
+            active_activity: RwLock::new(None),
+            timer: Mutex::new(PomodoroTimer::new(timer_settings)),
+            focus_session: RwLock::new(None),
+            current_project_id: RwLock::new(project_id),
+            schedule: Mutex::new(ScheduleState::new(schedule)),
+            tag_rules_stale: AtomicBool::new(false),
+        };
+        // Tracking that was on when the app quit carries on in a new session
+        if settings.tracking_enabled {
+            state.begin_session(SessionReason::Startup);
         }
+        state
     }
 
     pub fn is_tracking(&self) -> bool {
@@ -30,6 +79,88 @@ impl AppState {
         self.tracking_enabled.store(enabled, Ordering::SeqCst);
     }
 
+    /// Turn tracking on and start a new session.
+    pub fn start_session(&self, reason: SessionReason) {
+        self.set_tracking(true);
+        self.begin_session(reason);
+        log::info!("Tracking started ({})", reason.as_str());
+    }
+
+    pub fn stop_session(&self, reason: SessionReason) {
+        self.set_tracking(false);
+        self.end_session(reason);
+        log::info!("Tracking stopped ({})", reason.as_str());
+    }
+
+    /// Open a session unless one is already open. Tracking stays as it is,
+    /// so idle and suspend can split sessions without pausing.
+    pub fn begin_session(&self, reason: SessionReason) {
+        let conn = self.db.conn();
+        let mut session_id = self.session_id.write();
+        if session_id.is_some() {
+            return;
+        }
+        let now = chrono::Utc::now().timestamp();
+        match sessions::start(&conn, reason, now) {
+            Ok(id) => *session_id = Some(id),
+            Err(e) => log::error!("Failed to start session: {}", e),
+        }
+        self.set_session_start(Some(now));
+    }
+
+    pub fn end_session(&self, reason: SessionReason) {
+        self.end_session_at(reason, chrono::Utc::now().timestamp());
+    }
+
+    /// Close the open session as of `at`, for stretches noticed late such
+    /// as a suspend.
+    pub fn end_session_at(&self, reason: SessionReason, at: i64) {
+        let conn = self.db.conn();
+        let session_id = self.session_id.write().take();
+        if let Some(id) = session_id {
+            if let Err(e) = sessions::end(&conn, id, reason, at) {
+                log::error!("Failed to end session: {}", e);
+            }
+        }
+        self.set_session_start(None);
+    }
+
+    pub fn get_session_id(&self) -> Option<i64> {
+        *self.session_id.read()
+    }
+
+    /// Have the tracking loop reload its tag rules before saving more records.
+    pub fn tag_rules_changed(&self) {
+        self.tag_rules_stale.store(true, Ordering::SeqCst);
+    }
+
+    pub fn take_tag_rules_changed(&self) -> bool {
+        self.tag_rules_stale.swap(false, Ordering::SeqCst)
+    }
+
+    /// Store whether tracking is on and the current project so the next
+    /// launch picks up where this one left off. Only changes the user makes
+    /// are saved; pauses from the schedule or the focus timer are worked out
+    /// again on startup.
+    pub fn save_tracking_state(&self) -> Result<(), String> {
+        let conn = self.db.conn();
+        let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+        let values = [
+            (TRACKING_ENABLED_KEY, Value::from(self.is_tracking())),
+            (CURRENT_PROJECT_KEY, Value::from(self.get_current_project())),
+        ];
+        for (key, value) in values {
+            let def = settings::lookup(key).ok_or("Unknown setting")?;
+            tx.execute(
+                "INSERT OR REPLACE INTO settings (key, value, updated_at)
+                 VALUES (?, ?, strftime('%s', 'now'))",
+                [key, &def.to_stored(&value)?],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        tx.commit().map_err(|e| e.to_string())
+    }
+
     pub fn is_idle(&self) -> bool {
         self.is_idle.load(Ordering::SeqCst)
     }
@@ -38,6 +169,14 @@ impl AppState {
         self.is_idle.store(idle, Ordering::SeqCst);
     }
 
+    pub fn idle_threshold(&self) -> u64 {
+        self.idle_threshold_seconds.load(Ordering::SeqCst)
+    }
+
+    pub fn set_idle_threshold(&self, seconds: u64) {
+        self.idle_threshold_seconds.store(seconds, Ordering::SeqCst);
+    }
+
     pub fn get_current_window(&self) -> Option<WindowEvent> {
         self.current_window.read().clone()
     }
@@ -54,6 +193,49 @@ impl AppState {
         *self.session_start.write() = start;
     }
 
+    /// Project new activity is attributed to.
+    pub fn get_current_project(&self) -> Option<i64> {
+        *self.current_project_id.read()
+    }
+
+    pub fn set_current_project(&self, project_id: Option<i64>) {
+        *self.current_project_id.write() = project_id;
+    }
+
+    pub fn get_focus_session(&self) -> Option<i64> {
+        *self.focus_session.read()
+    }
+
+    pub fn set_focus_session(&self, session_id: Option<i64>) {
+        *self.focus_session.write() = session_id;
+    }
+
+    pub fn get_active_activity(&self) -> Option<ActiveActivity> {
+        *self.active_activity.read()
+    }
+
+    pub fn set_active_activity(&self, activity: Option<ActiveActivity>) {
+        *self.active_activity.write() = activity;
+    }
+
+    /// The activity the recorder is timing, if it should count towards totals
+    /// right now.
+    pub fn live_activity(&self) -> Option<ActiveActivity> {
+        if !self.is_tracking() || self.is_idle() {
+            return None;
+        }
+        *self.active_activity.read()
+    }
+
+    /// Today's recorded time plus the activity still being timed, which is
+    /// only written once the window changes.
+    pub fn today_seconds_with_live(&self) -> i64 {
+        let live_seconds = self
+            .live_activity()
+            .map_or(0, |a| chrono::Utc::now().timestamp() - a.start_time);
+        self.get_today_total_seconds() + live_seconds
+    }
+
     pub fn get_tracking_status(&self) -> TrackingStatus {
         let current = self.get_current_window();
         TrackingStatus {
@@ -63,6 +245,7 @@ impl AppState {
             current_window: current.as_ref().map(|w| w.window_title.clone()),
             today_total_seconds: self.get_today_total_seconds(),
             session_start_time: self.get_session_start(),
+            current_project_id: self.get_current_project(),
         }
     }
 
@@ -79,3 +262,40 @@ impl AppState {
         .unwrap_or(0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    #[test]
+    fn test_tracking_state_survives_restart() {
+        let (db, _temp_dir) = test_db();
+        db.conn()
+            .execute(
+                "INSERT INTO projects (id, name) VALUES (100, 'Restart Test')",
+                [],
+            )
+            .unwrap();
+
+        let state = AppState::new(db.clone());
+        assert!(state.is_tracking());
+        assert!(state.get_session_start().is_some());
+
+        let first = state.get_session_id();
+        state.set_current_project(Some(100));
+        state.end_session(SessionReason::Quit);
+        state.save_tracking_state().unwrap();
+        let restored = AppState::new(db.clone());
+        assert!(restored.get_session_id().is_some());
+        assert_ne!(restored.get_session_id(), first);
+        assert_eq!(restored.get_current_project(), Some(100));
+
+        restored.stop_session(SessionReason::User);
+        restored.save_tracking_state().unwrap();
+        let stopped = AppState::new(db.clone());
+        assert!(!stopped.is_tracking());
+        assert_eq!(stopped.get_session_start(), None);
+        assert_eq!(stopped.get_current_project(), Some(100));
+    }
+}
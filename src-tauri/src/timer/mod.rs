@@ -0,0 +1,378 @@
+use crate::db::{SessionReason, TimerPhase, TimerSession, TimerState, TimerStatus};
+use crate::state::AppState;
+use rusqlite::Connection;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+pub use crate::settings::TimerSettings;
+
+impl TimerSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, minutes) in [
+            ("Work", self.work_minutes),
+            ("Short break", self.short_break_minutes),
+            ("Long break", self.long_break_minutes),
+        ] {
+            if !(1..=24 * 60).contains(&minutes) {
+                return Err(format!("{} minutes must be between 1 and 1440", name));
+            }
+        }
+        if self.sessions_before_long_break < 1 {
+            return Err("Sessions before a long break must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn duration_seconds(&self, phase: TimerPhase) -> i64 {
+        60 * match phase {
+            TimerPhase::Work => self.work_minutes,
+            TimerPhase::ShortBreak => self.short_break_minutes,
+            TimerPhase::LongBreak => self.long_break_minutes,
+        }
+    }
+}
+
+/// A phase that has ended, by running out or being skipped or stopped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FinishedPhase {
+    pub phase: TimerPhase,
+    pub started_at: i64,
+    pub ended_at: i64,
+    pub planned_seconds: i64,
+    pub actual_seconds: i64,
+    pub completed: bool,
+}
+
+/// The outcome of a phase ending: what finished and what runs next, if the
+/// timer keeps going.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    pub finished: FinishedPhase,
+    pub next: Option<TimerPhase>,
+}
+
+/// Work/break cycle timer. Time is passed in explicitly so the state machine
+/// can be driven by the tracking loop and tested without sleeping.
+#[derive(Debug)]
+pub struct PomodoroTimer {
+    settings: TimerSettings,
+    state: TimerState,
+    phase: TimerPhase,
+    phase_started_at: Option<i64>,
+    duration: i64,
+    /// Running time accumulated before the latest pause
+    elapsed_before_pause: i64,
+    resumed_at: Option<i64>,
+    completed_work: i64,
+    /// Set when a break paused tracking, so work can resume it
+    pub paused_tracking: bool,
+}
+
+impl PomodoroTimer {
+    pub fn new(settings: TimerSettings) -> Self {
+        let duration = settings.duration_seconds(TimerPhase::Work);
+        Self {
+            settings,
+            state: TimerState::Stopped,
+            phase: TimerPhase::Work,
+            phase_started_at: None,
+            duration,
+            elapsed_before_pause: 0,
+            resumed_at: None,
+            completed_work: 0,
+            paused_tracking: false,
+        }
+    }
+
+    pub fn settings(&self) -> &TimerSettings {
+        &self.settings
+    }
+
+    /// New settings apply from the next phase on.
+    pub fn set_settings(&mut self, settings: TimerSettings) {
+        if self.state == TimerState::Stopped {
+            self.duration = settings.duration_seconds(self.phase);
+        }
+        self.settings = settings;
+    }
+
+    pub fn state(&self) -> TimerState {
+        self.state
+    }
+
+    fn elapsed(&self, now: i64) -> i64 {
+        self.elapsed_before_pause + self.resumed_at.map_or(0, |at| (now - at).max(0))
+    }
+
+    pub fn status(&self, now: i64) -> TimerStatus {
+        TimerStatus {
+            state: self.state,
+            phase: self.phase,
+            phase_started_at: self.phase_started_at,
+            duration_seconds: self.duration,
+            remaining_seconds: (self.duration - self.elapsed(now)).max(0),
+            completed_work_sessions: self.completed_work,
+        }
+    }
+
+    /// Start a fresh cycle with a work phase.
+    pub fn start(&mut self, now: i64) -> Result<(), String> {
+        if self.state != TimerState::Stopped {
+            return Err("Timer is already running".to_string());
+        }
+        self.completed_work = 0;
+        self.begin(TimerPhase::Work, now);
+        Ok(())
+    }
+
+    pub fn pause(&mut self, now: i64) -> Result<(), String> {
+        if self.state != TimerState::Running {
+            return Err("Timer is not running".to_string());
+        }
+        self.elapsed_before_pause = self.elapsed(now);
+        self.resumed_at = None;
+        self.state = TimerState::Paused;
+        Ok(())
+    }
+
+    pub fn resume(&mut self, now: i64) -> Result<(), String> {
+        if self.state != TimerState::Paused {
+            return Err("Timer is not paused".to_string());
+        }
+        self.resumed_at = Some(now);
+        self.state = TimerState::Running;
+        Ok(())
+    }
+
+    /// End the current phase early and move on to the next one.
+    pub fn skip(&mut self, now: i64) -> Result<Transition, String> {
+        if self.state == TimerState::Stopped {
+            return Err("Timer is not running".to_string());
+        }
+        Ok(self.advance(now, false))
+    }
+
+    /// End the current phase early and stop the cycle.
+    pub fn stop(&mut self, now: i64) -> Result<Transition, String> {
+        if self.state == TimerState::Stopped {
+            return Err("Timer is not running".to_string());
+        }
+        let finished = self.finish(now, false);
+        self.state = TimerState::Stopped;
+        self.phase = TimerPhase::Work;
+        self.phase_started_at = None;
+        self.duration = self.settings.duration_seconds(TimerPhase::Work);
+        self.elapsed_before_pause = 0;
+        self.resumed_at = None;
+        Ok(Transition {
+            finished,
+            next: None,
+        })
+    }
+
+    /// Advance to the next phase once the current one has run out.
+    pub fn tick(&mut self, now: i64) -> Option<Transition> {
+        (self.state == TimerState::Running && self.elapsed(now) >= self.duration)
+            .then(|| self.advance(now, true))
+    }
+
+    fn begin(&mut self, phase: TimerPhase, now: i64) {
+        self.phase = phase;
+        self.phase_started_at = Some(now);
+        self.duration = self.settings.duration_seconds(phase);
+        self.elapsed_before_pause = 0;
+        self.resumed_at = Some(now);
+        self.state = TimerState::Running;
+    }
+
+    fn finish(&mut self, now: i64, completed: bool) -> FinishedPhase {
+        let actual = self.elapsed(now).min(self.duration);
+        FinishedPhase {
+            phase: self.phase,
+            started_at: self.phase_started_at.unwrap_or(now),
+            ended_at: now,
+            planned_seconds: self.duration,
+            actual_seconds: actual,
+            completed,
+        }
+    }
+
+    fn advance(&mut self, now: i64, completed: bool) -> Transition {
+        let finished = self.finish(now, completed);
+        let next = match self.phase {
+            TimerPhase::Work => {
+                if completed {
+                    self.completed_work += 1;
+                }
+                if completed && self.completed_work % self.settings.sessions_before_long_break == 0
+                {
+                    TimerPhase::LongBreak
+                } else {
+                    TimerPhase::ShortBreak
+                }
+            }
+            TimerPhase::ShortBreak | TimerPhase::LongBreak => TimerPhase::Work,
+        };
+        self.begin(next, now);
+        Transition {
+            finished,
+            next: Some(next),
+        }
+    }
+}
+
+pub fn save_session(conn: &Connection, finished: &FinishedPhase) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO timer_sessions
+            (phase, started_at, ended_at, planned_seconds, actual_seconds, completed)
+         VALUES (?, ?, ?, ?, ?, ?)",
+        rusqlite::params![
+            finished.phase.as_str(),
+            finished.started_at,
+            finished.ended_at,
+            finished.planned_seconds,
+            finished.actual_seconds,
+            finished.completed,
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn list_sessions(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+) -> rusqlite::Result<Vec<TimerSession>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, phase, started_at, ended_at, planned_seconds, actual_seconds, completed
+         FROM timer_sessions
+         WHERE date(started_at, 'unixepoch', 'localtime') BETWEEN ? AND ?
+         ORDER BY started_at",
+    )?;
+    let sessions = stmt
+        .query_map([start_date, end_date], |row| {
+            Ok(TimerSession {
+                id: row.get(0)?,
+                phase: TimerPhase::parse(&row.get::<_, String>(1)?),
+                started_at: row.get(2)?,
+                ended_at: row.get(3)?,
+                planned_seconds: row.get(4)?,
+                actual_seconds: row.get(5)?,
+                completed: row.get::<_, i32>(6)? == 1,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(sessions)
+}
+
+/// Record a finished phase, pause or resume tracking around breaks when
+/// configured, and emit `timer:phase` with the new status.
+///
+/// Returns whether tracking was paused or resumed. The tray then needs a
+/// refresh, which callers must do after releasing the timer lock.
+#[must_use]
+pub fn apply_transition<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &AppState,
+    timer: &mut PomodoroTimer,
+    transition: &Transition,
+    now: i64,
+) -> bool {
+    let mut tracking_changed = false;
+    if let Err(e) = save_session(&state.db.conn(), &transition.finished) {
+        log::error!("Failed to save timer session: {}", e);
+    }
+
+    match transition.next {
+        Some(phase) if phase.is_break() => {
+            if timer.settings().auto_pause_tracking && state.is_tracking() {
+                log::info!("Pausing tracking for a break");
+                state.stop_session(SessionReason::Break);
+                timer.paused_tracking = true;
+                tracking_changed = true;
+            }
+        }
+        _ => {
+            if timer.paused_tracking {
+                timer.paused_tracking = false;
+                // The schedule only acts when its hours start or end, so if
+                // they ended during the break it takes over the pause instead
+                let local = chrono::Local::now().naive_local();
+                let mut schedule = state.schedule.lock();
+                if schedule.status(local, now).tracking_allowed {
+                    log::info!("Resuming tracking after a break");
+                    state.start_session(SessionReason::Break);
+                    tracking_changed = true;
+                } else {
+                    log::info!("Break ended outside scheduled hours, tracking stays paused");
+                    schedule.paused_tracking = true;
+                }
+            }
+        }
+    }
+
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.emit("timer:phase", timer.status(now));
+    }
+    tracking_changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> TimerSettings {
+        TimerSettings {
+            work_minutes: 10,
+            short_break_minutes: 2,
+            long_break_minutes: 5,
+            sessions_before_long_break: 2,
+            auto_pause_tracking: true,
+        }
+    }
+
+    #[test]
+    fn test_cycle_with_long_break() {
+        let mut timer = PomodoroTimer::new(settings());
+        timer.start(0).unwrap();
+        assert!(timer.tick(599).is_none());
+
+        let t = timer.tick(600).unwrap();
+        assert!(t.finished.completed);
+        assert_eq!(t.finished.actual_seconds, 600);
+        assert_eq!(t.next, Some(TimerPhase::ShortBreak));
+
+        let t = timer.tick(720).unwrap();
+        assert_eq!(t.next, Some(TimerPhase::Work));
+        let t = timer.tick(1320).unwrap();
+        assert_eq!(t.next, Some(TimerPhase::LongBreak));
+        assert_eq!(timer.status(1320).completed_work_sessions, 2);
+        assert_eq!(timer.status(1320).remaining_seconds, 300);
+    }
+
+    #[test]
+    fn test_pause_resume_and_skip() {
+        let mut timer = PomodoroTimer::new(settings());
+        timer.start(0).unwrap();
+        timer.pause(100).unwrap();
+        assert!(timer.pause(150).is_err());
+        // Paused time does not count
+        assert!(timer.tick(10_000).is_none());
+        assert_eq!(timer.status(10_000).remaining_seconds, 500);
+
+        timer.resume(10_000).unwrap();
+        assert!(timer.tick(10_499).is_none());
+
+        let t = timer.skip(10_200).unwrap();
+        assert!(!t.finished.completed);
+        assert_eq!(t.finished.actual_seconds, 300);
+        // A skipped work phase does not count towards a long break
+        assert_eq!(t.next, Some(TimerPhase::ShortBreak));
+        assert_eq!(timer.status(10_200).completed_work_sessions, 0);
+
+        let t = timer.stop(10_260).unwrap();
+        assert_eq!(t.finished.phase, TimerPhase::ShortBreak);
+        assert_eq!(t.next, None);
+        assert_eq!(timer.state(), TimerState::Stopped);
+        assert!(timer.skip(10_300).is_err());
+    }
+}
@@ -0,0 +1,294 @@
+use crate::db::{Timesheet, TimesheetRow, TimesheetStatus};
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use rusqlite::{Connection, OptionalExtension};
+use thiserror::Error;
+
+pub const DAYS_PER_WEEK: usize = 7;
+
+#[derive(Debug, Error)]
+pub enum TimesheetError {
+    #[error("database error: {0}")]
+    Database(#[from] rusqlite::Error),
+    #[error("invalid timesheet snapshot: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid week start '{0}', expected YYYY-MM-DD")]
+    InvalidWeek(String),
+    #[error("week start {0} is not a Monday")]
+    NotMonday(String),
+    #[error("timesheet locked: the week of {0} has been submitted")]
+    Locked(String),
+    #[error("timesheet for week of {0} is already submitted")]
+    AlreadySubmitted(String),
+    #[error("timesheet for week of {0} is not submitted")]
+    NotSubmitted(String),
+}
+
+/// Parse a week's first day, which must be a Monday.
+pub fn parse_week(week_start: &str) -> Result<NaiveDate, TimesheetError> {
+    let date = NaiveDate::parse_from_str(week_start, "%Y-%m-%d")
+        .map_err(|_| TimesheetError::InvalidWeek(week_start.to_string()))?;
+    if date.weekday() != Weekday::Mon {
+        return Err(TimesheetError::NotMonday(week_start.to_string()));
+    }
+    Ok(date)
+}
+
+/// Fail if a record spanning `start_time`..`end_time` touches a submitted
+/// week. The triggers reject such writes anyway; this names the week.
+pub fn check_unlocked(
+    conn: &Connection,
+    start_time: i64,
+    end_time: i64,
+) -> Result<(), TimesheetError> {
+    let locked: Option<String> = conn
+        .query_row(
+            "SELECT week_start FROM timesheets
+             WHERE status = 'submitted'
+               AND (date(?1, 'unixepoch', 'localtime')
+                        BETWEEN week_start AND date(week_start, '+6 days')
+                    OR date(?2 - 1, 'unixepoch', 'localtime')
+                        BETWEEN week_start AND date(week_start, '+6 days'))",
+            [start_time, end_time],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    match locked {
+        Some(week_start) => Err(TimesheetError::Locked(week_start)),
+        None => Ok(()),
+    }
+}
+
+/// Project × day grid of non-idle time for the seven days from `week_start`,
+/// attributing each record to the local day it started on.
+pub fn build_grid(conn: &Connection, week_start: NaiveDate) -> Result<Timesheet, TimesheetError> {
+    let days: Vec<String> = (0..DAYS_PER_WEEK as i64)
+        .map(|i| (week_start + Duration::days(i)).to_string())
+        .collect();
+
+    let mut stmt = conn.prepare(
+        "SELECT ar.project_id, COALESCE(p.name, 'No project'), COALESCE(p.color, '#9CA3AF'),
+                date(ar.start_time, 'unixepoch', 'localtime') AS day,
+                SUM(ar.duration_seconds)
+         FROM activity_records ar
+         LEFT JOIN projects p ON ar.project_id = p.id
+         WHERE ar.is_idle = 0 AND day BETWEEN ?1 AND ?2
+         GROUP BY ar.project_id, day
+         ORDER BY p.name IS NULL, p.name, ar.project_id",
+    )?;
+    let cells = stmt
+        .query_map([&days[0], &days[DAYS_PER_WEEK - 1]], |row| {
+            Ok((
+                row.get::<_, Option<i64>>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, i64>(4)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut rows: Vec<TimesheetRow> = Vec::new();
+    for (project_id, project_name, color, day, seconds) in cells {
+        let Some(index) = days.iter().position(|d| *d == day) else {
+            continue;
+        };
+        if rows.last().map(|r| r.project_id) != Some(project_id) {
+            rows.push(TimesheetRow {
+                project_id,
+                project_name,
+                color,
+                day_seconds: vec![0; DAYS_PER_WEEK],
+                total_seconds: 0,
+            });
+        }
+        let row = rows.last_mut().expect("row was just pushed");
+        row.day_seconds[index] += seconds;
+        row.total_seconds += seconds;
+    }
+
+    let day_totals: Vec<i64> = (0..DAYS_PER_WEEK)
+        .map(|i| rows.iter().map(|r| r.day_seconds[i]).sum())
+        .collect();
+
+    Ok(Timesheet {
+        week_start: days[0].clone(),
+        days,
+        total_seconds: day_totals.iter().sum(),
+        rows,
+        day_totals,
+        status: TimesheetStatus::Open,
+        submitted_at: None,
+        unlocked_at: None,
+        unlock_reason: None,
+    })
+}
+
+/// The week's timesheet: the frozen snapshot once submitted, live data otherwise.
+pub fn load(conn: &Connection, week_start: NaiveDate) -> Result<Timesheet, TimesheetError> {
+    let stored = conn
+        .query_row(
+            "SELECT status, snapshot_json, submitted_at, unlocked_at, unlock_reason
+             FROM timesheets WHERE week_start = ?",
+            [week_start.to_string()],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, Option<i64>>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                ))
+            },
+        )
+        .optional()?;
+
+    let Some((status, snapshot, submitted_at, unlocked_at, unlock_reason)) = stored else {
+        return build_grid(conn, week_start);
+    };
+
+    let mut timesheet = if status == "submitted" {
+        serde_json::from_str::<Timesheet>(&snapshot)?
+    } else {
+        build_grid(conn, week_start)?
+    };
+    timesheet.status = if status == "submitted" {
+        TimesheetStatus::Submitted
+    } else {
+        TimesheetStatus::Unlocked
+    };
+    timesheet.submitted_at = Some(submitted_at);
+    timesheet.unlocked_at = unlocked_at;
+    timesheet.unlock_reason = unlock_reason;
+
+    Ok(timesheet)
+}
+
+/// Snapshot the week and lock its records against further writes.
+pub fn submit(conn: &Connection, week_start: NaiveDate) -> Result<Timesheet, TimesheetError> {
+    if load(conn, week_start)?.status == TimesheetStatus::Submitted {
+        return Err(TimesheetError::AlreadySubmitted(week_start.to_string()));
+    }
+
+    let snapshot = build_grid(conn, week_start)?;
+    conn.execute(
+        "INSERT INTO timesheets (week_start, status, snapshot_json, submitted_at)
+         VALUES (?1, 'submitted', ?2, strftime('%s', 'now'))
+         ON CONFLICT (week_start) DO UPDATE SET
+             status = 'submitted',
+             snapshot_json = excluded.snapshot_json,
+             submitted_at = excluded.submitted_at,
+             unlocked_at = NULL,
+             unlock_reason = NULL",
+        rusqlite::params![week_start.to_string(), serde_json::to_string(&snapshot)?],
+    )?;
+
+    load(conn, week_start)
+}
+
+/// Reopen a submitted week for edits. The snapshot is kept for reference.
+pub fn unlock(
+    conn: &Connection,
+    week_start: NaiveDate,
+    reason: &str,
+) -> Result<Timesheet, TimesheetError> {
+    let updated = conn.execute(
+        "UPDATE timesheets
+         SET status = 'unlocked', unlocked_at = strftime('%s', 'now'), unlock_reason = ?
+         WHERE week_start = ? AND status = 'submitted'",
+        rusqlite::params![reason, week_start.to_string()],
+    )?;
+    if updated == 0 {
+        return Err(TimesheetError::NotSubmitted(week_start.to_string()));
+    }
+
+    load(conn, week_start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    fn insert(
+        conn: &Connection,
+        local_start: &str,
+        minutes: i64,
+        project_id: Option<i64>,
+    ) -> rusqlite::Result<usize> {
+        conn.execute(
+            "INSERT INTO activity_records (window_title, start_time, end_time, project_id)
+             VALUES ('work', strftime('%s', ?1, 'utc'), strftime('%s', ?1, 'utc') + ?2, ?3)",
+            rusqlite::params![local_start, minutes * 60, project_id],
+        )
+    }
+
+    #[test]
+    fn test_grid_and_lock() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+        conn.execute("INSERT INTO projects (id, name) VALUES (1, 'Website')", [])
+            .expect("Failed to insert project");
+
+        insert(&conn, "2024-03-04 09:00:00", 60, Some(1)).unwrap();
+        insert(&conn, "2024-03-04 13:00:00", 30, Some(1)).unwrap();
+        insert(&conn, "2024-03-06 10:00:00", 45, None).unwrap();
+        insert(&conn, "2024-03-11 10:00:00", 15, Some(1)).unwrap();
+
+        assert!(matches!(parse_week("2024-03-05"), Err(TimesheetError::NotMonday(_))));
+        let week = parse_week("2024-03-04").unwrap();
+        let grid = build_grid(&conn, week).expect("Failed to build grid");
+        assert_eq!(grid.days[6], "2024-03-10");
+        assert_eq!(grid.rows.len(), 2);
+        assert_eq!(grid.rows[0].project_name, "Website");
+        assert_eq!(grid.rows[0].day_seconds[0], 5400);
+        assert_eq!(grid.rows[1].day_seconds[2], 2700);
+        assert_eq!(grid.total_seconds, 8100);
+
+        let submitted = submit(&conn, week).expect("Failed to submit");
+        assert_eq!(submitted.status, TimesheetStatus::Submitted);
+        assert!(matches!(
+            submit(&conn, week),
+            Err(TimesheetError::AlreadySubmitted(_))
+        ));
+
+        // Writes inside the week are rejected, outside it they are not
+        let locked = insert(&conn, "2024-03-05 09:00:00", 10, None).unwrap_err();
+        assert!(locked.to_string().contains("timesheet locked"));
+        assert!(conn
+            .execute(
+                "UPDATE activity_records SET notes = 'x' WHERE project_id = 1",
+                []
+            )
+            .is_err());
+        // Reassigning or deleting its project changes the submitted grid
+        assert!(conn
+            .execute("UPDATE activity_records SET project_id = NULL WHERE project_id = 1", [])
+            .is_err());
+        assert!(conn.execute("DELETE FROM projects WHERE id = 1", []).is_err());
+        assert!(conn
+            .execute("DELETE FROM activity_records WHERE project_id = 1", [])
+            .is_err());
+        let local = |time: &str| {
+            conn.query_row("SELECT strftime('%s', ?, 'utc')", [time], |row| {
+                row.get::<_, String>(0)
+            })
+            .unwrap()
+            .parse::<i64>()
+            .unwrap()
+        };
+        assert!(matches!(
+            check_unlocked(&conn, local("2024-03-04 09:00:00"), local("2024-03-04 10:00:00")),
+            Err(TimesheetError::Locked(_))
+        ));
+        assert!(check_unlocked(&conn, local("2024-03-11 09:00:00"), local("2024-03-11 10:00:00"))
+            .is_ok());
+        insert(&conn, "2024-03-12 09:00:00", 10, None).unwrap();
+
+        let unlocked = unlock(&conn, week, "Forgot a meeting").expect("Failed to unlock");
+        assert_eq!(unlocked.status, TimesheetStatus::Unlocked);
+        assert_eq!(unlocked.unlock_reason.as_deref(), Some("Forgot a meeting"));
+        insert(&conn, "2024-03-05 09:00:00", 10, None).unwrap();
+        assert_eq!(load(&conn, week).unwrap().total_seconds, 8700);
+    }
+}
@@ -1,5 +1,7 @@
 pub mod detector;
 pub mod recorder;
+pub mod tagger;
 
 pub use detector::WindowDetector;
 pub use recorder::ActivityRecorder;
+pub use tagger::TagRules;
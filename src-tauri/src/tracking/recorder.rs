@@ -1,9 +1,13 @@
-use crate::db::{Database, WindowEvent};
+use super::TagRules;
+use crate::db::{ActiveActivity, Database, IdlePeriod, WindowEvent};
 use chrono::Utc;
 
 pub struct ActivityRecorder {
     db: Database,
     current_activity: Option<CurrentActivity>,
+    project_id: Option<i64>,
+    session_id: Option<i64>,
+    tag_rules: TagRules,
 }
 
 struct CurrentActivity {
@@ -16,13 +20,47 @@ struct CurrentActivity {
 
 impl ActivityRecorder {
     pub fn new(db: Database) -> Self {
-        Self {
+        let mut recorder = Self {
             db,
             current_activity: None,
+            project_id: None,
+            session_id: None,
+            tag_rules: TagRules::default(),
+        };
+        recorder.reload_tag_rules();
+        recorder
+    }
+
+    /// Read the tag rules again after they changed. On failure the rules
+    /// loaded before are kept.
+    pub fn reload_tag_rules(&mut self) {
+        match TagRules::load(&self.db.conn()) {
+            Ok(rules) => self.tag_rules = rules,
+            Err(e) => log::error!("Failed to load tag rules: {}", e),
+        }
+    }
+
+    /// Attribute new activity to `project_id`, closing the activity timed so
+    /// far under the previous project.
+    pub fn set_project(&mut self, project_id: Option<i64>) {
+        if project_id != self.project_id {
+            self.flush_current();
+            self.project_id = project_id;
+        }
+    }
+
+    /// Link new activity to `session_id`, closing the activity timed so far
+    /// under the previous session.
+    pub fn set_session(&mut self, session_id: Option<i64>) {
+        if session_id != self.session_id {
+            self.flush_current();
+            self.session_id = session_id;
         }
     }
 
-    pub fn record_window_change(&mut self, window: &WindowEvent) {
+    /// Returns whether the window differs from the one being timed, which
+    /// starts a new activity.
+    pub fn record_window_change(&mut self, window: &WindowEvent) -> bool {
         let now = Utc::now().timestamp();
 
         // Check if this is a different window
@@ -51,15 +89,52 @@ impl ActivityRecorder {
                 application_id: app_id,
             });
         }
+
+        is_different
+    }
+
+    pub fn in_progress(&self) -> Option<ActiveActivity> {
+        self.current_activity.as_ref().map(|a| ActiveActivity {
+            application_id: a.application_id,
+            start_time: a.start_time,
+        })
     }
 
     pub fn flush_current(&mut self) {
+        self.flush_at(Utc::now().timestamp());
+    }
+
+    /// Close the current activity as of `end_time`, e.g. when the machine
+    /// went to sleep.
+    pub fn flush_at(&mut self, end_time: i64) {
         if let Some(activity) = self.current_activity.take() {
-            let now = Utc::now().timestamp();
-            self.save_activity(&activity, now);
+            self.save_activity(&activity, end_time);
         }
     }
 
+    /// Store an idle stretch awaiting a keep or discard decision.
+    pub fn save_idle_period(&self, start_time: i64, end_time: i64) -> Option<IdlePeriod> {
+        let conn = self.db.conn();
+        let result = conn.execute(
+            "INSERT INTO idle_periods (start_time, end_time, duration_seconds)
+             VALUES (?1, ?2, ?2 - ?1)",
+            [start_time, end_time],
+        );
+        if let Err(e) = result {
+            log::error!("Failed to save idle period: {}", e);
+            return None;
+        }
+
+        Some(IdlePeriod {
+            id: conn.last_insert_rowid(),
+            start_time,
+            end_time: Some(end_time),
+            duration_seconds: Some(end_time - start_time),
+            disposition: None,
+            notes: None,
+        })
+    }
+
     fn save_activity(&self, activity: &CurrentActivity, end_time: i64) {
         // Don't save very short activities (less than 1 second)
         if end_time - activity.start_time < 1 {
@@ -80,19 +155,39 @@ impl ActivityRecorder {
         });
 
         let result = conn.execute(
-            "INSERT INTO activity_records (application_id, window_title, start_time, end_time, category_id, is_idle)
-             VALUES (?, ?, ?, ?, ?, 0)",
+            "INSERT INTO activity_records (application_id, window_title, start_time, end_time, category_id, project_id, session_id, is_idle)
+             VALUES (?, ?, ?, ?, ?, ?, ?, 0)",
             rusqlite::params![
                 activity.application_id,
                 activity.window_title,
                 activity.start_time,
                 end_time,
                 category_id,
+                self.project_id,
+                self.session_id,
             ],
         );
 
         if let Err(e) = result {
-            log::error!("Failed to save activity record: {}", e);
+            // Weeks with a submitted timesheet reject new records by design
+            if e.to_string().contains("timesheet locked") {
+                log::warn!("Discarding activity in a submitted timesheet week");
+            } else {
+                log::error!("Failed to save activity record: {}", e);
+            }
+            return;
+        }
+
+        let activity_id = conn.last_insert_rowid();
+        let tagged = self.tag_rules.apply(
+            &conn,
+            activity_id,
+            Some(&activity.app_name),
+            &activity.window_title,
+            None,
+        );
+        if let Err(e) = tagged {
+            log::error!("Failed to apply tag rules: {}", e);
         }
     }
 
@@ -0,0 +1,129 @@
+use regex::{Regex, RegexBuilder};
+use rusqlite::Connection;
+
+/// Active tag rules with their patterns compiled.
+#[derive(Default)]
+pub struct TagRules {
+    rules: Vec<(String, Regex, i64)>,
+}
+
+impl TagRules {
+    /// Load active rules; rules with invalid patterns are skipped.
+    pub fn load(conn: &Connection) -> Result<Self, rusqlite::Error> {
+        let mut stmt =
+            conn.prepare("SELECT rule_type, pattern, tag_id FROM tag_rules WHERE is_active = 1")?;
+
+        let rules = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .filter_map(|(rule_type, pattern, tag_id)| match compile(&pattern) {
+                Ok(regex) => Some((rule_type, regex, tag_id)),
+                Err(e) => {
+                    log::warn!(
+                        "Skipping tag rule with invalid pattern '{}': {}",
+                        pattern,
+                        e
+                    );
+                    None
+                }
+            })
+            .collect();
+
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Tag ids whose rules match the given activity.
+    pub fn matching(&self, app_name: Option<&str>, title: &str, url: Option<&str>) -> Vec<i64> {
+        let mut tags: Vec<i64> = self
+            .rules
+            .iter()
+            .filter(|(rule_type, regex, _)| {
+                let text = match rule_type.as_str() {
+                    "app_name" => app_name,
+                    "window_title" => Some(title),
+                    "url" => url,
+                    _ => None,
+                };
+                text.is_some_and(|t| regex.is_match(t))
+            })
+            .map(|(_, _, tag_id)| *tag_id)
+            .collect();
+        tags.sort_unstable();
+        tags.dedup();
+        tags
+    }
+
+    /// Tag a stored record with every matching tag. Returns the number of new tags.
+    pub fn apply(
+        &self,
+        conn: &Connection,
+        activity_id: i64,
+        app_name: Option<&str>,
+        title: &str,
+        url: Option<&str>,
+    ) -> Result<usize, rusqlite::Error> {
+        let mut added = 0;
+        for tag_id in self.matching(app_name, title, url) {
+            added += conn.execute(
+                "INSERT OR IGNORE INTO activity_tags (activity_id, tag_id) VALUES (?, ?)",
+                [activity_id, tag_id],
+            )?;
+        }
+        Ok(added)
+    }
+}
+
+/// Compile a rule pattern case-insensitively.
+pub fn compile(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern).case_insensitive(true).build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    #[test]
+    fn test_rules_match_and_apply() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+
+        conn.execute_batch(
+            "INSERT INTO tags (id, name) VALUES (1, 'client-a'), (2, 'review');
+             INSERT INTO tag_rules (rule_type, pattern, tag_id) VALUES
+                 ('window_title', 'acme', 1),
+                 ('url', 'github\\.com/.*/pull', 2),
+                 ('window_title', '(unclosed', 2);
+             INSERT INTO activity_records (id, window_title, start_time, end_time)
+                 VALUES (10, 'ACME roadmap', 0, 60);",
+        )
+        .expect("Failed to seed");
+
+        let rules = TagRules::load(&conn).expect("Failed to load rules");
+        assert_eq!(
+            rules.matching(None, "Acme PR", Some("https://github.com/x/y/pull/3")),
+            vec![1, 2]
+        );
+        assert!(rules.matching(Some("acme"), "Inbox", None).is_empty());
+
+        assert_eq!(
+            rules.apply(&conn, 10, None, "ACME roadmap", None).unwrap(),
+            1
+        );
+        assert_eq!(
+            rules.apply(&conn, 10, None, "ACME roadmap", None).unwrap(),
+            0
+        );
+    }
+}
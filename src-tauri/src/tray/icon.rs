@@ -0,0 +1,105 @@
+use tauri::image::Image;
+
+const BASE_ICON: &[u8] = include_bytes!("../../icons/32x32.png");
+
+/// What the tray icon shows, most specific first: paused beats idle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayIconState {
+    Tracking,
+    Paused,
+    Idle,
+}
+
+impl TrayIconState {
+    pub fn from_status(is_tracking: bool, is_idle: bool) -> Self {
+        match (is_tracking, is_idle) {
+            (false, _) => TrayIconState::Paused,
+            (true, true) => TrayIconState::Idle,
+            (true, false) => TrayIconState::Tracking,
+        }
+    }
+
+    fn badge_color(&self) -> [u8; 3] {
+        match self {
+            TrayIconState::Tracking => [0x22, 0xC5, 0x5E],
+            TrayIconState::Paused => [0x9C, 0xA3, 0xAF],
+            TrayIconState::Idle => [0xF5, 0x9E, 0x0B],
+        }
+    }
+}
+
+/// The app icon with a status dot in the bottom-right corner.
+pub fn render(state: TrayIconState) -> tauri::Result<Image<'static>> {
+    let base = Image::from_bytes(BASE_ICON)?;
+    let (width, height) = (base.width(), base.height());
+    let mut rgba = base.rgba().to_vec();
+    draw_badge(&mut rgba, width, height, state.badge_color());
+    Ok(Image::new_owned(rgba, width, height))
+}
+
+/// Draw a filled dot with a white ring over the bottom-right corner of an
+/// RGBA buffer. Edge pixels are blended by coverage so the dot stays round
+/// at small sizes.
+fn draw_badge(rgba: &mut [u8], width: u32, height: u32, color: [u8; 3]) {
+    let size = width.min(height) as f32;
+    let radius = size * 0.22;
+    let ring = (size * 0.05).max(1.0);
+    let cx = width as f32 - radius - ring;
+    let cy = height as f32 - radius - ring;
+
+    for y in 0..height {
+        for x in 0..width {
+            let dx = x as f32 + 0.5 - cx;
+            let dy = y as f32 + 0.5 - cy;
+            let distance = (dx * dx + dy * dy).sqrt();
+
+            let outer = (radius + ring + 0.5 - distance).clamp(0.0, 1.0);
+            if outer == 0.0 {
+                continue;
+            }
+            let inner = (radius + 0.5 - distance).clamp(0.0, 1.0);
+            let i = ((y * width + x) * 4) as usize;
+            for (c, channel) in color.iter().enumerate() {
+                let dot = *channel as f32 * inner + 255.0 * (1.0 - inner);
+                let blended = dot * outer + rgba[i + c] as f32 * (1.0 - outer);
+                rgba[i + c] = blended.round() as u8;
+            }
+            let alpha = 255.0 * outer + rgba[i + 3] as f32 * (1.0 - outer);
+            rgba[i + 3] = alpha.round() as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_badge_states_render_distinct_icons() {
+        let pixel = |image: &Image, x: u32, y: u32| {
+            let i = ((y * image.width() + x) * 4) as usize;
+            image.rgba()[i..i + 4].to_vec()
+        };
+
+        let tracking = render(TrayIconState::Tracking).unwrap();
+        let paused = render(TrayIconState::Paused).unwrap();
+        let base = Image::from_bytes(BASE_ICON).unwrap();
+        let (w, h) = (tracking.width(), tracking.height());
+        let center = (w - w * 27 / 100, h - h * 27 / 100);
+
+        assert_eq!(
+            pixel(&tracking, center.0, center.1),
+            vec![0x22, 0xC5, 0x5E, 255]
+        );
+        assert_eq!(
+            pixel(&paused, center.0, center.1),
+            vec![0x9C, 0xA3, 0xAF, 255]
+        );
+        // The top-left corner is left untouched
+        assert_eq!(pixel(&tracking, 2, 2), pixel(&base, 2, 2));
+        assert_eq!(
+            TrayIconState::from_status(false, true),
+            TrayIconState::Paused
+        );
+    }
+}
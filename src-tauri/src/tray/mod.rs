@@ -1,30 +1,66 @@
+mod icon;
+
+use crate::db::SessionReason;
+use crate::state::AppState;
+use icon::TrayIconState;
+use parking_lot::Mutex;
 use tauri::{
-    menu::{Menu, MenuItem},
+    menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
     AppHandle, Emitter, Manager, Runtime,
 };
 
+const TRAY_ID: &str = "main";
+const PROJECT_PREFIX: &str = "project:";
+const NO_PROJECT_ID: &str = "project:none";
+
+/// Active projects and the current selection, as shown in the submenu.
+type ProjectChoices = (Vec<(i64, String)>, Option<i64>);
+
+/// Menu items the tracking loop keeps up to date.
+pub struct TrayMenu<R: Runtime> {
+    status_item: MenuItem<R>,
+    app_item: MenuItem<R>,
+    toggle_item: MenuItem<R>,
+    project_menu: Submenu<R>,
+    /// What the submenu was last built from
+    projects_shown: Mutex<Option<ProjectChoices>>,
+    icon_state: Mutex<Option<TrayIconState>>,
+}
+
 pub fn setup_tray<R: Runtime>(app: &AppHandle<R>) -> Result<(), Box<dyn std::error::Error>> {
+    let status_item = MenuItem::with_id(app, "status", "Today: 0m", false, None::<&str>)?;
+    let app_item = MenuItem::with_id(app, "current_app", "No active app", false, None::<&str>)?;
+    let toggle_item = MenuItem::with_id(app, "toggle", "Pause Tracking", true, None::<&str>)?;
+    let project_menu = Submenu::with_id(app, "projects", "Switch Project", true)?;
     let show_item = MenuItem::with_id(app, "show", "Show Dashboard", true, None::<&str>)?;
-    let separator1 = MenuItem::with_id(app, "sep1", "─────────────", false, None::<&str>)?;
-    let start_item = MenuItem::with_id(app, "start", "Start Tracking", true, None::<&str>)?;
-    let pause_item = MenuItem::with_id(app, "pause", "Pause Tracking", true, None::<&str>)?;
-    let separator2 = MenuItem::with_id(app, "sep2", "─────────────", false, None::<&str>)?;
     let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
 
     let menu = Menu::with_items(
         app,
         &[
+            &status_item,
+            &app_item,
+            &PredefinedMenuItem::separator(app)?,
+            &toggle_item,
+            &project_menu,
+            &PredefinedMenuItem::separator(app)?,
             &show_item,
-            &separator1,
-            &start_item,
-            &pause_item,
-            &separator2,
             &quit_item,
         ],
     )?;
 
-    let _tray = TrayIconBuilder::new()
+    app.manage(TrayMenu {
+        status_item,
+        app_item,
+        toggle_item,
+        project_menu,
+        projects_shown: Mutex::new(None),
+        icon_state: Mutex::new(None),
+    });
+
+    let _tray = TrayIconBuilder::with_id(TRAY_ID)
+        .icon(icon::render(TrayIconState::Paused)?)
         .menu(&menu)
         .tooltip("Time Tracker")
         .on_menu_event(move |app, event| match event.id.as_ref() {
@@ -34,20 +70,31 @@ pub fn setup_tray<R: Runtime>(app: &AppHandle<R>) -> Result<(), Box<dyn std::err
                     let _ = window.set_focus();
                 }
             }
-            "start" => {
-                if let Some(window) = app.get_webview_window("main") {
-                    let _ = window.emit("tracking:start", ());
-                }
-            }
-            "pause" => {
-                if let Some(window) = app.get_webview_window("main") {
-                    let _ = window.emit("tracking:pause", ());
+            "toggle" => {
+                let state = app.state::<AppState>();
+                if state.is_tracking() {
+                    state.stop_session(SessionReason::User);
+                } else {
+                    state.start_session(SessionReason::User);
                 }
+                notify_changed(app);
             }
             "quit" => {
                 app.exit(0);
             }
-            _ => {}
+            id => {
+                let project_id = if id == NO_PROJECT_ID {
+                    Some(None)
+                } else {
+                    id.strip_prefix(PROJECT_PREFIX)
+                        .and_then(|p| p.parse().ok())
+                        .map(Some)
+                };
+                if let Some(project_id) = project_id {
+                    app.state::<AppState>().set_current_project(project_id);
+                    notify_changed(app);
+                }
+            }
         })
         .on_tray_icon_event(|tray, event| {
             if let TrayIconEvent::Click {
@@ -65,5 +112,128 @@ pub fn setup_tray<R: Runtime>(app: &AppHandle<R>) -> Result<(), Box<dyn std::err
         })
         .build(app)?;
 
+    refresh(app);
+
+    Ok(())
+}
+
+/// Save, refresh the tray and tell the webview, after a change made from the
+/// tray.
+fn notify_changed<R: Runtime>(app: &AppHandle<R>) {
+    let state = app.state::<AppState>();
+    if let Err(e) = state.save_tracking_state() {
+        log::error!("Failed to save tracking state: {}", e);
+    }
+    refresh(app);
+    let status = state.get_tracking_status();
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.emit("tracking:changed", status);
+    }
+}
+
+fn format_duration(seconds: i64) -> String {
+    let minutes = seconds / 60;
+    if minutes < 60 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    }
+}
+
+/// Update the status lines, Start/Pause item, tooltip and project submenu
+/// from the current tracking state.
+pub fn refresh<R: Runtime>(app: &AppHandle<R>) {
+    let (Some(state), Some(menu)) = (app.try_state::<AppState>(), app.try_state::<TrayMenu<R>>())
+    else {
+        return;
+    };
+    let status = state.get_tracking_status();
+
+    let today = format!("Today: {}", format_duration(state.today_seconds_with_live()));
+    let current = match (&status.current_app, status.is_tracking, status.is_idle) {
+        (_, false, _) => "Paused".to_string(),
+        (_, true, true) => "Idle".to_string(),
+        (Some(app_name), true, false) => format!("Current: {}", app_name),
+        (None, true, false) => "No active app".to_string(),
+    };
+    let toggle = if status.is_tracking {
+        "Pause Tracking"
+    } else {
+        "Start Tracking"
+    };
+
+    let _ = menu.status_item.set_text(&today);
+    let _ = menu.app_item.set_text(&current);
+    let _ = menu.toggle_item.set_text(toggle);
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(format!("Time Tracker\n{}\n{}", today, current)));
+
+        let icon_state = TrayIconState::from_status(status.is_tracking, status.is_idle);
+        let mut shown = menu.icon_state.lock();
+        if *shown != Some(icon_state) {
+            match icon::render(icon_state) {
+                Ok(image) => {
+                    let _ = tray.set_icon(Some(image));
+                    *shown = Some(icon_state);
+                }
+                Err(e) => log::error!("Failed to render tray icon: {}", e),
+            }
+        }
+    }
+
+    if let Err(e) = refresh_projects(app, &state, &menu) {
+        log::error!("Failed to update tray projects: {}", e);
+    }
+}
+
+/// Rebuild the "Switch Project" submenu when projects or the selection change.
+fn refresh_projects<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &AppState,
+    menu: &TrayMenu<R>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let projects: Vec<(i64, String)> = {
+        let conn = state.db.conn();
+        let mut stmt =
+            conn.prepare("SELECT id, name FROM projects WHERE is_active = 1 ORDER BY name")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+    let current = state.get_current_project();
+
+    let mut shown = menu.projects_shown.lock();
+    if shown.as_ref() == Some(&(projects.clone(), current)) {
+        return Ok(());
+    }
+
+    for item in menu.project_menu.items()? {
+        menu.project_menu.remove(&item)?;
+    }
+    menu.project_menu.append(&CheckMenuItem::with_id(
+        app,
+        NO_PROJECT_ID,
+        "No Project",
+        true,
+        current.is_none(),
+        None::<&str>,
+    )?)?;
+    if !projects.is_empty() {
+        menu.project_menu
+            .append(&PredefinedMenuItem::separator(app)?)?;
+    }
+    for (id, name) in &projects {
+        menu.project_menu.append(&CheckMenuItem::with_id(
+            app,
+            format!("{}{}", PROJECT_PREFIX, id),
+            name,
+            true,
+            current == Some(*id),
+            None::<&str>,
+        )?)?;
+    }
+
+    *shown = Some((projects, current));
     Ok(())
 }
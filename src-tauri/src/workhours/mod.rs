@@ -0,0 +1,317 @@
+use crate::db::{DayOff, DayOffKind, WorkBalance, WorkBalanceDay};
+use crate::schedule::TrackingSchedule;
+use chrono::{Datelike, Duration, NaiveDate};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum WorkHoursError {
+    #[error("database error: {0}")]
+    Database(#[from] rusqlite::Error),
+    #[error("invalid date '{0}', expected YYYY-MM-DD")]
+    InvalidDate(String),
+    #[error("end date {1} is before start date {0}")]
+    InvalidRange(String, String),
+}
+
+pub fn parse_date(date: &str) -> Result<NaiveDate, WorkHoursError> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| WorkHoursError::InvalidDate(date.to_string()))
+}
+
+pub fn parse_range(start: &str, end: &str) -> Result<(NaiveDate, NaiveDate), WorkHoursError> {
+    let (start_date, end_date) = (parse_date(start)?, parse_date(end)?);
+    if end_date < start_date {
+        return Err(WorkHoursError::InvalidRange(
+            start.to_string(),
+            end.to_string(),
+        ));
+    }
+    Ok((start_date, end_date))
+}
+
+/// Expected work time per weekday and where the running balance begins.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkHoursSettings {
+    /// Expected minutes for each weekday, Monday first
+    pub day_minutes: Vec<i64>,
+    /// First day counted towards the balance; without it the balance starts
+    /// at each report's start date
+    pub balance_start_date: Option<String>,
+}
+
+impl Default for WorkHoursSettings {
+    fn default() -> Self {
+        Self {
+            day_minutes: vec![480, 480, 480, 480, 480, 0, 0],
+            balance_start_date: None,
+        }
+    }
+}
+
+impl WorkHoursSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.day_minutes.len() != 7 {
+            return Err(format!(
+                "Expected hours need 7 weekdays, got {}",
+                self.day_minutes.len()
+            ));
+        }
+        if self.day_minutes.iter().any(|m| !(0..=24 * 60).contains(m)) {
+            return Err("Expected minutes per day must be between 0 and 1440".to_string());
+        }
+        if let Some(date) = &self.balance_start_date {
+            parse_date(date).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    fn expected_seconds(&self, date: NaiveDate) -> i64 {
+        self.day_minutes
+            .get(date.weekday().num_days_from_monday() as usize)
+            .copied()
+            .unwrap_or(0)
+            * 60
+    }
+}
+
+pub fn get_days_off(
+    conn: &Connection,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> rusqlite::Result<Vec<DayOff>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, date, kind, note FROM days_off WHERE date BETWEEN ? AND ? ORDER BY date",
+    )?;
+    let days = stmt
+        .query_map([start.to_string(), end.to_string()], |row| {
+            Ok(DayOff {
+                id: row.get(0)?,
+                date: row.get(1)?,
+                kind: DayOffKind::parse(&row.get::<_, String>(2)?),
+                note: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(days)
+}
+
+/// Days in the range that would otherwise be expected work days. Booking
+/// vacation over a week skips the weekend and holidays.
+pub fn working_days(
+    settings: &WorkHoursSettings,
+    schedule: &TrackingSchedule,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Vec<NaiveDate> {
+    start
+        .iter_days()
+        .take_while(|d| *d <= end)
+        .filter(|d| settings.expected_seconds(*d) > 0 && !schedule.is_holiday(*d))
+        .collect()
+}
+
+/// Non-idle seconds per local day, by the day each record started on.
+fn actual_by_day(
+    conn: &Connection,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> rusqlite::Result<HashMap<String, i64>> {
+    let mut stmt = conn.prepare(
+        "SELECT date(start_time, 'unixepoch', 'localtime') AS day, SUM(duration_seconds)
+         FROM activity_records
+         WHERE is_idle = 0 AND day BETWEEN ? AND ?
+         GROUP BY day",
+    )?;
+    let rows = stmt
+        .query_map([start.to_string(), end.to_string()], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?
+        .collect::<Result<HashMap<_, _>, _>>()?;
+    Ok(rows)
+}
+
+fn balance_days(
+    conn: &Connection,
+    settings: &WorkHoursSettings,
+    schedule: &TrackingSchedule,
+    start: NaiveDate,
+    end: NaiveDate,
+    opening_balance: i64,
+) -> rusqlite::Result<Vec<WorkBalanceDay>> {
+    let actual = actual_by_day(conn, start, end)?;
+    let days_off: HashMap<String, DayOffKind> = get_days_off(conn, start, end)?
+        .into_iter()
+        .map(|d| (d.date, d.kind))
+        .collect();
+
+    let mut balance = opening_balance;
+    let days = start
+        .iter_days()
+        .take_while(|d| *d <= end)
+        .map(|day| {
+            let date = day.to_string();
+            let is_holiday = schedule.is_holiday(day);
+            let day_off = days_off.get(&date).copied();
+            let expected = if is_holiday || day_off.is_some() {
+                0
+            } else {
+                settings.expected_seconds(day)
+            };
+            let actual_seconds = actual.get(&date).copied().unwrap_or(0);
+            let difference = actual_seconds - expected;
+            balance += difference;
+
+            WorkBalanceDay {
+                date,
+                expected_seconds: expected,
+                actual_seconds,
+                difference_seconds: difference,
+                balance_seconds: balance,
+                day_off,
+                is_holiday,
+            }
+        })
+        .collect();
+    Ok(days)
+}
+
+/// Expected against actual time for each day from `start` to `end`, with a
+/// running balance carried in from `balance_start_date` when it is earlier.
+/// Days before `balance_start_date` are listed with no expected or actual
+/// time.
+pub fn work_balance(
+    conn: &Connection,
+    settings: &WorkHoursSettings,
+    schedule: &TrackingSchedule,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<WorkBalance, WorkHoursError> {
+    let balance_start = settings
+        .balance_start_date
+        .as_deref()
+        .map(parse_date)
+        .transpose()?;
+
+    let opening = match balance_start {
+        Some(from) if from < start => {
+            balance_days(conn, settings, schedule, from, start - Duration::days(1), 0)?
+                .last()
+                .map_or(0, |d| d.balance_seconds)
+        }
+        _ => 0,
+    };
+
+    let mut days = balance_days(conn, settings, schedule, start, end, opening)?;
+    if let Some(from) = balance_start.filter(|from| *from > start) {
+        // Not yet counted: keep the rows but out of the balance
+        let mut balance = 0;
+        for day in &mut days {
+            if parse_date(&day.date)? < from {
+                day.expected_seconds = 0;
+                day.actual_seconds = 0;
+                day.difference_seconds = 0;
+            }
+            balance += day.difference_seconds;
+            day.balance_seconds = balance;
+        }
+    }
+
+    let expected: i64 = days.iter().map(|d| d.expected_seconds).sum();
+    let actual: i64 = days.iter().map(|d| d.actual_seconds).sum();
+    let difference: i64 = days.iter().map(|d| d.difference_seconds).sum();
+    Ok(WorkBalance {
+        start_date: start.to_string(),
+        end_date: end.to_string(),
+        opening_balance_seconds: opening,
+        closing_balance_seconds: days.last().map_or(opening, |d| d.balance_seconds),
+        days,
+        expected_seconds: expected,
+        actual_seconds: actual,
+        difference_seconds: difference,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+    use chrono::{Local, TimeZone};
+
+    fn work(conn: &Connection, date: &str, hours: i64) {
+        let start = Local
+            .from_local_datetime(
+                &NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                    .unwrap()
+                    .and_hms_opt(9, 0, 0)
+                    .unwrap(),
+            )
+            .earliest()
+            .unwrap()
+            .timestamp();
+        conn.execute(
+            "INSERT INTO activity_records (window_title, start_time, end_time)
+             VALUES ('work', ?1, ?1 + ?2)",
+            [start, hours * 3600],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_balance_with_days_off_and_carry_over() {
+        let (db, _temp_dir) = test_db();
+        let conn = db.conn();
+
+        // Week of Monday 2024-03-04: 9h, 7h, sick, holiday, 8h, Saturday 2h
+        work(&conn, "2024-03-04", 9);
+        work(&conn, "2024-03-05", 7);
+        work(&conn, "2024-03-08", 8);
+        work(&conn, "2024-03-09", 2);
+        conn.execute(
+            "INSERT INTO days_off (date, kind) VALUES ('2024-03-06', 'sick')",
+            [],
+        )
+        .unwrap();
+        let schedule = TrackingSchedule {
+            holidays: vec!["2024-03-07".to_string()],
+            ..Default::default()
+        };
+        let mut settings = WorkHoursSettings::default();
+
+        let (start, end) = parse_range("2024-03-04", "2024-03-10").unwrap();
+        let week = work_balance(&conn, &settings, &schedule, start, end).unwrap();
+        assert_eq!(week.expected_seconds, 3 * 8 * 3600);
+        assert_eq!(week.actual_seconds, 26 * 3600);
+        assert_eq!(week.closing_balance_seconds, 2 * 3600);
+        assert_eq!(week.days[2].day_off, Some(DayOffKind::Sick));
+        assert!(week.days[3].is_holiday);
+        assert_eq!(week.days[1].balance_seconds, 0);
+
+        // The next week carries the balance in; Monday is 8h short
+        settings.balance_start_date = Some("2024-03-04".to_string());
+        let (start, end) = parse_range("2024-03-11", "2024-03-11").unwrap();
+        let next = work_balance(&conn, &settings, &schedule, start, end).unwrap();
+        assert_eq!(next.opening_balance_seconds, 2 * 3600);
+        assert_eq!(next.closing_balance_seconds, -6 * 3600);
+
+        let vacation = working_days(
+            &settings,
+            &schedule,
+            parse_date("2024-03-04").unwrap(),
+            parse_date("2024-03-10").unwrap(),
+        );
+        assert_eq!(vacation.len(), 4);
+
+        // Days before the balance starts count for nothing, worked or not
+        settings.balance_start_date = Some("2024-03-08".to_string());
+        let (start, end) = parse_range("2024-03-04", "2024-03-10").unwrap();
+        let clipped = work_balance(&conn, &settings, &schedule, start, end).unwrap();
+        assert_eq!(clipped.days[0].actual_seconds, 0);
+        assert_eq!(clipped.expected_seconds, 8 * 3600);
+        assert_eq!(clipped.actual_seconds, 10 * 3600);
+        assert_eq!(clipped.difference_seconds, 2 * 3600);
+        assert_eq!(clipped.closing_balance_seconds, 2 * 3600);
+    }
+}
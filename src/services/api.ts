@@ -8,44 +8,354 @@ import type {
   CategoryStats,
   AppSettings,
   ActivityRecord,
+  GroupBy,
+  ImportReport,
+  CsvColumnMapping,
+  CsvImportPreview,
+  CsvImportReport,
+  AuditEntry,
+  ConflictStrategy,
+  PresetPackInfo,
+  PresetImportReport,
+  Tag,
+  TagRule,
+  RecordFilter,
+  ActivitySearchMatch,
+  Project,
+  Client,
+  ClientInput,
+  BillingSummary,
+  Invoice,
+  InvoiceFormat,
+  InvoiceTemplate,
+  InvoiceTemplateInput,
+  Timesheet,
+  Goal,
+  GoalInput,
+  GoalProgress,
+  IdlePeriod,
+  NotificationSettings,
+  TimerSession,
+  TimerSettings,
+  TimerStatus,
+  FocusSessionReport,
+  TrackingSchedule,
+  ScheduleStatus,
+  DayOff,
+  DayOffKind,
+  WorkBalance,
+  WorkHoursSettings,
+  TrackingSession,
 } from "../types";
 
 // Tracking commands
 export const startTracking = () => invoke<void>("start_tracking");
 export const stopTracking = () => invoke<void>("stop_tracking");
 export const getTrackingStatus = () => invoke<TrackingStatus>("get_tracking_status");
+export const getSessions = (startDate: string, endDate: string) =>
+  invoke<TrackingSession[]>("get_sessions", { startDate, endDate });
 export const getCurrentWindow = () => invoke<WindowEvent | null>("get_current_window");
 export const getIdleTime = () => invoke<number>("get_idle_time");
+export const setCurrentProject = (projectId: number | null) =>
+  invoke<void>("set_current_project", { projectId });
+
+// Schedule commands
+export const getSchedule = () => invoke<TrackingSchedule>("get_schedule");
+export const setSchedule = (schedule: TrackingSchedule) =>
+  invoke<ScheduleStatus>("set_schedule", { schedule });
+export const getScheduleStatus = () => invoke<ScheduleStatus>("get_schedule_status");
+export const trackAnywayUntil = (until: number) =>
+  invoke<ScheduleStatus>("track_anyway_until", { until });
+export const clearScheduleOverride = () => invoke<ScheduleStatus>("clear_schedule_override");
 
 // Category commands
 export const getCategories = () => invoke<Category[]>("get_categories");
-export const createCategory = (name: string, color: string, isProductive: boolean) =>
-  invoke<Category>("create_category", { name, color, isProductive });
+export const createCategory = (
+  name: string,
+  color: string,
+  isProductive?: boolean,
+  parentId?: number
+) => invoke<Category>("create_category", { name, color, isProductive, parentId });
 export const updateCategory = (
   id: number,
   name?: string,
   color?: string,
-  isProductive?: boolean
-) => invoke<Category>("update_category", { id, name, color, isProductive });
-export const deleteCategory = (id: number) => invoke<void>("delete_category", { id });
+  isProductive?: boolean,
+  parent?: { parentId?: number; clearParent?: boolean; inheritProductive?: boolean }
+) => invoke<Category>("update_category", { id, name, color, isProductive, ...parent });
+export const deleteCategory = (id: number, reassignTo?: number) =>
+  invoke<number>("delete_category", { id, reassignTo });
 export const assignAppToCategory = (appId: number, categoryId: number | null) =>
   invoke<void>("assign_app_to_category", { appId, categoryId });
 
+// Preset pack commands
+export const listBuiltinPacks = () => invoke<PresetPackInfo[]>("list_builtin_packs");
+export const exportPresetPack = (name: string, description: string | null, path: string) =>
+  invoke<void>("export_preset_pack", { name, description, path });
+export const importPresetPack = (path: string, strategy: ConflictStrategy) =>
+  invoke<PresetImportReport>("import_preset_pack", { path, strategy });
+export const importBuiltinPack = (id: string, strategy: ConflictStrategy) =>
+  invoke<PresetImportReport>("import_builtin_pack", { id, strategy });
+
+// Activity record commands
+export const createManualEntry = (
+  startTime: number,
+  endTime: number,
+  title: string,
+  categoryId?: number,
+  projectId?: number,
+  notes?: string
+) =>
+  invoke<ActivityRecord>("create_manual_entry", {
+    startTime,
+    endTime,
+    title,
+    categoryId,
+    projectId,
+    notes,
+  });
+export const updateActivityRecord = (
+  id: number,
+  changes: {
+    startTime?: number;
+    endTime?: number;
+    title?: string;
+    /** null clears the category */
+    categoryId?: number | null;
+    /** null clears the project */
+    projectId?: number | null;
+    notes?: string;
+  }
+) =>
+  invoke<ActivityRecord>("update_activity_record", {
+    id,
+    ...changes,
+    clearCategory: changes.categoryId === null,
+    clearProject: changes.projectId === null,
+  });
+export const setActivityNote = (id: number, notes: string | null) =>
+  invoke<ActivityRecord>("set_activity_note", { id, notes });
+export const addNoteToRange = (startTime: number, endTime: number, note: string) =>
+  invoke<number>("add_note_to_range", { startTime, endTime, note });
+export const searchNotes = (
+  query: string,
+  startDate?: string,
+  endDate?: string,
+  limit?: number
+) => invoke<ActivityRecord[]>("search_notes", { query, startDate, endDate, limit });
+export const setRecordsBillable = (recordIds: number[], isBillable: boolean | null) =>
+  invoke<number>("set_records_billable", { recordIds, isBillable });
+export const resolveIdlePeriod = (id: number, keep: boolean) =>
+  invoke<IdlePeriod>("resolve_idle_period", { id, keep });
+export const splitActivityRecord = (id: number, at: number) =>
+  invoke<ActivityRecord[]>("split_activity_record", { id, at });
+export const mergeActivityRecords = (firstId: number, secondId: number) =>
+  invoke<ActivityRecord>("merge_activity_records", { firstId, secondId });
+export const deleteActivityRecord = (id: number) =>
+  invoke<void>("delete_activity_record", { id });
+
 // Report commands
 export const getDailySummary = (date: string) =>
   invoke<DailySummary>("get_daily_summary", { date });
 export const getTodaySummary = () => invoke<DailySummary>("get_today_summary");
 export const getActivityRange = (startDate: string, endDate: string) =>
   invoke<ActivityRecord[]>("get_activity_range", { startDate, endDate });
-export const getAppUsage = (startDate: string, endDate: string) =>
-  invoke<AppUsageStats[]>("get_app_usage", { startDate, endDate });
-export const getCategoryBreakdown = (startDate: string, endDate: string) =>
-  invoke<CategoryStats[]>("get_category_breakdown", { startDate, endDate });
+export const getAppUsage = (
+  startDate: string,
+  endDate: string,
+  includeTags?: number[],
+  excludeTags?: number[]
+) => invoke<AppUsageStats[]>("get_app_usage", { startDate, endDate, includeTags, excludeTags });
+export const getCategoryBreakdown = (
+  startDate: string,
+  endDate: string,
+  asTree?: boolean,
+  includeTags?: number[],
+  excludeTags?: number[]
+) =>
+  invoke<CategoryStats[]>("get_category_breakdown", {
+    startDate,
+    endDate,
+    asTree,
+    includeTags,
+    excludeTags,
+  });
+
+// Export commands
+export const exportIcs = (
+  startDate: string,
+  endDate: string,
+  groupBy: GroupBy,
+  path: string
+) => invoke<number>("export_ics", { startDate, endDate, groupBy, path });
+
+// Import commands
+export const importActivityWatch = (path: string) =>
+  invoke<ImportReport>("import_activitywatch", { path });
+export const previewCsvImport = (path: string) =>
+  invoke<CsvImportPreview>("preview_csv_import", { path });
+export const importCsvTimeEntries = (
+  path: string,
+  mapping: CsvColumnMapping | null,
+  dryRun: boolean
+) => invoke<CsvImportReport>("import_csv_time_entries", { path, mapping, dryRun });
+
+// Project and client commands
+export const getProjects = () => invoke<Project[]>("get_projects");
+export const createProject = (
+  name: string,
+  color?: string,
+  clientId?: number,
+  categoryId?: number
+) => invoke<Project>("create_project", { name, color, clientId, categoryId });
+export const setProjectBilling = (
+  projectId: number,
+  clientId: number | null,
+  hourlyRateCents: number | null,
+  roundingMinutes: number | null,
+  isBillable: boolean
+) =>
+  invoke<Project>("set_project_billing", {
+    projectId,
+    clientId,
+    hourlyRateCents,
+    roundingMinutes,
+    isBillable,
+  });
+export const getClients = () => invoke<Client[]>("get_clients");
+export const createClient = (client: ClientInput) => invoke<Client>("create_client", { client });
+export const updateClient = (id: number, client: ClientInput) =>
+  invoke<Client>("update_client", { id, client });
+export const deleteClient = (id: number) => invoke<void>("delete_client", { id });
+export const getBillingSummary = (clientId: number, startDate: string, endDate: string) =>
+  invoke<BillingSummary>("get_billing_summary", { clientId, startDate, endDate });
+
+// Invoice commands
+export const getInvoiceTemplates = () => invoke<InvoiceTemplate[]>("get_invoice_templates");
+export const createInvoiceTemplate = (template: InvoiceTemplateInput) =>
+  invoke<InvoiceTemplate>("create_invoice_template", { template });
+export const updateInvoiceTemplate = (id: number, template: InvoiceTemplateInput) =>
+  invoke<InvoiceTemplate>("update_invoice_template", { id, template });
+export const deleteInvoiceTemplate = (id: number) =>
+  invoke<void>("delete_invoice_template", { id });
+export const createInvoice = (
+  clientId: number,
+  projectIds: number[],
+  startDate: string,
+  endDate: string,
+  templateId?: number
+) =>
+  invoke<Invoice>("create_invoice", { clientId, projectIds, startDate, endDate, templateId });
+export const getInvoices = () => invoke<Invoice[]>("get_invoices");
+export const exportInvoice = (id: number, format: InvoiceFormat, path: string) =>
+  invoke<void>("export_invoice", { id, format, path });
+
+// Timesheet commands
+export const getTimesheet = (weekStart: string) =>
+  invoke<Timesheet>("get_timesheet", { weekStart });
+export const submitTimesheet = (weekStart: string) =>
+  invoke<Timesheet>("submit_timesheet", { weekStart });
+export const unlockTimesheet = (weekStart: string, reason: string) =>
+  invoke<Timesheet>("unlock_timesheet", { weekStart, reason });
+
+// Work hours commands
+export const getWorkBalance = (startDate: string, endDate: string) =>
+  invoke<WorkBalance>("get_work_balance", { startDate, endDate });
+export const getWorkHoursSettings = () => invoke<WorkHoursSettings>("get_work_hours_settings");
+export const updateWorkHoursSettings = (settings: WorkHoursSettings) =>
+  invoke<WorkHoursSettings>("update_work_hours_settings", { settings });
+export const getDaysOff = (startDate: string, endDate: string) =>
+  invoke<DayOff[]>("get_days_off", { startDate, endDate });
+export const addDaysOff = (
+  startDate: string,
+  endDate: string,
+  kind: DayOffKind,
+  note?: string
+) => invoke<DayOff[]>("add_days_off", { startDate, endDate, kind, note });
+export const removeDayOff = (date: string) => invoke<void>("remove_day_off", { date });
+
+// Goal commands
+export const getGoals = () => invoke<Goal[]>("get_goals");
+export const createGoal = (goal: GoalInput) => invoke<Goal>("create_goal", { goal });
+export const updateGoal = (id: number, goal: GoalInput) =>
+  invoke<Goal>("update_goal", { id, goal });
+export const deleteGoal = (id: number) => invoke<void>("delete_goal", { id });
+export const getGoalProgress = () => invoke<GoalProgress[]>("get_goal_progress");
+
+// Focus timer and session commands
+export const getTimerStatus = () => invoke<TimerStatus>("get_timer_status");
+export const startTimer = () => invoke<TimerStatus>("start_timer");
+export const pauseTimer = () => invoke<TimerStatus>("pause_timer");
+export const resumeTimer = () => invoke<TimerStatus>("resume_timer");
+export const skipTimerPhase = () => invoke<TimerStatus>("skip_timer_phase");
+export const stopTimer = () => invoke<TimerStatus>("stop_timer");
+export const getTimerSessions = (startDate: string, endDate: string) =>
+  invoke<TimerSession[]>("get_timer_sessions", { startDate, endDate });
+export const getTimerSettings = () => invoke<TimerSettings>("get_timer_settings");
+export const updateTimerSettings = (settings: TimerSettings) =>
+  invoke<void>("update_timer_settings", { settings });
+export const startFocusSession = () => invoke<FocusSessionReport>("start_focus_session");
+export const stopFocusSession = () => invoke<FocusSessionReport>("stop_focus_session");
+export const getFocusSession = () => invoke<FocusSessionReport | null>("get_focus_session");
+export const getFocusReport = (startDate: string, endDate: string) =>
+  invoke<FocusSessionReport[]>("get_focus_report", { startDate, endDate });
+
+// Tag commands
+export const getTags = () => invoke<Tag[]>("get_tags");
+export const createTag = (name: string, color: string) =>
+  invoke<Tag>("create_tag", { name, color });
+export const updateTag = (id: number, name?: string, color?: string) =>
+  invoke<Tag>("update_tag", { id, name, color });
+export const deleteTag = (id: number) => invoke<void>("delete_tag", { id });
+export const getActivityTags = (activityId: number) =>
+  invoke<Tag[]>("get_activity_tags", { activityId });
+export const tagActivityRecords = (tagId: number, recordIds: number[]) =>
+  invoke<number>("tag_activity_records", { tagId, recordIds });
+export const untagActivityRecords = (tagId: number, recordIds: number[]) =>
+  invoke<number>("untag_activity_records", { tagId, recordIds });
+export const tagActivityRange = (
+  tagId: number,
+  startTime: number,
+  endTime: number,
+  filter?: RecordFilter
+) => invoke<number>("tag_activity_range", { tagId, startTime, endTime, filter });
+export const tagProject = (projectId: number, tagId: number) =>
+  invoke<void>("tag_project", { projectId, tagId });
+export const untagProject = (projectId: number, tagId: number) =>
+  invoke<void>("untag_project", { projectId, tagId });
+export const getTagRules = () => invoke<TagRule[]>("get_tag_rules");
+export const createTagRule = (ruleType: string, pattern: string, tagId: number) =>
+  invoke<TagRule>("create_tag_rule", { ruleType, pattern, tagId });
+export const deleteTagRule = (id: number) => invoke<void>("delete_tag_rule", { id });
+export const applyTagRules = (startDate: string, endDate: string) =>
+  invoke<number>("apply_tag_rules", { startDate, endDate });
+
+// Search commands
+export const searchActivity = (
+  query: string,
+  startDate?: string,
+  endDate?: string,
+  limit?: number
+) =>
+  invoke<ActivitySearchMatch[]>("search_activity", { query, startDate, endDate, limit });
+
+// Audit commands
+export const getAuditLog = (entityType?: string, limit?: number) =>
+  invoke<AuditEntry[]>("get_audit_log", { entityType, limit });
+export const undoLastChange = () => invoke<AuditEntry>("undo_last_change");
 
 // Settings commands
 export const getSettings = () => invoke<AppSettings>("get_settings");
 export const updateSettings = (settings: AppSettings) =>
   invoke<void>("update_settings", { settings });
+export const getSetting = <K extends keyof AppSettings>(key: K) =>
+  invoke<AppSettings[K]>("get_setting", { key });
+export const setSetting = <K extends keyof AppSettings>(key: K, value: AppSettings[K]) =>
+  invoke<AppSettings[K]>("set_setting", { key, value });
 export const getIdleThreshold = () => invoke<number>("get_idle_threshold");
 export const setIdleThreshold = (seconds: number) =>
   invoke<void>("set_idle_threshold", { seconds });
+export const getNotificationSettings = () =>
+  invoke<NotificationSettings>("get_notification_settings");
+export const updateNotificationSettings = (settings: NotificationSettings) =>
+  invoke<void>("update_notification_settings", { settings });
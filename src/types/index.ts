@@ -6,6 +6,9 @@ export interface Category {
   is_productive: boolean;
   created_at: number;
   updated_at: number;
+  is_protected: boolean;
+  parent_id: number | null;
+  inherit_productive: boolean;
 }
 
 export interface Project {
@@ -15,6 +18,10 @@ export interface Project {
   category_id: number | null;
   color: string;
   is_active: boolean;
+  client_id: number | null;
+  hourly_rate_cents: number | null;
+  rounding_minutes: number | null;
+  is_billable: boolean;
   created_at: number;
   updated_at: number;
 }
@@ -41,6 +48,9 @@ export interface ActivityRecord {
   category_id: number | null;
   project_id: number | null;
   is_idle: boolean;
+  source: string;
+  notes: string | null;
+  is_billable: boolean | null;
 }
 
 export interface WindowEvent {
@@ -58,6 +68,7 @@ export interface TrackingStatus {
   current_window: string | null;
   today_total_seconds: number;
   session_start_time: number | null;
+  current_project_id: number | null;
 }
 
 export interface DailySummary {
@@ -84,6 +95,9 @@ export interface CategoryStats {
   color: string;
   total_seconds: number;
   is_productive: boolean;
+  parent_id: number | null;
+  own_seconds: number;
+  children: CategoryStats[];
 }
 
 export interface AppSettings {
@@ -92,3 +106,411 @@ export interface AppSettings {
   polling_interval_ms: number;
   theme: string;
 }
+
+export type GroupBy = "project" | "category";
+
+export interface TimelineBlock {
+  group_id: number | null;
+  label: string;
+  color: string;
+  start_time: number;
+  end_time: number;
+  active_seconds: number;
+  record_count: number;
+  notes: string[];
+}
+
+export interface ImportReport {
+  records_imported: number;
+  records_skipped: number;
+  idle_periods_imported: number;
+  idle_periods_skipped: number;
+  applications_created: number;
+}
+
+export interface CsvColumnMapping {
+  project: number | null;
+  client: number | null;
+  description: number | null;
+  tags: number | null;
+  billable?: number | null;
+  start_date: number | null;
+  start_time: number | null;
+  end_date: number | null;
+  end_time: number | null;
+}
+
+export interface CsvImportPreview {
+  format: string;
+  headers: string[];
+  mapping: CsvColumnMapping;
+  sample_rows: string[][];
+}
+
+export interface CsvConflict {
+  row: number;
+  description: string;
+  start_time: number;
+  end_time: number;
+  conflicting_seconds: number;
+}
+
+export interface CsvImportReport {
+  format: string;
+  dry_run: boolean;
+  entries_imported: number;
+  entries_skipped: number;
+  projects_created: number;
+  clients_created: number;
+  conflicts: CsvConflict[];
+  errors: string[];
+}
+
+export interface AuditEntry {
+  id: number;
+  action: string;
+  entity_type: string;
+  entity_id: number | null;
+  before: Record<string, Record<string, unknown>[]>;
+  after: Record<string, Record<string, unknown>[]>;
+  created_at: number;
+  undone_at: number | null;
+  reason: string | null;
+}
+
+export type ConflictStrategy = "skip" | "overwrite" | "rename";
+
+export interface PresetPackInfo {
+  id: string;
+  name: string;
+  description: string | null;
+  category_count: number;
+  rule_count: number;
+  application_count: number;
+}
+
+export interface PresetImportReport {
+  categories_created: number;
+  categories_updated: number;
+  categories_skipped: number;
+  rules_created: number;
+  rules_updated: number;
+  rules_skipped: number;
+  applications_assigned: number;
+  applications_skipped: number;
+}
+
+export interface Tag {
+  id: number;
+  name: string;
+  color: string;
+}
+
+export interface TagRule {
+  id: number;
+  rule_type: string;
+  pattern: string;
+  tag_id: number;
+  is_active: boolean;
+}
+
+export interface RecordFilter {
+  application_id?: number | null;
+  category_id?: number | null;
+  project_id?: number | null;
+  title_contains?: string | null;
+}
+
+export interface ActivitySearchMatch {
+  window_title: string;
+  app_name: string | null;
+  /** HTML-escaped excerpt with matched terms wrapped in `<mark>` */
+  snippet: string;
+  record_ids: number[];
+  total_seconds: number;
+  first_seen: number;
+  last_seen: number;
+}
+
+export type RoundingScope = "entry" | "day";
+
+export interface ClientInput {
+  name: string;
+  email: string | null;
+  address: string | null;
+  currency: string;
+  hourly_rate_cents: number | null;
+  rounding_minutes: number;
+  rounding_scope: RoundingScope;
+}
+
+export interface Client extends ClientInput {
+  id: number;
+  created_at: number;
+}
+
+export interface BillingLineItem {
+  project_id: number;
+  project_name: string;
+  entry_count: number;
+  tracked_seconds: number;
+  billed_seconds: number;
+  hourly_rate_cents: number;
+  amount_cents: number;
+}
+
+export interface BillingSummary {
+  client: Client;
+  start_date: string;
+  end_date: string;
+  currency: string;
+  line_items: BillingLineItem[];
+  tracked_seconds: number;
+  billed_seconds: number;
+  non_billable_seconds: number;
+  total_cents: number;
+}
+
+export interface InvoiceTemplateInput {
+  name: string;
+  company_name: string;
+  /** Supports placeholders such as `{{client}}`, `{{number}}` and `{{due_date}}` */
+  header_text: string;
+  footer_text: string;
+  accent_color: string;
+  payment_terms_days: number;
+}
+
+export interface InvoiceTemplate extends InvoiceTemplateInput {
+  id: number;
+}
+
+export interface InvoiceLine {
+  id: number;
+  project_id: number | null;
+  description: string;
+  billed_seconds: number;
+  hourly_rate_cents: number;
+  amount_cents: number;
+}
+
+export interface Invoice {
+  id: number;
+  number: string;
+  client_id: number | null;
+  client_name: string;
+  client_address: string | null;
+  template_id: number | null;
+  start_date: string;
+  end_date: string;
+  currency: string;
+  issue_date: string;
+  due_date: string;
+  total_cents: number;
+  lines: InvoiceLine[];
+  created_at: number;
+}
+
+export type InvoiceFormat = "html" | "pdf";
+
+export type TimesheetStatus = "open" | "submitted" | "unlocked";
+
+export interface TimesheetRow {
+  project_id: number | null;
+  project_name: string;
+  color: string;
+  day_seconds: number[];
+  total_seconds: number;
+}
+
+export interface Timesheet {
+  week_start: string;
+  days: string[];
+  rows: TimesheetRow[];
+  day_totals: number[];
+  total_seconds: number;
+  status: TimesheetStatus;
+  submitted_at: number | null;
+  unlocked_at: number | null;
+  unlock_reason: string | null;
+}
+
+export type GoalTarget = "category" | "application";
+export type GoalKind = "min" | "max";
+export type GoalPeriod = "day" | "week";
+
+export interface GoalInput {
+  name: string;
+  target_type: GoalTarget;
+  target_id: number;
+  kind: GoalKind;
+  period: GoalPeriod;
+  target_seconds: number;
+  /** Weekdays a daily goal applies to, Monday = 1 ... Sunday = 64 */
+  active_days: number;
+  is_active: boolean;
+}
+
+export interface Goal extends GoalInput {
+  id: number;
+  created_at: number;
+}
+
+export type GoalStatus = "in_progress" | "met" | "exceeded";
+
+export interface GoalProgress {
+  goal: Goal;
+  period_start: string;
+  period_end: string;
+  actual_seconds: number;
+  target_seconds: number;
+  percent: number;
+  status: GoalStatus;
+}
+
+export interface IdlePeriod {
+  id: number;
+  start_time: number;
+  end_time: number | null;
+  duration_seconds: number | null;
+  disposition: string | null;
+  notes: string | null;
+}
+
+export interface NotificationSettings {
+  break_reminder_enabled: boolean;
+  break_reminder_minutes: number;
+  daily_limit_enabled: boolean;
+  daily_limit_minutes: number;
+  idle_prompt_enabled: boolean;
+  idle_prompt_minutes: number;
+  /** Local "HH:MM"; empty strings disable quiet hours */
+  quiet_hours_start: string;
+  quiet_hours_end: string;
+}
+
+export type TimerPhase = "work" | "short_break" | "long_break";
+export type TimerState = "stopped" | "running" | "paused";
+
+export interface TimerStatus {
+  state: TimerState;
+  phase: TimerPhase;
+  phase_started_at: number | null;
+  duration_seconds: number;
+  remaining_seconds: number;
+  completed_work_sessions: number;
+}
+
+export interface TimerSettings {
+  work_minutes: number;
+  short_break_minutes: number;
+  long_break_minutes: number;
+  sessions_before_long_break: number;
+  auto_pause_tracking: boolean;
+}
+
+export interface TimerSession {
+  id: number;
+  phase: TimerPhase;
+  started_at: number;
+  ended_at: number;
+  planned_seconds: number;
+  actual_seconds: number;
+  completed: boolean;
+}
+
+export interface FocusDistraction {
+  id: number;
+  session_id: number;
+  application_id: number | null;
+  app_name: string;
+  window_title: string;
+  category_id: number | null;
+  category_name: string | null;
+  started_at: number;
+  ended_at: number | null;
+}
+
+export interface FocusSessionReport {
+  id: number;
+  started_at: number;
+  ended_at: number | null;
+  duration_seconds: number;
+  distraction_count: number;
+  distraction_seconds: number;
+  focused_seconds: number;
+  distractions: FocusDistraction[];
+}
+
+export interface TimeRange {
+  /** Local "HH:MM", inclusive */
+  start: string;
+  /** Local "HH:MM", exclusive */
+  end: string;
+}
+
+export interface TrackingSchedule {
+  enabled: boolean;
+  /** Ranges for each weekday, Monday first */
+  days: TimeRange[][];
+  /** "YYYY-MM-DD" dates with no tracking */
+  holidays: string[];
+}
+
+export interface ScheduleStatus {
+  enabled: boolean;
+  in_schedule: boolean;
+  override_until: number | null;
+  tracking_allowed: boolean;
+}
+
+export type DayOffKind = "vacation" | "sick";
+
+export interface DayOff {
+  id: number;
+  date: string;
+  kind: DayOffKind;
+  note: string | null;
+}
+
+export interface WorkHoursSettings {
+  /** Expected minutes for each weekday, Monday first */
+  day_minutes: number[];
+  /** First "YYYY-MM-DD" counted towards the balance */
+  balance_start_date: string | null;
+}
+
+export interface WorkBalanceDay {
+  date: string;
+  expected_seconds: number;
+  actual_seconds: number;
+  difference_seconds: number;
+  balance_seconds: number;
+  day_off: DayOffKind | null;
+  is_holiday: boolean;
+}
+
+export interface WorkBalance {
+  start_date: string;
+  end_date: string;
+  opening_balance_seconds: number;
+  days: WorkBalanceDay[];
+  expected_seconds: number;
+  actual_seconds: number;
+  difference_seconds: number;
+  closing_balance_seconds: number;
+}
+
+/** For a start, what ended the gap before the session */
+export type SessionReason = "user" | "schedule" | "break" | "idle" | "suspend" | "quit" | "startup";
+
+export interface TrackingSession {
+  id: number;
+  started_at: number;
+  ended_at: number | null;
+  start_reason: SessionReason;
+  end_reason: SessionReason | null;
+  duration_seconds: number;
+  active_seconds: number;
+  record_count: number;
+}